@@ -3,9 +3,10 @@
 //! This module provides a high-level async interface for making requests to the dictionary and
 //! parsing the results into structured data.
 
+use std::pin::Pin;
 use std::time::Duration;
 
-use reqwest::{ClientBuilder, redirect::Policy};
+use reqwest::{ClientBuilder, RequestBuilder, Response, redirect::Policy};
 
 use crate::{DictionaryDocument, Error};
 
@@ -16,6 +17,13 @@ const QUERY_PATH: &str = "/ddo/query";
 /// The name of the query parameter used to specify the word to look up.
 const QUERY_WORD_PARAM: &str = "q";
 
+/// A function that sends a request and returns the response.
+///
+/// This lets requests be sent through something other than [`RequestBuilder::send`], e.g. a
+/// shared HTTP layer with caching and retries.
+pub type SendRequest =
+    fn(RequestBuilder) -> Pin<Box<dyn Future<Output = reqwest::Result<Response>> + Send>>;
+
 /// An asynchronous client for the Danish Dictionary (Den Danske Ordbog).
 ///
 /// This client handles the construction of HTTP requests, sending them to the dictionary service,
@@ -26,6 +34,8 @@ pub struct Client {
     base_url: String,
     /// The underlying [`reqwest::Client`] used for making HTTP requests.
     client: reqwest::Client,
+    /// The function that sends requests.
+    send: SendRequest,
 }
 
 impl Client {
@@ -76,7 +86,21 @@ impl Client {
     pub fn with_client(client: reqwest::Client) -> Client {
         let base_url = String::from(BASE_URL);
 
-        Client { base_url, client }
+        Client {
+            base_url,
+            client,
+            send: |request| Box::pin(request.send()),
+        }
+    }
+
+    /// Sends requests with `send` instead of [`RequestBuilder::send`].
+    ///
+    /// # Arguments
+    ///
+    /// * `send` - The function that sends requests.
+    #[must_use]
+    pub fn with_send(self, send: SendRequest) -> Client {
+        Client { send, ..self }
     }
 
     /// Queries the dictionary for a specific word and returns the parsed result.
@@ -95,10 +119,11 @@ impl Client {
     ///   a timeout, or if the server returns a non-successful status code (e.g., 404, 500).
     /// - [`Error::MissingElement`]: If the response body is received but the HTML
     ///   is malformed or does not match the expected structure, preventing parsing.
+    #[cfg_attr(feature = "log", tracing::instrument(skip(self)))]
     pub async fn query(&self, word: &str) -> Result<DictionaryDocument, Error> {
         let url = format!("{base_url}{QUERY_PATH}", base_url = self.base_url);
         let request = self.client.get(url).query(&[(QUERY_WORD_PARAM, word)]);
-        let response = request.send().await.map_err(Error::Request)?;
+        let response = (self.send)(request).await.map_err(Error::Request)?;
 
         match response.error_for_status() {
             Ok(response) => {
@@ -1,8 +1,9 @@
+use std::pin::Pin;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, LOCATION};
-use reqwest::{StatusCode, redirect::Policy};
+use reqwest::{RequestBuilder, Response, StatusCode, redirect::Policy};
 use secrecy::{ExposeSecret, SecretString};
 use serde::Deserialize;
 use tokio::sync::RwLock;
@@ -12,6 +13,13 @@ use url::Url;
 use crate::{BASE_URL, HTTP_TIMEOUT, OAUTH_BASE_URL, USER_AGENT};
 use crate::{Error, Item, Link, Submission, Subreddit};
 
+/// A function that sends a request and returns the response.
+///
+/// This lets requests be sent through something other than [`RequestBuilder::send`], e.g. a
+/// shared HTTP layer with caching and retries.
+pub type SendRequest =
+    fn(RequestBuilder) -> Pin<Box<dyn Future<Output = reqwest::Result<Response>> + Send>>;
+
 struct TokenCache {
     access_token: String,
     expires_at: Instant,
@@ -35,6 +43,8 @@ pub struct Client {
     client_secret: SecretString,
     /// Current authentication token state.
     token_state: Arc<RwLock<Option<TokenCache>>>,
+    /// The function that sends requests.
+    send: SendRequest,
 }
 
 impl TokenCache {
@@ -76,9 +86,16 @@ impl Client {
             client_id,
             client_secret,
             token_state,
+            send: |request| Box::pin(request.send()),
         }
     }
 
+    /// Sends requests with `send` instead of [`RequestBuilder::send`].
+    #[must_use]
+    pub fn with_send(self, send: SendRequest) -> Client {
+        Client { send, ..self }
+    }
+
     async fn get_valid_token(&self) -> Result<String, Error> {
         {
             let read = self.token_state.read().await;
@@ -118,7 +135,9 @@ impl Client {
             .post("https://www.reddit.com/api/v1/access_token")
             .basic_auth(&self.client_id, Some(self.client_secret.expose_secret()))
             .body("grant_type=client_credentials");
-        let response = request.send().await.map_err(Error::RequestAuthToken)?;
+        let response = (self.send)(request)
+            .await
+            .map_err(Error::RequestAuthToken)?;
         let access_token = response
             .json::<AccessTokenResponse>()
             .await
@@ -139,7 +158,7 @@ impl Client {
             .get(format!("{OAUTH_BASE_URL}/by_id/t3_{article}"))
             .header(AUTHORIZATION, format!("bearer {access_token}"))
             .header(CONTENT_TYPE, "application/json");
-        let response = request.send().await.map_err(Error::Reqwest)?;
+        let response = (self.send)(request).await.map_err(Error::Reqwest)?;
 
         match response.error_for_status() {
             Ok(response) => {
@@ -203,7 +222,7 @@ impl Client {
             .header(CONTENT_TYPE, "application/json");
 
         debug!("requesting subreddit details");
-        let response = request.send().await.map_err(Error::Reqwest)?;
+        let response = (self.send)(request).await.map_err(Error::Reqwest)?;
 
         match response.error_for_status() {
             Ok(response) => {
@@ -245,7 +264,7 @@ impl Client {
     async fn get_redirect_location(&self, url: &str) -> Result<Url, Error> {
         debug!("fetching redirect location for url {url}");
         let request = self.client.head(url);
-        let response = request.send().await.map_err(Error::Reqwest)?;
+        let response = (self.send)(request).await.map_err(Error::Reqwest)?;
         let location = response
             .headers()
             .get(LOCATION)
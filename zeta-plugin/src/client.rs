@@ -0,0 +1,135 @@
+use std::fmt;
+
+use irc::error::Result;
+use irc::proto::{Command, Message};
+
+/// A function that is called with every message before it's sent.
+pub type OnSend = fn(&Message);
+
+/// The IRC client handed to plugins.
+///
+/// This wraps the client of the `irc` crate, so every message that is sent to the server,
+/// whether by a plugin or by the bot itself, passes through [`send`](Client::send) and can be
+/// observed at the time it's sent.
+pub struct Client {
+    /// The underlying IRC client.
+    inner: irc::client::Client,
+    /// Called with every message before it's sent.
+    on_send: OnSend,
+}
+
+/// A handle for sending messages that can be moved into other tasks.
+#[derive(Clone)]
+pub struct Sender {
+    /// The underlying sender.
+    inner: irc::client::Sender,
+    /// Called with every message before it's sent.
+    on_send: OnSend,
+}
+
+impl Client {
+    /// Wraps `inner`, calling `on_send` with every message before it's sent.
+    #[must_use]
+    pub fn new(inner: irc::client::Client, on_send: OnSend) -> Client {
+        Client { inner, on_send }
+    }
+
+    /// Sends a message to the server.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message could not be queued for sending.
+    pub fn send<M: Into<Message>>(&self, message: M) -> Result<()> {
+        let message = message.into();
+
+        (self.on_send)(&message);
+        self.inner.send(message)
+    }
+
+    /// Sends a `PRIVMSG` to `target`, with one message per line of `message`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message could not be queued for sending.
+    pub fn send_privmsg<S, T>(&self, target: S, message: T) -> Result<()>
+    where
+        S: fmt::Display,
+        T: fmt::Display,
+    {
+        let target = target.to_string();
+
+        for line in message.to_string().split("\r\n") {
+            self.send(Command::PRIVMSG(target.clone(), line.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends a `NOTICE` to `target`, with one message per line of `message`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message could not be queued for sending.
+    pub fn send_notice<S, T>(&self, target: S, message: T) -> Result<()>
+    where
+        S: fmt::Display,
+        T: fmt::Display,
+    {
+        let target = target.to_string();
+
+        for line in message.to_string().split("\r\n") {
+            self.send(Command::NOTICE(target.clone(), line.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Joins the comma-separated channels in `chanlist`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message could not be queued for sending.
+    pub fn send_join<S: fmt::Display>(&self, chanlist: S) -> Result<()> {
+        self.send(Command::JOIN(chanlist.to_string(), None, None))
+    }
+
+    /// Joins the comma-separated channels in `chanlist` with the corresponding `keys`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message could not be queued for sending.
+    pub fn send_join_with_keys<S, T>(&self, chanlist: S, keys: T) -> Result<()>
+    where
+        S: fmt::Display,
+        T: fmt::Display,
+    {
+        self.send(Command::JOIN(
+            chanlist.to_string(),
+            Some(keys.to_string()),
+            None,
+        ))
+    }
+
+    /// Returns a handle for sending messages from other tasks.
+    #[must_use]
+    pub fn sender(&self) -> Sender {
+        Sender {
+            inner: self.inner.sender(),
+            on_send: self.on_send,
+        }
+    }
+}
+
+impl Sender {
+    /// Sends a message to the server.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message could not be queued for sending.
+    pub fn send<M: Into<Message>>(&self, message: M) -> Result<()> {
+        let message = message.into();
+
+        (self.on_send)(&message);
+        self.inner.send(message)
+    }
+}
@@ -15,6 +15,14 @@ pub enum Error {
     Irc(#[from] irc::error::Error),
     #[error("Plugin error: {0}")]
     Plugin(BoxError),
+    #[error("environment variable `{name}`: {source}")]
+    Env {
+        /// The name of the environment variable.
+        name: String,
+        /// Why the variable couldn't be read.
+        #[source]
+        source: std::env::VarError,
+    },
 }
 
 impl From<BoxError> for Error {
@@ -35,7 +43,7 @@ pub fn plugin_err<E: StdError + Send + Sync + 'static>(e: E) -> Error {
 ///
 /// # Errors
 ///
-/// Returns [`Error::Plugin`] if the variable is not set or contains invalid
+/// Returns [`Error::Env`] if the variable is not set or contains invalid
 /// UTF-8. The error message includes the variable name.
 ///
 /// # Example
@@ -47,9 +55,8 @@ pub fn plugin_err<E: StdError + Send + Sync + 'static>(e: E) -> Error {
 /// }
 /// ```
 pub fn require_env(name: &str) -> Result<String, Error> {
-    std::env::var(name).map_err(|e| {
-        Error::Plugin(Box::new(std::io::Error::other(format!(
-            "environment variable `{name}`: {e}"
-        ))))
+    std::env::var(name).map_err(|source| Error::Env {
+        name: name.to_string(),
+        source,
     })
 }
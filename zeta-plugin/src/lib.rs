@@ -1,9 +1,12 @@
 //! Plugin and task management.
 
+pub mod client;
 mod error;
 mod plugin;
+pub mod reply;
 mod types;
 
+pub use client::Client;
 pub use error::Error;
 pub use plugin::Plugin;
 pub use types::{Author, Metadata, Name};
@@ -12,5 +15,6 @@ pub mod prelude {
     pub use async_trait::async_trait;
 
     pub use super::error::{BoxError, plugin_err, require_env};
-    pub use super::{Author, Error, Metadata, Name, Plugin};
+    pub use super::reply::Reply;
+    pub use super::{Author, Client, Error, Metadata, Name, Plugin};
 }
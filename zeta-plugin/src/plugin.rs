@@ -1,15 +1,14 @@
 use async_trait::async_trait;
-use irc::client::Client;
 use irc::proto::Message;
+use url::Url;
 
-use crate::{Error, Metadata};
+use crate::{Client, Error, Metadata};
 
 /// The base trait that all plugins must implement.
 ///
 ///# Examples
 ///
 /// ```
-/// use irc::client::Client;
 /// use irc::proto::{Command, Message};
 /// use zeta_plugin::{Error, prelude::*};
 ///
@@ -61,6 +60,15 @@ pub trait Plugin<C = ()>: Send + Sync {
     where
         Self: Sized;
 
+    /// Returns whether the plugin handles messages sent privately to the bot.
+    ///
+    /// Private messages, and the actions and URLs in them, are only passed to plugins that
+    /// return `true`. Those plugins must reply to the sender rather than to the target of the
+    /// message, which is the bot itself.
+    fn accepts_private_messages(&self) -> bool {
+        false
+    }
+
     /// Handles IRC protocol messages.
     async fn handle_message(
         &self,
@@ -70,4 +78,46 @@ pub trait Plugin<C = ()>: Send + Sync {
     ) -> Result<(), Error> {
         Ok(())
     }
+
+    /// Handles a CTCP `ACTION` (i.e. `/me`) in `message`, with the text of the action.
+    ///
+    /// Actions aren't passed to [`handle_message`](Plugin::handle_message), so plugins that
+    /// match on the text of messages don't mistake them for ordinary messages.
+    async fn handle_action(
+        &self,
+        _ctx: &C,
+        _client: &Client,
+        _message: &Message,
+        _text: &str,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Handles a periodic tick, for work that isn't triggered by a message, such as lifting
+    /// something that expired.
+    ///
+    /// Ticks are only sent while the bot is connected.
+    async fn handle_tick(&self, _ctx: &C, _client: &Client) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Returns whether the plugin wants to handle `url`.
+    ///
+    /// URLs are extracted from each message once, and every URL is handed to
+    /// [`handle_url`](Plugin::handle_url) of the first plugin that claims it, so that only one
+    /// plugin responds to each link.
+    fn claims_url(&self, _url: &Url) -> bool {
+        false
+    }
+
+    /// Handles a `url` found in `message` that the plugin has claimed.
+    async fn handle_url(
+        &self,
+        _ctx: &C,
+        _client: &Client,
+        _message: &Message,
+        _url: &Url,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
 }
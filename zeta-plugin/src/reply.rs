@@ -0,0 +1,499 @@
+//! Structured replies.
+//!
+//! Plugins describe what a reply consists of with a [`Reply`], and the reply is rendered with
+//! the shared [`Theme`] when it's sent, so every plugin is styled the same way and formatting
+//! codes are always closed:
+//!
+//! ```ignore
+//! let reply = Reply::tagged("Twitch")
+//!     .quoted(&clip.title)
+//!     .text(" is a clip of ")
+//!     .value(&clip.broadcaster_name)
+//!     .field("Views", views);
+//!
+//! client.send_privmsg(channel, reply)?;
+//! ```
+//!
+//! Replies render as IRC formatting codes when displayed, and can also be rendered as plain text
+//! or ANSI escape codes with [`Reply::render`].
+
+use std::fmt::{self, Write as _};
+use std::sync::OnceLock;
+
+use irc::proto::FormattedStringExt;
+use serde::{Deserialize, Serialize};
+
+/// The suffix of a truncated reply.
+const ELLIPSIS: char = '…';
+
+/// The theme used by all replies.
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Sets the theme used by all replies.
+///
+/// # Errors
+///
+/// Returns the given theme if a theme has already been set.
+pub fn set_theme(theme: Theme) -> Result<(), Theme> {
+    THEME.set(theme)
+}
+
+/// Returns the theme used by all replies, or the default theme if none has been set.
+pub fn theme() -> &'static Theme {
+    THEME.get_or_init(Theme::default)
+}
+
+/// The 16 standard IRC colors.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Color {
+    White,
+    Black,
+    Blue,
+    Green,
+    Red,
+    Brown,
+    Purple,
+    Orange,
+    Yellow,
+    LightGreen,
+    Cyan,
+    LightCyan,
+    LightBlue,
+    Pink,
+    Grey,
+    LightGrey,
+}
+
+impl Color {
+    /// All colors, in the order of their IRC color numbers.
+    const ALL: [Self; 16] = [
+        Self::White,
+        Self::Black,
+        Self::Blue,
+        Self::Green,
+        Self::Red,
+        Self::Brown,
+        Self::Purple,
+        Self::Orange,
+        Self::Yellow,
+        Self::LightGreen,
+        Self::Cyan,
+        Self::LightCyan,
+        Self::LightBlue,
+        Self::Pink,
+        Self::Grey,
+        Self::LightGrey,
+    ];
+
+    /// Returns the color with the given IRC color number, if it's one of the standard colors.
+    #[must_use]
+    pub fn from_number(number: u8) -> Option<Self> {
+        Self::ALL.get(usize::from(number)).copied()
+    }
+
+    /// Returns the IRC color number of the color.
+    #[must_use]
+    pub const fn number(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns the closest color in the 256-color ANSI palette.
+    #[must_use]
+    pub const fn ansi(self) -> u8 {
+        match self {
+            Self::White => 15,
+            Self::Black => 0,
+            Self::Blue => 4,
+            Self::Green => 2,
+            Self::Red => 9,
+            Self::Brown => 1,
+            Self::Purple => 5,
+            Self::Orange => 3,
+            Self::Yellow => 11,
+            Self::LightGreen => 10,
+            Self::Cyan => 6,
+            Self::LightCyan => 14,
+            Self::LightBlue => 12,
+            Self::Pink => 13,
+            Self::Grey => 8,
+            Self::LightGrey => 7,
+        }
+    }
+}
+
+/// How replies are styled.
+///
+/// Colors that are unset use the default color of the client.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Theme {
+    /// The text that starts every reply.
+    pub prefix: String,
+    /// The color of the prefix and text.
+    pub text: Option<Color>,
+    /// The color of values, such as field values, quoted text and links.
+    pub value: Option<Color>,
+    /// The color of the plugin tag.
+    pub tag: Option<Color>,
+    /// Whether the plugin tag is bold.
+    pub bold_tag: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            prefix: ">".to_string(),
+            text: Some(Color::Cyan),
+            value: None,
+            tag: None,
+            bold_tag: true,
+        }
+    }
+}
+
+/// The output a reply is rendered for.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Output {
+    /// IRC formatting codes.
+    Irc,
+    /// Plain text without any formatting.
+    Plain,
+    /// ANSI escape codes, for terminals.
+    Ansi,
+}
+
+/// A part of a reply.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Segment {
+    /// Text in the text color.
+    Text(String),
+    /// Text in the value color.
+    Value(String),
+    /// A value in quotation marks.
+    Quoted(String),
+    /// Bold text.
+    Emphasis(String),
+    /// A key and its value.
+    Field(String, String),
+    /// A link.
+    Link(String),
+}
+
+/// A reply to send to IRC.
+///
+/// Segments are written one after another as-is, except fields which are separated from whatever
+/// comes before them by a space. Formatting codes in the given text are removed.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[must_use]
+pub struct Reply {
+    /// The name of the plugin or service the reply is from.
+    tag: Option<String>,
+    /// The contents of the reply.
+    segments: Vec<Segment>,
+    /// The maximum number of characters of the reply.
+    max_length: Option<usize>,
+}
+
+/// A run of text with a single style.
+struct Span<'a> {
+    color: Option<Color>,
+    bold: bool,
+    text: &'a str,
+}
+
+impl Reply {
+    /// Creates an empty reply.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty reply with a tag, such as the name of the plugin or service it's from.
+    pub fn tagged(tag: impl Into<String>) -> Self {
+        Self {
+            tag: Some(tag.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Appends text.
+    pub fn text(self, text: impl fmt::Display) -> Self {
+        self.push(Segment::Text(text.to_string()))
+    }
+
+    /// Appends a value, such as a name or a number.
+    pub fn value(self, value: impl fmt::Display) -> Self {
+        self.push(Segment::Value(value.to_string()))
+    }
+
+    /// Appends a value in quotation marks, such as a title.
+    pub fn quoted(self, value: impl fmt::Display) -> Self {
+        self.push(Segment::Quoted(value.to_string()))
+    }
+
+    /// Appends emphasized text.
+    pub fn emphasis(self, text: impl fmt::Display) -> Self {
+        self.push(Segment::Emphasis(text.to_string()))
+    }
+
+    /// Appends a key and its value, separated from what comes before it by a space.
+    pub fn field(self, key: impl fmt::Display, value: impl fmt::Display) -> Self {
+        self.push(Segment::Field(key.to_string(), value.to_string()))
+    }
+
+    /// Appends a link.
+    pub fn link(self, url: impl fmt::Display) -> Self {
+        self.push(Segment::Link(url.to_string()))
+    }
+
+    /// Truncates the reply to `max_length` characters, including the prefix and tag.
+    pub const fn truncate(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    /// Renders the reply for the given output.
+    #[must_use]
+    pub fn render(&self, output: Output, theme: &Theme) -> String {
+        let mut rendered = String::new();
+        let mut color = None;
+        let spans: Vec<_> = self
+            .spans(theme)
+            .into_iter()
+            .map(|span| (span.text.strip_formatting(), span))
+            .filter(|(text, _)| !text.is_empty())
+            .collect();
+        let length: usize = spans.iter().map(|(text, _)| text.chars().count()).sum();
+        // The ellipsis takes up the last character of a truncated reply
+        let mut remaining = match self.max_length {
+            Some(max_length) if length > max_length => max_length.saturating_sub(1),
+            _ => usize::MAX,
+        };
+
+        for (text, span) in spans {
+            let length = text.chars().count();
+            let truncated = length > remaining;
+            let text = if truncated {
+                let mut text: String = text.chars().take(remaining).collect();
+                text.push(ELLIPSIS);
+                text
+            } else {
+                remaining -= length;
+                text.into_owned()
+            };
+
+            match output {
+                Output::Plain => rendered.push_str(&text),
+                Output::Irc => {
+                    if span.color != color {
+                        match span.color {
+                            Some(new) => {
+                                let _ = write!(rendered, "\x03{:02}", new.number());
+
+                                // A comma would otherwise be read as the start of a background color
+                                if text.starts_with(',') {
+                                    rendered.push_str("\x02\x02");
+                                }
+                            }
+                            None => rendered.push('\x0f'),
+                        }
+                        color = span.color;
+                    }
+
+                    if span.bold {
+                        let _ = write!(rendered, "\x02{text}\x02");
+                    } else {
+                        rendered.push_str(&text);
+                    }
+                }
+                Output::Ansi => {
+                    if span.color != color {
+                        match span.color {
+                            Some(new) => {
+                                let _ = write!(rendered, "\x1b[38;5;{}m", new.ansi());
+                            }
+                            None => rendered.push_str("\x1b[39m"),
+                        }
+                        color = span.color;
+                    }
+
+                    if span.bold {
+                        let _ = write!(rendered, "\x1b[1m{text}\x1b[22m");
+                    } else {
+                        rendered.push_str(&text);
+                    }
+                }
+            }
+
+            if truncated {
+                break;
+            }
+        }
+
+        if output == Output::Ansi && rendered.contains('\x1b') {
+            rendered.push_str("\x1b[0m");
+        }
+
+        rendered
+    }
+
+    /// Appends a segment.
+    fn push(mut self, segment: Segment) -> Self {
+        self.segments.push(segment);
+        self
+    }
+
+    /// Returns the styled runs of text of the reply.
+    fn spans<'a>(&'a self, theme: &'a Theme) -> Vec<Span<'a>> {
+        let span = |color, text| Span {
+            color,
+            bold: false,
+            text,
+        };
+        let mut spans = Vec::new();
+
+        if !theme.prefix.is_empty() {
+            spans.push(span(theme.text, &theme.prefix));
+            spans.push(span(theme.text, " "));
+        }
+
+        if let Some(tag) = &self.tag {
+            spans.push(Span {
+                color: theme.tag,
+                bold: theme.bold_tag,
+                text: tag,
+            });
+            spans.push(span(theme.text, ": "));
+        }
+
+        for (idx, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Text(text) => spans.push(span(theme.text, text)),
+                Segment::Value(value) | Segment::Link(value) => {
+                    spans.push(span(theme.value, value));
+                }
+                Segment::Quoted(value) => {
+                    spans.push(span(theme.text, "“"));
+                    spans.push(span(theme.value, value));
+                    spans.push(span(theme.text, "”"));
+                }
+                Segment::Emphasis(text) => spans.push(Span {
+                    color: theme.text,
+                    bold: true,
+                    text,
+                }),
+                Segment::Field(key, value) => {
+                    if idx > 0 {
+                        spans.push(span(theme.text, " "));
+                    }
+
+                    spans.push(span(theme.text, key));
+                    spans.push(span(theme.text, ": "));
+                    spans.push(span(theme.value, value));
+                }
+            }
+        }
+
+        spans
+    }
+}
+
+impl fmt::Display for Reply {
+    /// Renders the reply as IRC formatting codes with the shared theme.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(Output::Irc, theme()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply() -> Reply {
+        Reply::tagged("Twitch")
+            .quoted("Some clip")
+            .text(" is a clip of ")
+            .value("streamer")
+            .field("Views", 1234)
+    }
+
+    #[test]
+    fn it_should_render_irc() {
+        assert_eq!(
+            reply().render(Output::Irc, &Theme::default()),
+            "\x0310> \x0f\x02Twitch\x02\x0310: “\x0fSome clip\x0310” is a clip of \x0fstreamer\x0310 Views: \x0f1234"
+        );
+    }
+
+    #[test]
+    fn it_should_render_plain_text() {
+        assert_eq!(
+            reply().render(Output::Plain, &Theme::default()),
+            "> Twitch: “Some clip” is a clip of streamer Views: 1234"
+        );
+    }
+
+    #[test]
+    fn it_should_render_ansi() {
+        let reply = Reply::new().text("a ").emphasis("b").value(" c");
+
+        assert_eq!(
+            reply.render(Output::Ansi, &Theme::default()),
+            "\x1b[38;5;6m> a \x1b[1mb\x1b[22m\x1b[39m c\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn it_should_pad_color_numbers() {
+        let theme = Theme {
+            prefix: String::new(),
+            text: Some(Color::Green),
+            ..Theme::default()
+        };
+
+        assert_eq!(
+            Reply::new().text("5 stars").render(Output::Irc, &theme),
+            "\x03035 stars"
+        );
+        assert_eq!(
+            Reply::new().text(",5").render(Output::Irc, &theme),
+            "\x0303\x02\x02,5"
+        );
+    }
+
+    #[test]
+    fn it_should_strip_formatting_from_text() {
+        let reply = Reply::new().value("\x034red\x02 bold");
+
+        assert_eq!(reply.render(Output::Plain, &Theme::default()), "> red bold");
+    }
+
+    #[test]
+    fn it_should_truncate() {
+        let reply = Reply::tagged("Tag").value("0123456789").truncate(12);
+
+        assert_eq!(
+            reply.render(Output::Plain, &Theme::default()),
+            "> Tag: 0123…"
+        );
+    }
+
+    #[test]
+    fn it_should_truncate_at_the_end_of_a_span() {
+        let reply = Reply::tagged("Tag").value("0123").text("4567").truncate(11);
+        let rendered = reply.render(Output::Plain, &Theme::default());
+
+        assert_eq!(rendered, "> Tag: 012…");
+        assert_eq!(rendered.chars().count(), 11);
+    }
+
+    #[test]
+    fn it_should_not_truncate_replies_that_fit_exactly() {
+        let reply = Reply::tagged("Tag").value("0123").truncate(11);
+
+        assert_eq!(
+            reply.render(Output::Plain, &Theme::default()),
+            "> Tag: 0123"
+        );
+    }
+}
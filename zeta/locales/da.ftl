@@ -0,0 +1,59 @@
+# Danish messages.
+#
+# See `src/i18n.rs` for the syntax. Every message must also be translated in the other catalogues.
+
+## Shared
+
+list-pair = { $first } og { $last }
+list-many = { $rest } og { $last }
+duration-week-one = { $count } uge
+duration-week-other = { $count } uger
+duration-day-one = { $count } dag
+duration-day-other = { $count } dage
+duration-hour-one = { $count } time
+duration-hour-other = { $count } timer
+duration-minute-one = { $count } minut
+duration-minute-other = { $count } minutter
+
+## choices
+
+# The word that separates the last option from the others, e.g. "a, b eller c?"
+choices-or = eller
+
+## health
+
+health-memory = Hukommelsesforbrug: { $physical } MiB ({ $virtual } MiB virtuel)
+health-runtime = Tråde: { $workers } Opgaver: { $tasks } ({ $scheduled } i kø)
+
+## isitopen
+
+isitopen-always-open = { $nick }: { $name } har døgnåbent
+isitopen-already-open = { $nick }: { $name } har allerede åbent
+isitopen-already-open-since = { $nick }: { $name } har allerede åbent - de åbnede kl. { $opening }
+isitopen-opens-at = { $nick }: { $name } åbner kl. { $opening }
+isitopen-no-opening-time = { $nick }: pas - { $name } har ikke nogen åbningstid
+isitopen-closes-at = { $nick }: { $name } lukker kl. { $closing } - de åbnede kl. { $opening }
+isitopen-closes-at-not-open-yet = { $nick }: { $name } lukker kl. { $closing }, men de har ikke åbent endnu - de åbner først kl. { $opening }
+isitopen-closed-for-the-rest-of-the-day = { $nick }: { $name } har lukket for resten af dagen
+isitopen-no-closing-time = { $nick }: pas - { $name } har ikke nogen lukketid
+isitopen-no-opening-hours = { $nick }: pas - { $name } har ikke nogen åben- og lukketid
+isitopen-yes-always-open = { $nick }: ja, { $name } har døgnåbent
+isitopen-yes-opened-at = { $nick }: ja, { $name } åbnede kl. { $opening } i dag
+isitopen-yes-closed-today = { $nick }: ja, { $name } har lukket for i dag
+isitopen-yes-closed-opens-at = { $nick }: ja, { $name } har lukket, men de åbner kl. { $opening }
+isitopen-no-always-open = { $nick }: nej, { $name } har døgnåbent
+isitopen-no-open-until = { $nick }: nej, { $name } åbnede kl. { $opening } og lukker kl. { $closing } i dag
+isitopen-no-closed-today = { $nick }: nej, { $name } har lukket for i dag
+isitopen-no-closed-opens-at = { $nick }: nej, { $name } har lukket, men de åbner kl. { $opening }
+isitopen-unknown-time = ukendt tid
+isitopen-not-found = Fejl: stedet blev ikke fundet
+isitopen-error = Fejl: { $error }
+
+## tvmaze
+
+tvmaze-next-episode = Næste afsnit “{ $title }” ({ $episode }) sendes om { $duration }
+tvmaze-no-next-episode = { $name } har status { $status }, og der er ikke noget næste afsnit
+tvmaze-not-found = Fejl: Serien blev ikke fundet
+tvmaze-request-failed = Fejl: Kunne ikke hente information om serien
+tvmaze-invalid-response = Fejl: Kunne ikke læse information om serien
+tvmaze-unexpected-response = Fejl: Uventet svar fra API'et
@@ -0,0 +1,59 @@
+# English messages.
+#
+# See `src/i18n.rs` for the syntax. Every message must also be translated in the other catalogues.
+
+## Shared
+
+list-pair = { $first } and { $last }
+list-many = { $rest } and { $last }
+duration-week-one = { $count } week
+duration-week-other = { $count } weeks
+duration-day-one = { $count } day
+duration-day-other = { $count } days
+duration-hour-one = { $count } hour
+duration-hour-other = { $count } hours
+duration-minute-one = { $count } minute
+duration-minute-other = { $count } minutes
+
+## choices
+
+# The word that separates the last option from the others, e.g. "a, b or c?"
+choices-or = or
+
+## health
+
+health-memory = Memory usage: { $physical } MiB ({ $virtual } MiB virtual)
+health-runtime = Workers: { $workers } Tasks: { $tasks } ({ $scheduled } scheduled)
+
+## isitopen
+
+isitopen-always-open = { $nick }: { $name } is open around the clock
+isitopen-already-open = { $nick }: { $name } is already open
+isitopen-already-open-since = { $nick }: { $name } is already open - they opened at { $opening }
+isitopen-opens-at = { $nick }: { $name } opens at { $opening }
+isitopen-no-opening-time = { $nick }: no idea - { $name } doesn't have an opening time
+isitopen-closes-at = { $nick }: { $name } closes at { $closing } - they opened at { $opening }
+isitopen-closes-at-not-open-yet = { $nick }: { $name } closes at { $closing }, but they aren't open yet - they open at { $opening }
+isitopen-closed-for-the-rest-of-the-day = { $nick }: { $name } is closed for the rest of the day
+isitopen-no-closing-time = { $nick }: no idea - { $name } doesn't have a closing time
+isitopen-no-opening-hours = { $nick }: no idea - { $name } doesn't have any opening hours
+isitopen-yes-always-open = { $nick }: yes, { $name } is open around the clock
+isitopen-yes-opened-at = { $nick }: yes, { $name } opened at { $opening } today
+isitopen-yes-closed-today = { $nick }: yes, { $name } is closed for today
+isitopen-yes-closed-opens-at = { $nick }: yes, { $name } is closed, but they open at { $opening }
+isitopen-no-always-open = { $nick }: no, { $name } is open around the clock
+isitopen-no-open-until = { $nick }: no, { $name } opened at { $opening } and closes at { $closing } today
+isitopen-no-closed-today = { $nick }: no, { $name } is closed for today
+isitopen-no-closed-opens-at = { $nick }: no, { $name } is closed, but they open at { $opening }
+isitopen-unknown-time = an unknown time
+isitopen-not-found = Error: place not found
+isitopen-error = Error: { $error }
+
+## tvmaze
+
+tvmaze-next-episode = Next episode “{ $title }” ({ $episode }) airs in { $duration }
+tvmaze-no-next-episode = { $name } is currently marked as { $status } and there is no next episode
+tvmaze-not-found = Error: Show not found
+tvmaze-request-failed = Error: Failed to fetch show information
+tvmaze-invalid-response = Error: Failed to parse show information
+tvmaze-unexpected-response = Error: Received unexpected response from API
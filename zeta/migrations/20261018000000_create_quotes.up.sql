@@ -0,0 +1,11 @@
+CREATE TABLE quotes (
+    id BIGSERIAL PRIMARY KEY,
+    channel TEXT NOT NULL,
+    author TEXT NOT NULL,
+    content TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    search TSVECTOR GENERATED ALWAYS AS (to_tsvector('simple', content)) STORED
+);
+
+CREATE INDEX quotes_channel_idx ON quotes (channel);
+CREATE INDEX quotes_search_idx ON quotes USING GIN (search);
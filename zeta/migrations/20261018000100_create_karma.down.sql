@@ -0,0 +1,2 @@
+DROP TABLE karma_events;
+DROP TABLE karma;
@@ -0,0 +1,22 @@
+CREATE TABLE karma (
+    channel TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    display_name TEXT NOT NULL,
+    score BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (channel, subject)
+);
+
+CREATE INDEX karma_score_idx ON karma (channel, score);
+
+CREATE TABLE karma_events (
+    id BIGSERIAL PRIMARY KEY,
+    channel TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    giver TEXT NOT NULL,
+    delta SMALLINT NOT NULL,
+    reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX karma_events_subject_idx ON karma_events (channel, subject, created_at);
@@ -0,0 +1,11 @@
+CREATE TABLE url_history (
+    id BIGSERIAL PRIMARY KEY,
+    channel TEXT NOT NULL,
+    url TEXT NOT NULL,
+    original_url TEXT NOT NULL,
+    poster TEXT NOT NULL,
+    posted_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX url_history_url_idx ON url_history (channel, url, posted_at);
+CREATE INDEX url_history_posted_at_idx ON url_history (channel, posted_at);
@@ -0,0 +1,8 @@
+CREATE TABLE user_preferences (
+    owner_kind TEXT NOT NULL CHECK (owner_kind IN ('account', 'nick')),
+    owner TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (owner_kind, owner, key)
+);
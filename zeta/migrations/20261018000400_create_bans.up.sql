@@ -0,0 +1,13 @@
+CREATE TABLE bans (
+    id BIGSERIAL PRIMARY KEY,
+    channel TEXT NOT NULL,
+    mode TEXT NOT NULL,
+    mask TEXT NOT NULL,
+    reason TEXT,
+    set_by TEXT NOT NULL,
+    expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (channel, mode, mask)
+);
+
+CREATE INDEX bans_expires_at_idx ON bans (expires_at) WHERE expires_at IS NOT NULL;
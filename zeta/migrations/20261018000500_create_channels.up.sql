@@ -0,0 +1,6 @@
+CREATE TABLE channels (
+    key TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    invited_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
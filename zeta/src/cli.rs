@@ -8,4 +8,731 @@ pub struct Opts {
     /// the path to the config file
     #[argh(option, short = 'c', default = r#"PathBuf::from("config.toml")"#)]
     pub config_path: PathBuf,
+    /// the command to run instead of starting the bot
+    #[argh(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Available subcommands.
+#[derive(FromArgs, Debug)]
+#[argh(subcommand)]
+pub enum Command {
+    Run(RunCommand),
+    CheckConfig(CheckConfigCommand),
+    ListPlugins(ListPluginsCommand),
+    Migrate(MigrateCommand),
+    Quotes(QuotesCommand),
+    Repl(ReplCommand),
+}
+
+/// start the bot (the default)
+#[derive(FromArgs, Debug, Default)]
+#[argh(subcommand, name = "run")]
+pub struct RunCommand {}
+
+/// validate the configuration
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "check-config")]
+pub struct CheckConfigCommand {
+    /// fail on warnings, such as unknown keys and missing plugin secrets
+    #[argh(switch)]
+    pub strict: bool,
+}
+
+/// list the plugins compiled into this build and whether they initialize
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "list-plugins")]
+pub struct ListPluginsCommand {}
+
+/// manage database migrations
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "migrate")]
+pub struct MigrateCommand {
+    /// the migration operation to perform
+    #[argh(subcommand)]
+    pub action: MigrateAction,
+}
+
+/// Database migration operations.
+#[derive(FromArgs, Debug)]
+#[argh(subcommand)]
+pub enum MigrateAction {
+    Run(MigrateRun),
+    Revert(MigrateRevert),
+}
+
+/// apply all pending migrations
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "run")]
+pub struct MigrateRun {}
+
+/// revert applied migrations
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "revert")]
+pub struct MigrateRevert {
+    /// revert all migrations newer than this version, instead of only the latest migration
+    #[argh(option)]
+    pub target: Option<i64>,
+}
+
+/// manage the quote database
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "quotes")]
+pub struct QuotesCommand {
+    /// the quote operation to perform
+    #[argh(subcommand)]
+    pub action: QuotesAction,
+}
+
+/// Quote database operations.
+#[derive(FromArgs, Debug)]
+#[argh(subcommand)]
+pub enum QuotesAction {
+    Export(QuotesExport),
+    Import(QuotesImport),
+}
+
+/// export quotes as JSON Lines
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "export")]
+pub struct QuotesExport {
+    /// only export quotes from this channel
+    #[argh(option)]
+    pub channel: Option<String>,
+    /// the file to write the quotes to
+    #[argh(positional)]
+    pub output: PathBuf,
+}
+
+/// import quotes from a JSON Lines file
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "import")]
+pub struct QuotesImport {
+    /// the file to read the quotes from
+    #[argh(positional)]
+    pub input: PathBuf,
+}
+
+/// exercise the plugins by typing messages into a channel, without an IRC server
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "repl")]
+pub struct ReplCommand {
+    /// the nickname to send messages as
+    #[argh(option, default = r#"String::from("tester")"#)]
+    pub nick: String,
+    /// the channel to send messages to
+    #[argh(option, default = r##"String::from("#zeta")"##)]
+    pub channel: String,
+}
+
+#[cfg(feature = "database")]
+pub mod quotes {
+    //! Quote database export and import.
+
+    use std::fs::File;
+    use std::io::{BufRead, BufReader, BufWriter, Write};
+
+    use miette::{IntoDiagnostic, WrapErr};
+    use zeta::database::{Database, quotes::QuoteRecord};
+
+    use super::{QuotesAction, QuotesCommand};
+
+    /// Runs the given quote database operation.
+    pub async fn run(command: QuotesCommand, db: &Database) -> miette::Result<()> {
+        match command.action {
+            QuotesAction::Export(opts) => {
+                let records = zeta::database::quotes::export(db, opts.channel.as_deref())
+                    .await
+                    .into_diagnostic()
+                    .wrap_err("could not fetch quotes")?;
+                let file = File::create(&opts.output)
+                    .into_diagnostic()
+                    .wrap_err("could not create output file")?;
+                let mut writer = BufWriter::new(file);
+
+                for record in &records {
+                    serde_json::to_writer(&mut writer, record).into_diagnostic()?;
+                    writer.write_all(b"\n").into_diagnostic()?;
+                }
+
+                writer.flush().into_diagnostic()?;
+                println!("exported {} quotes", records.len());
+            }
+            QuotesAction::Import(opts) => {
+                let file = File::open(&opts.input)
+                    .into_diagnostic()
+                    .wrap_err("could not open input file")?;
+                let mut records = Vec::new();
+
+                for (number, line) in BufReader::new(file).lines().enumerate() {
+                    let line = line.into_diagnostic()?;
+
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+
+                    let record: QuoteRecord = serde_json::from_str(&line)
+                        .into_diagnostic()
+                        .wrap_err_with(|| format!("invalid quote on line {}", number + 1))?;
+                    records.push(record);
+                }
+
+                let count = zeta::database::quotes::import(db, &records)
+                    .await
+                    .into_diagnostic()
+                    .wrap_err("could not import quotes")?;
+                println!("imported {count} quotes");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "database")]
+pub mod migrate {
+    //! Database migrations.
+
+    use miette::{IntoDiagnostic, WrapErr};
+    use zeta::database::{self, Database};
+
+    use super::{MigrateAction, MigrateCommand};
+
+    /// Runs the given migration operation.
+    pub async fn run(command: MigrateCommand, db: Database) -> miette::Result<()> {
+        match command.action {
+            MigrateAction::Run(_) => {
+                database::migrate(db)
+                    .await
+                    .into_diagnostic()
+                    .wrap_err("could not apply migrations")?;
+                println!("database is up to date");
+            }
+            MigrateAction::Revert(opts) => {
+                let version = database::revert(db, opts.target)
+                    .await
+                    .into_diagnostic()
+                    .wrap_err("could not revert migrations")?;
+                println!("reverted database to version {version}");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub mod plugins {
+    //! Plugin initialization checks.
+
+    use miette::IntoDiagnostic;
+    use zeta::context::Context;
+    use zeta::{Config, Registry};
+
+    /// The outcome of initializing a plugin.
+    pub struct PluginStatus {
+        /// The name of the plugin.
+        pub name: String,
+        /// The feature flag that compiles the plugin in.
+        pub feature: &'static str,
+        /// The initialization error, if the plugin failed to initialize.
+        pub error: Option<zeta_plugin::Error>,
+    }
+
+    /// Initializes every plugin compiled into this build, and returns how it went.
+    ///
+    /// The database isn't connected to, so plugins that use it during initialization fail.
+    pub fn check(config: &Config) -> miette::Result<Vec<PluginStatus>> {
+        zeta::http::init(&config.http);
+
+        #[cfg(feature = "database")]
+        let db = zeta::database::connect_lazy(&config.database.url, &config.database)
+            .into_diagnostic()?;
+        let context = Context::new(
+            #[cfg(feature = "database")]
+            db,
+            zeta::dns::new(),
+            config.clone(),
+        );
+        let mut registry = Registry::preloaded(&context);
+
+        Ok(zeta::bundled_plugins()
+            .into_iter()
+            .map(|(name, feature)| {
+                let name = name.to_string();
+                let error = registry
+                    .failed
+                    .iter()
+                    .position(|(failed, _)| *failed == name)
+                    .map(|idx| registry.failed.swap_remove(idx).1);
+
+                PluginStatus {
+                    name,
+                    feature,
+                    error,
+                }
+            })
+            .collect())
+    }
+
+    /// Prints the plugins compiled into this build, their feature flags and whether they
+    /// initialize.
+    pub fn list(config: &Config) -> miette::Result<()> {
+        let statuses = check(config)?;
+        let name_width = statuses.iter().map(|s| s.name.len()).max().unwrap_or(0);
+        let feature_width = statuses.iter().map(|s| s.feature.len()).max().unwrap_or(0);
+
+        for status in &statuses {
+            let outcome = status
+                .error
+                .as_ref()
+                .map_or_else(|| "ok".to_string(), |err| format!("failed: {err}"));
+
+            println!(
+                "{:name_width$}  {:feature_width$}  {outcome}",
+                status.name, status.feature
+            );
+        }
+
+        Ok(())
+    }
+}
+
+pub mod check_config {
+    //! Configuration validation.
+
+    use figment::Figment;
+    use figment::value::{Dict, Value};
+    use miette::IntoDiagnostic;
+    use zeta::Config;
+    use zeta_plugin::Error as PluginError;
+
+    use super::CheckConfigCommand;
+    use super::plugins;
+
+    /// Validates the configuration, and reports unknown keys and missing plugin secrets.
+    ///
+    /// Errors in the configuration itself have already been reported when it was extracted.
+    pub fn run(
+        command: &CheckConfigCommand,
+        figment: &Figment,
+        config: &Config,
+    ) -> miette::Result<()> {
+        let mut warnings = Vec::new();
+        let provided: Dict = figment.extract().into_diagnostic()?;
+        let known = Value::serialize(config).into_diagnostic()?;
+
+        if let Some(known) = known.as_dict() {
+            let mut unknown = Vec::new();
+
+            unknown_keys(&provided, known, "", &mut unknown);
+            warnings.extend(
+                unknown
+                    .into_iter()
+                    .map(|key| format!("unknown key `{key}`")),
+            );
+        }
+
+        for status in plugins::check(config)? {
+            if let Some(PluginError::Env { name, .. }) = status.error {
+                warnings.push(format!(
+                    "plugin `{}` is missing the secret `{name}`",
+                    status.name
+                ));
+            }
+        }
+
+        for warning in &warnings {
+            println!("warning: {warning}");
+        }
+
+        if command.strict && !warnings.is_empty() {
+            miette::bail!("configuration has {} warnings", warnings.len());
+        }
+
+        println!("configuration is valid");
+
+        Ok(())
+    }
+
+    /// Collects the paths of keys in `provided` that aren't in `known` into `unknown`.
+    fn unknown_keys(provided: &Dict, known: &Dict, prefix: &str, unknown: &mut Vec<String>) {
+        for (key, value) in provided {
+            let path = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{prefix}.{key}")
+            };
+
+            match (value, known.get(key)) {
+                (_, None) => unknown.push(path),
+                (Value::Dict(_, provided), Some(Value::Dict(_, known))) => {
+                    unknown_keys(provided, known, &path, unknown);
+                }
+                (Value::Array(_, provided), Some(Value::Array(_, known))) => {
+                    for (idx, (provided, known)) in provided.iter().zip(known).enumerate() {
+                        if let (Value::Dict(_, provided), Value::Dict(_, known)) = (provided, known)
+                        {
+                            unknown_keys(provided, known, &format!("{path}[{idx}]"), unknown);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use figment::providers::{Format, Toml};
+
+        use super::*;
+
+        fn dict(toml: &str) -> Dict {
+            Figment::from(Toml::string(toml)).extract().unwrap()
+        }
+
+        #[test]
+        fn it_should_report_unknown_keys() {
+            let provided = dict(
+                r##"
+                nickname = "zeta"
+                typo = true
+
+                [tls]
+                enabled = true
+                verify = false
+
+                [[channels]]
+                name = "#zeta"
+                password = "secret"
+                "##,
+            );
+            let known = dict(
+                r##"
+                nickname = "zeta"
+
+                [tls]
+                enabled = true
+
+                [[channels]]
+                name = "#zeta"
+                "##,
+            );
+            let mut unknown = Vec::new();
+
+            unknown_keys(&provided, &known, "", &mut unknown);
+
+            assert_eq!(unknown, ["channels[0].password", "tls.verify", "typo"]);
+        }
+    }
+}
+
+pub mod repl {
+    //! Offline plugin testing.
+    //!
+    //! The bot connects to a loopback IRC server that relays lines from stdin as messages from a
+    //! user in a channel, and prints the bot's replies with their formatting rendered as ANSI
+    //! escape codes.
+
+    use std::fmt::Write as _;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    use irc::proto::{Command, Message};
+    use miette::{IntoDiagnostic, WrapErr};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio::net::tcp::OwnedWriteHalf;
+    use zeta::config::{IrcChannelConfig, IrcConfig};
+    use zeta::{Config, Zeta};
+    use zeta_plugin::reply::Color;
+
+    use super::ReplCommand;
+
+    /// The name of the loopback server.
+    const SERVER_NAME: &str = "zeta.repl";
+
+    /// How long to wait for replies after stdin is closed, before exiting.
+    const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Runs the bot against a loopback server, and relays lines from stdin to it.
+    pub async fn run(
+        command: ReplCommand,
+        mut config: Config,
+        #[cfg(feature = "database")] db: zeta::database::Database,
+    ) -> miette::Result<()> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .into_diagnostic()
+            .wrap_err("could not bind loopback server")?;
+        let port = listener.local_addr().into_diagnostic()?.port();
+
+        // Settings of the channel, such as its locale, still apply if it's configured
+        let channel = config
+            .irc
+            .channels
+            .iter()
+            .find(|channel| channel.name.eq_ignore_ascii_case(&command.channel))
+            .cloned()
+            .map(|channel| IrcChannelConfig {
+                // There are no services to identify with
+                requires_identification: false,
+                ..channel
+            })
+            .unwrap_or_else(|| IrcChannelConfig {
+                name: command.channel.clone(),
+                ..IrcChannelConfig::default()
+            });
+
+        config.irc = IrcConfig {
+            hostname: Ipv4Addr::LOCALHOST.to_string(),
+            port: Some(port),
+            tls: None,
+            password: None,
+            nick_password: None,
+            should_ghost: false,
+            services: None,
+            oper: None,
+            umodes: None,
+            channels: vec![channel],
+            ..config.irc
+        };
+        // Nothing outside of the repl should be affected by it
+        config.server.enabled = false;
+        config.logger.enabled = false;
+
+        let mut nickname = config.irc.nickname.clone();
+        let mut zeta = Zeta::new(
+            config,
+            #[cfg(feature = "database")]
+            db,
+            zeta::dns::new(),
+        );
+        let mut bot = tokio::spawn(async move { zeta.run().await });
+
+        let socket = tokio::select! {
+            accepted = listener.accept() => accepted.into_diagnostic()?.0,
+            result = &mut bot => {
+                result.into_diagnostic()??;
+                miette::bail!("the bot exited before connecting");
+            }
+        };
+        let (reader, mut writer) = socket.into_split();
+        let mut replies = BufReader::new(reader).lines();
+        let mut input = Some(BufReader::new(tokio::io::stdin()).lines());
+        let source = format!("{0}!{0}@{SERVER_NAME}", command.nick);
+
+        loop {
+            tokio::select! {
+                line = replies.next_line() => {
+                    let Some(line) = line.into_diagnostic()? else {
+                        break;
+                    };
+
+                    if let Ok(message) = line.parse::<Message>() {
+                        respond(&message, &mut nickname, &mut writer).await?;
+                    }
+                }
+                Some(line) = next_line(input.as_mut()) => {
+                    let Some(line) = line.into_diagnostic()? else {
+                        input = None;
+                        continue;
+                    };
+
+                    if !line.trim().is_empty() {
+                        let line = format!(":{source} PRIVMSG {} :{line}", command.channel);
+
+                        send(&mut writer, &line).await?;
+                    }
+                }
+                () = tokio::time::sleep(DRAIN_TIMEOUT), if input.is_none() => break,
+            }
+        }
+
+        if bot.is_finished() {
+            bot.await.into_diagnostic()??;
+        } else {
+            bot.abort();
+        }
+
+        Ok(())
+    }
+
+    /// Reads the next line from stdin, or never resolves once stdin is closed.
+    async fn next_line(
+        input: Option<&mut tokio::io::Lines<BufReader<tokio::io::Stdin>>>,
+    ) -> Option<std::io::Result<Option<String>>> {
+        match input {
+            Some(input) => Some(input.next_line().await),
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Acts as the server for a message sent by the bot, and prints its replies.
+    async fn respond(
+        message: &Message,
+        nickname: &mut String,
+        writer: &mut OwnedWriteHalf,
+    ) -> miette::Result<()> {
+        match &message.command {
+            Command::NICK(nick) => nickname.clone_from(nick),
+            Command::USER(..) => {
+                send(
+                    writer,
+                    &format!(":{SERVER_NAME} 001 {nickname} :Welcome to the zeta repl"),
+                )
+                .await?;
+                send(
+                    writer,
+                    &format!(":{SERVER_NAME} 422 {nickname} :MOTD File is missing"),
+                )
+                .await?;
+            }
+            Command::JOIN(channels, ..) => {
+                for channel in channels.split(',') {
+                    send(
+                        writer,
+                        &format!(":{nickname}!{nickname}@{SERVER_NAME} JOIN {channel}"),
+                    )
+                    .await?;
+                }
+            }
+            Command::PING(token, _) => {
+                send(
+                    writer,
+                    &format!(":{SERVER_NAME} PONG {SERVER_NAME} :{token}"),
+                )
+                .await?;
+            }
+            Command::PRIVMSG(target, text) => {
+                println!("[{target}] <{nickname}> {}", render(text));
+            }
+            Command::NOTICE(target, text) => {
+                println!("[{target}] -{nickname}- {}", render(text));
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Sends a line to the bot.
+    async fn send(writer: &mut OwnedWriteHalf, line: &str) -> miette::Result<()> {
+        writer
+            .write_all(format!("{line}\r\n").as_bytes())
+            .await
+            .into_diagnostic()
+            .wrap_err("could not send message to the bot")
+    }
+
+    /// Renders IRC formatting in `text` as ANSI escape codes.
+    fn render(text: &str) -> String {
+        let mut rendered = String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+        let mut bold = false;
+        let mut italic = false;
+        let mut underline = false;
+        let mut strikethrough = false;
+        let mut reverse = false;
+
+        let toggle = |rendered: &mut String, state: &mut bool, on: u8, off: u8| {
+            *state = !*state;
+            let _ = write!(rendered, "\x1b[{}m", if *state { on } else { off });
+        };
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\x02' => toggle(&mut rendered, &mut bold, 1, 22),
+                '\x1d' => toggle(&mut rendered, &mut italic, 3, 23),
+                '\x1f' => toggle(&mut rendered, &mut underline, 4, 24),
+                '\x1e' => toggle(&mut rendered, &mut strikethrough, 9, 29),
+                '\x16' => toggle(&mut rendered, &mut reverse, 7, 27),
+                '\x0f' => {
+                    bold = false;
+                    italic = false;
+                    underline = false;
+                    strikethrough = false;
+                    reverse = false;
+                    rendered.push_str("\x1b[0m");
+                }
+                '\x03' => {
+                    let foreground = color_number(&mut chars);
+                    let background = foreground
+                        .and_then(|_| chars.next_if_eq(&',').map(|_| color_number(&mut chars)));
+
+                    match (foreground, background) {
+                        (None, _) => rendered.push_str("\x1b[39;49m"),
+                        (Some(foreground), background) => {
+                            rendered.push_str(&ansi_color(foreground, 38));
+
+                            match background {
+                                Some(Some(background)) => {
+                                    rendered.push_str(&ansi_color(background, 48));
+                                }
+                                // A comma that isn't followed by a color is just a comma
+                                Some(None) => rendered.push(','),
+                                None => {}
+                            }
+                        }
+                    }
+                }
+                '\x11' => {}
+                c => rendered.push(c),
+            }
+        }
+
+        if rendered.contains('\x1b') {
+            rendered.push_str("\x1b[0m");
+        }
+
+        rendered
+    }
+
+    /// Reads an IRC color number of up to two digits.
+    fn color_number(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Option<u8> {
+        let mut digits = String::with_capacity(2);
+
+        while digits.len() < 2
+            && let Some(digit) = chars.next_if(char::is_ascii_digit)
+        {
+            digits.push(digit);
+        }
+
+        digits.parse().ok()
+    }
+
+    /// Returns the ANSI escape code that sets an IRC color, where `layer` is 38 for the
+    /// foreground and 48 for the background.
+    fn ansi_color(color: u8, layer: u8) -> String {
+        match Color::from_number(color) {
+            Some(color) => format!("\x1b[{layer};5;{}m", color.ansi()),
+            None => format!("\x1b[{}m", layer + 1),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn it_should_render_formatting() {
+            assert_eq!(render("plain"), "plain");
+            assert_eq!(
+                render("\x02bold\x02 text"),
+                "\x1b[1mbold\x1b[22m text\x1b[0m"
+            );
+            assert_eq!(render("\x1dit\x0f done"), "\x1b[3mit\x1b[0m done\x1b[0m");
+        }
+
+        #[test]
+        fn it_should_render_colors() {
+            assert_eq!(render("\x0310> hi"), "\x1b[38;5;6m> hi\x1b[0m");
+            assert_eq!(
+                render("\x034,1red\x03 plain"),
+                "\x1b[38;5;9m\x1b[48;5;0mred\x1b[39;49m plain\x1b[0m"
+            );
+            assert_eq!(render("\x033,x"), "\x1b[38;5;2m,x\x1b[0m");
+            assert_eq!(render("\x0399default"), "\x1b[39mdefault\x1b[0m");
+        }
+    }
 }
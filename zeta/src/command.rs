@@ -1,6 +1,7 @@
 //! IRC prefix command matching.
 //!
-//! Matches a static prefix against an IRC message and extracts the trailing arguments.
+//! Matches a static prefix against an IRC message and extracts the trailing arguments. Commands
+//! with structured arguments use [`Typed`], which parses them into an [`argh::FromArgs`] struct.
 //!
 //! # Example
 //!
@@ -15,6 +16,25 @@
 //! assert_eq!(YT.parse(".goodbye"), None);
 //! ```
 
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::sync::{LazyLock, Mutex};
+
+use argh::{EarlyExit, FromArgs};
+use zeta_plugin::Client;
+use zeta_plugin::reply::Reply;
+
+/// The help of every typed command, by prefix.
+static COMMANDS: LazyLock<Mutex<BTreeMap<&'static str, CommandHelp>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+thread_local! {
+    /// The prefix of the last command that matched on this thread, while a plugin is polled in
+    /// [`matched`].
+    static MATCHED: Cell<Option<&'static str>> = const { Cell::new(None) };
+}
+
 /// A zero-sized prefix matcher for IRC bot commands.
 ///
 /// Stores a `&'static str` prefix and provides [`parse`](Prefix::parse) to check whether a message
@@ -43,10 +63,13 @@ impl Prefix {
     ///
     /// Returns `None` if the input does not start with the prefix, or if the character immediately
     /// following the prefix is not whitespace (i.e. it is part of a longer word).
+    ///
+    /// On a match, the prefix is reported to [`matched`], so the dispatcher knows that the plugin
+    /// handles the message.
     #[must_use]
     pub fn parse<'a>(&self, input: &'a str) -> Option<&'a str> {
         let suffix = input.strip_prefix(self.0)?;
-        match suffix.chars().next() {
+        let args = match suffix.chars().next() {
             // Input is exactly the prefix — no arguments.
             None => Some(""),
             // Prefix followed by whitespace — skip all leading whitespace.
@@ -60,10 +83,285 @@ impl Prefix {
             }
             // Prefix followed by a non-whitespace character — not a match (e.g. `.y` vs `.yt`).
             Some(_) => None,
+        };
+
+        if args.is_some() {
+            MATCHED.set(Some(self.0));
         }
+
+        args
     }
 }
 
+/// A command whose arguments are parsed into `T`.
+///
+/// Arguments are split like a shell would, so they can be quoted, and parsed with [`FromArgs`].
+/// `--help` replies with the usage of the command, and invalid arguments with an error and the
+/// usage. The help is registered when the command is created, and can be looked up with
+/// [`help`].
+///
+/// # Example
+///
+/// ```ignore
+/// /// DNS lookup utility
+/// #[derive(FromArgs)]
+/// struct Opts {
+///     /// the name of the domain to look up
+///     #[argh(positional)]
+///     name: String,
+/// }
+///
+/// let command = Typed::<Opts>::new(".dig", "Dig");
+///
+/// if let Some(opts) = command.handle(client, channel, user_message)? {
+///     // …
+/// }
+/// ```
+pub struct Typed<T> {
+    /// The prefix of the command.
+    prefix: Prefix,
+    /// The tag of replies, such as the name of the plugin.
+    tag: &'static str,
+    /// The help of the command.
+    help: CommandHelp,
+    _args: PhantomData<fn() -> T>,
+}
+
+/// The result of parsing a typed command.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Parsed<T> {
+    /// The arguments were parsed.
+    Command(T),
+    /// The user asked for help.
+    Help,
+    /// The arguments were invalid, with a description of why.
+    Invalid(String),
+}
+
+/// The help of a command, as generated by [`FromArgs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandHelp {
+    /// The prefix of the command, e.g. `.dig`.
+    pub command: &'static str,
+    /// The usage line, e.g. `.dig <name> [<record_type>]`.
+    pub usage: String,
+    /// The description of the command.
+    pub description: String,
+    /// The name and description of each argument and option.
+    pub arguments: Vec<(String, String)>,
+}
+
+impl<T: FromArgs> Typed<T> {
+    /// Creates a typed command with the given prefix, and registers its help.
+    #[must_use]
+    pub fn new(prefix: &'static str, tag: &'static str) -> Self {
+        let help = match T::from_args(&[prefix], &["--help"]) {
+            Ok(_) => CommandHelp {
+                command: prefix,
+                usage: prefix.to_string(),
+                description: String::new(),
+                arguments: Vec::new(),
+            },
+            Err(exit) => CommandHelp::parse(prefix, &exit.output),
+        };
+
+        COMMANDS.lock().unwrap().insert(prefix, help.clone());
+
+        Self {
+            prefix: Prefix::new(prefix),
+            tag,
+            help,
+            _args: PhantomData,
+        }
+    }
+
+    /// Returns the prefix of the command.
+    #[must_use]
+    pub const fn prefix(&self) -> Prefix {
+        self.prefix
+    }
+
+    /// Returns the help of the command.
+    #[must_use]
+    pub const fn help(&self) -> &CommandHelp {
+        &self.help
+    }
+
+    /// Parses `input` if it starts with the command prefix.
+    #[must_use]
+    pub fn parse(&self, input: &str) -> Option<Parsed<T>> {
+        let args = self.prefix.parse(input)?;
+        let Some(args) = shlex::split(args) else {
+            return Some(Parsed::Invalid("Unterminated quote".to_string()));
+        };
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+        Some(match T::from_args(&[self.prefix.as_str()], &args) {
+            Ok(args) => Parsed::Command(args),
+            Err(EarlyExit { status: Ok(()), .. }) => Parsed::Help,
+            Err(EarlyExit { output, .. }) => {
+                Parsed::Invalid(output.split_whitespace().collect::<Vec<_>>().join(" "))
+            }
+        })
+    }
+
+    /// Parses `input` and returns the arguments if it's a valid invocation of the command.
+    ///
+    /// Help and errors are sent to `target` instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a reply could not be sent.
+    pub fn handle(
+        &self,
+        client: &Client,
+        target: &str,
+        input: &str,
+    ) -> Result<Option<T>, irc::error::Error> {
+        match self.parse(input) {
+            Some(parsed) => self.respond(client, target, parsed),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the arguments of `parsed` if it's a valid invocation of the command.
+    ///
+    /// Help and errors are sent to `target` instead. This is the second half of
+    /// [`handle`](Typed::handle), for callers that parse the input themselves.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a reply could not be sent.
+    pub fn respond(
+        &self,
+        client: &Client,
+        target: &str,
+        parsed: Parsed<T>,
+    ) -> Result<Option<T>, irc::error::Error> {
+        match parsed {
+            Parsed::Command(args) => Ok(Some(args)),
+            Parsed::Help => {
+                for reply in self.help.replies(self.tag) {
+                    client.send_privmsg(target, reply)?;
+                }
+
+                Ok(None)
+            }
+            Parsed::Invalid(error) => {
+                let reply = Reply::tagged(self.tag)
+                    .text(format!("{error} - Usage: "))
+                    .value(&self.help.usage);
+
+                client.send_privmsg(target, reply)?;
+
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl CommandHelp {
+    /// Parses the `--help` output of [`FromArgs`].
+    fn parse(command: &'static str, output: &str) -> CommandHelp {
+        let mut help = CommandHelp {
+            command,
+            usage: command.to_string(),
+            description: String::new(),
+            arguments: Vec::new(),
+        };
+        let mut in_arguments = false;
+
+        for line in output.lines() {
+            if let Some(usage) = line.strip_prefix("Usage: ") {
+                help.usage = usage.trim().to_string();
+            } else if line.trim().is_empty() {
+                continue;
+            } else if !line.starts_with(' ') {
+                in_arguments = matches!(line, "Positional Arguments:" | "Options:");
+
+                if !in_arguments && !line.ends_with(':') && help.description.is_empty() {
+                    help.description = line.trim().to_string();
+                }
+            } else if !in_arguments {
+                continue;
+            } else if line.starts_with("   ") {
+                // A description that's wrapped onto the next line.
+                if let Some((_, description)) = help.arguments.last_mut() {
+                    description.push(' ');
+                    description.push_str(line.trim());
+                }
+            } else {
+                let line = line.trim();
+                let (name, description) = line.split_once("  ").unwrap_or((line, ""));
+
+                if !name.starts_with("--help") {
+                    help.arguments
+                        .push((name.to_string(), description.trim().to_string()));
+                }
+            }
+        }
+
+        help
+    }
+
+    /// Returns the replies that describe the command, starting with its usage.
+    #[must_use]
+    pub fn replies(&self, tag: &str) -> Vec<Reply> {
+        let mut usage = Reply::tagged(tag).text("Usage: ").value(&self.usage);
+
+        if !self.description.is_empty() {
+            usage = usage.text(" - ").text(&self.description);
+        }
+
+        std::iter::once(usage)
+            .chain(self.arguments.iter().map(|(name, description)| {
+                Reply::tagged(tag).value(name).text(" - ").text(description)
+            }))
+            .collect()
+    }
+}
+
+/// Returns the help of the command with the given prefix, if it's a typed command.
+///
+/// The prefix can be given without its leading character, e.g. `dig` for `.dig`.
+#[must_use]
+pub fn help(command: &str) -> Option<CommandHelp> {
+    let commands = COMMANDS.lock().unwrap();
+
+    commands.get(command).cloned().or_else(|| {
+        commands
+            .iter()
+            .find(|(prefix, _)| prefix.get(1..) == Some(command))
+            .map(|(_, help)| help.clone())
+    })
+}
+
+/// Reports that the plugin being polled handles the message, with `action` in place of a command.
+///
+/// This is for plugins that act on messages that aren't commands, such as karma changes, so
+/// their invocations are recorded like those of commands that matched.
+pub fn invoked(action: &'static str) {
+    MATCHED.set(Some(action));
+}
+
+/// Calls `f`, and returns its output along with the prefix of the last command that matched
+/// while it ran, if any.
+///
+/// This is used to tell whether a plugin handles a message while it's polled, since plugins are
+/// passed every message.
+pub(crate) fn matched<T>(f: impl FnOnce() -> T) -> (T, Option<&'static str>) {
+    let outer = MATCHED.replace(None);
+    let output = f();
+
+    (output, MATCHED.replace(outer))
+}
+
+/// Returns the prefixes of all typed commands, in order.
+#[must_use]
+pub fn commands() -> Vec<&'static str> {
+    COMMANDS.lock().unwrap().keys().copied().collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,10 +403,92 @@ mod tests {
         assert_eq!(CMD.parse("!test\u{3000}args"), Some("args"));
     }
 
+    #[test]
+    fn matched_reports_matched_commands() {
+        const CMD: Prefix = Prefix::new("!test");
+
+        assert_eq!(
+            matched(|| CMD.parse("!test args")),
+            (Some("args"), Some("!test"))
+        );
+        assert_eq!(matched(|| CMD.parse("!other")), (None, None));
+        assert_eq!(
+            matched(|| {
+                let inner = matched(|| CMD.parse("!test"));
+
+                (inner, CMD.parse("!other"))
+            }),
+            (((Some(""), Some("!test")), None), None)
+        );
+    }
+
+    #[test]
+    fn matched_reports_invocations() {
+        assert_eq!(matched(|| invoked("karma")), ((), Some("karma")));
+    }
+
     #[test]
     fn as_str_returns_prefix() {
         const CMD: Prefix = Prefix::new(".yt");
 
         assert_eq!(CMD.as_str(), ".yt");
     }
+
+    /// Test utility
+    #[derive(FromArgs, Debug, PartialEq, Eq)]
+    struct TestOpts {
+        /// the name to look up
+        #[argh(positional)]
+        name: String,
+        /// the number of results
+        #[argh(option, short = 'n', default = "1")]
+        count: u32,
+    }
+
+    #[test]
+    fn typed_parses_quoted_arguments() {
+        let command = Typed::<TestOpts>::new("!typed", "Test");
+
+        assert_eq!(
+            command.parse("!typed \"hello world\" -n 3"),
+            Some(Parsed::Command(TestOpts {
+                name: "hello world".to_string(),
+                count: 3,
+            }))
+        );
+        assert_eq!(command.parse("!other"), None);
+    }
+
+    #[test]
+    fn typed_reports_help_and_errors() {
+        let command = Typed::<TestOpts>::new("!typed", "Test");
+
+        assert_eq!(command.parse("!typed --help"), Some(Parsed::Help));
+        assert!(matches!(command.parse("!typed"), Some(Parsed::Invalid(_))));
+        assert!(matches!(
+            command.parse("!typed \"unterminated"),
+            Some(Parsed::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn typed_registers_help() {
+        let command = Typed::<TestOpts>::new("!helped", "Test");
+        let help = command.help();
+
+        assert_eq!(help.usage, "!helped <name> [-n <count>]");
+        assert_eq!(help.description, "Test utility");
+        assert_eq!(
+            help.arguments,
+            [
+                ("name".to_string(), "the name to look up".to_string()),
+                (
+                    "-n, --count".to_string(),
+                    "the number of results".to_string()
+                ),
+            ]
+        );
+        assert_eq!(super::help("helped").as_ref(), Some(help));
+        assert!(commands().contains(&"!helped"));
+    }
 }
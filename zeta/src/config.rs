@@ -1,10 +1,25 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::time::Duration;
 
+use ipnet::IpNet;
 use serde::{Deserialize, Serialize};
+pub use zeta_plugin::reply::Theme;
 
 use crate::consts::{
-    DEFAULT_DB_IDLE_TIMEOUT, DEFAULT_IRC_PORT, DEFAULT_IRC_TLS_PORT, DEFAULT_MAX_DB_CONNECTIONS,
+    DEFAULT_CAPS_MIN_LENGTH, DEFAULT_CTCP_BURST, DEFAULT_CTCP_INTERVAL, DEFAULT_CTCP_VERSION,
+    DEFAULT_DB_IDLE_TIMEOUT, DEFAULT_FETCH_MAX_BYTES, DEFAULT_FETCH_MAX_REDIRECTS,
+    DEFAULT_HTTP_CACHE_CAPACITY, DEFAULT_HTTP_CACHE_MAX_ENTRY_SIZE, DEFAULT_HTTP_CACHE_TTL,
+    DEFAULT_HTTP_MAX_CONCURRENT_REQUESTS, DEFAULT_HTTP_MAX_RETRIES, DEFAULT_HTTP_MAX_RETRY_DELAY,
+    DEFAULT_HTTP_RETRY_BACKOFF, DEFAULT_IRC_PORT, DEFAULT_IRC_TLS_PORT, DEFAULT_KARMA_COOLDOWN,
+    DEFAULT_LOG_DIRECTORY, DEFAULT_MAX_DB_CONNECTIONS, DEFAULT_MAX_URLS_PER_MESSAGE,
+    DEFAULT_MODERATION_BAN_DURATION, DEFAULT_MUTE_MODE, DEFAULT_NICK_RECOVERY_INTERVAL,
+    DEFAULT_NICK_RECOVERY_MAX_BACKOFF, DEFAULT_REJOIN_KICK_DELAY, DEFAULT_REJOIN_MAX_RETRIES,
+    DEFAULT_REJOIN_MAX_RETRY_DELAY, DEFAULT_REJOIN_RETRY_DELAY, DEFAULT_SERVER_ADDRESS,
+    DEFAULT_TRACING_FILTER, DEFAULT_TRACING_SAMPLING_RATIO, DEFAULT_UNFURL_MAX_BYTES,
 };
+use crate::i18n::Locale;
 
 /// Main application configuration structure.
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -15,6 +30,30 @@ pub struct Config {
     pub tracing: TracingConfig,
     /// IRC client configuration
     pub irc: IrcConfig,
+    /// Permission configuration
+    #[serde(default)]
+    pub permissions: PermissionsConfig,
+    /// Plugin configuration
+    #[serde(default)]
+    pub plugins: PluginsConfig,
+    /// Channel logging configuration
+    #[serde(default)]
+    pub logger: LoggerConfig,
+    /// HTTP server configuration
+    #[serde(default)]
+    pub server: ServerConfig,
+    /// Outbound HTTP client configuration
+    #[serde(default)]
+    pub http: HttpConfig,
+    /// How plugin replies are styled
+    #[serde(default)]
+    pub theme: Theme,
+    /// Localisation configuration
+    #[serde(default)]
+    pub i18n: I18nConfig,
+    /// CTCP configuration
+    #[serde(default)]
+    pub ctcp: CtcpConfig,
 }
 
 /// Database connection configuration.
@@ -42,8 +81,427 @@ pub struct DnsConfig {
 /// Tracing and logging configuration.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct TracingConfig {
-    /// Enable tracing
+    /// Enable exporting traces over OTLP
+    pub enabled: bool,
+    /// The protocol to use when exporting to the OTLP collector
+    #[serde(default)]
+    pub protocol: OtlpProtocol,
+    /// The base URL of the OTLP collector. If unset, the `OTEL_EXPORTER_OTLP_*` environment
+    /// variables or the exporter defaults are used
+    pub endpoint: Option<String>,
+    /// Headers to send with every export request, e.g. for authentication
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// The ratio of traces to sample, between 0.0 and 1.0. Child spans follow the sampling
+    /// decision of their parent
+    #[serde(default = "default_sampling_ratio")]
+    pub sampling_ratio: f64,
+    /// Also export logs over OTLP
+    #[serde(default)]
+    pub logs: bool,
+    /// Also export metrics over OTLP
+    #[serde(default)]
+    pub metrics: bool,
+    /// The format of the logs written to stdout
+    #[serde(default)]
+    pub format: TracingFormat,
+    /// The log level filter, using the same syntax as `RUST_LOG`. `RUST_LOG` takes precedence
+    #[serde(default = "default_tracing_filter")]
+    pub filter: String,
+}
+
+/// The protocol used to export telemetry to an OTLP collector.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OtlpProtocol {
+    /// gRPC
+    Grpc,
+    /// Protobuf over HTTP
+    #[default]
+    Http,
+}
+
+/// The format of logs written to stdout.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TracingFormat {
+    /// One JSON object per line
+    #[default]
+    Json,
+    /// Multi-line, human-readable output
+    Pretty,
+    /// Single-line, human-readable output
+    Compact,
+}
+
+/// Channel logging configuration.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LoggerConfig {
+    /// Enable channel logging
+    #[serde(default)]
+    pub enabled: bool,
+    /// The directory to write logs to, with a subdirectory per channel
+    #[serde(default = "default_log_directory")]
+    pub directory: PathBuf,
+    /// The format to write logs in
+    #[serde(default)]
+    pub format: LogFormat,
+    /// How long to keep logs for, unless overridden for a channel. Logs are kept forever if unset
+    #[serde(default, with = "humantime_serde::option")]
+    pub retention: Option<Duration>,
+    /// Channels that should never be logged
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Per-channel overrides
+    #[serde(default)]
+    pub channels: Vec<ChannelLoggerConfig>,
+}
+
+impl Default for LoggerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: default_log_directory(),
+            format: LogFormat::default(),
+            retention: None,
+            exclude: vec![],
+            channels: vec![],
+        }
+    }
+}
+
+/// The format of channel logs.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Plain text, compatible with irssi
+    #[default]
+    Irssi,
+    /// Tab-separated plain text, compatible with WeeChat
+    Weechat,
+    /// JSON Lines, one JSON object per event
+    Jsonl,
+}
+
+/// Per-channel logging configuration.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ChannelLoggerConfig {
+    /// Name of the channel
+    pub name: String,
+    /// How long to keep logs for this channel, instead of the global retention
+    #[serde(default, with = "humantime_serde::option")]
+    pub retention: Option<Duration>,
+}
+
+/// HTTP server configuration.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ServerConfig {
+    /// Enable the HTTP server
+    #[serde(default)]
     pub enabled: bool,
+    /// The address to listen on
+    #[serde(default = "default_server_address")]
+    pub address: SocketAddr,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: default_server_address(),
+        }
+    }
+}
+
+/// CTCP configuration.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CtcpConfig {
+    /// Reply to CTCP queries such as `VERSION` and `PING`
+    #[serde(default = "default_ctcp_enabled")]
+    pub enabled: bool,
+    /// The reply to `VERSION` queries, where `{version}` is replaced with the version of zeta
+    #[serde(default = "default_ctcp_version")]
+    pub version: String,
+    /// The number of queries answered in a burst before replies are rate limited
+    #[serde(default = "default_ctcp_burst")]
+    pub burst: u32,
+    /// How often another query may be answered once the burst is used up
+    #[serde(default = "default_ctcp_interval", with = "humantime_serde")]
+    pub interval: Duration,
+}
+
+impl Default for CtcpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_ctcp_enabled(),
+            version: default_ctcp_version(),
+            burst: DEFAULT_CTCP_BURST,
+            interval: DEFAULT_CTCP_INTERVAL,
+        }
+    }
+}
+
+/// Outbound HTTP client configuration, shared by all plugins.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HttpConfig {
+    /// The maximum number of responses to keep in the response cache. Set to 0 to disable caching
+    #[serde(default = "default_http_cache_capacity")]
+    pub cache_capacity: usize,
+    /// The maximum duration to cache responses for. Responses are only cached for as long as their
+    /// `Cache-Control: max-age` or `Expires` header says they're fresh
+    #[serde(default = "default_http_cache_ttl", with = "humantime_serde")]
+    pub cache_ttl: Duration,
+    /// The maximum size of a single cached response body, in bytes
+    #[serde(default = "default_http_cache_max_entry_size")]
+    pub cache_max_entry_size: usize,
+    /// The maximum number of times to retry a failed idempotent request
+    #[serde(default = "default_http_max_retries")]
+    pub max_retries: u32,
+    /// The initial delay between retries, doubled on every attempt
+    #[serde(default = "default_http_retry_backoff", with = "humantime_serde")]
+    pub retry_backoff: Duration,
+    /// The maximum delay before a retry. Requests whose `Retry-After` exceeds this aren't retried
+    #[serde(default = "default_http_max_retry_delay", with = "humantime_serde")]
+    pub max_retry_delay: Duration,
+    /// The default limits for each upstream host
+    #[serde(default)]
+    pub limits: HostLimitsConfig,
+    /// Per-host overrides of the limits, keyed by hostname
+    #[serde(default)]
+    pub hosts: HashMap<String, HostLimitsConfig>,
+    /// Restrictions on fetching user-supplied URLs
+    #[serde(default)]
+    pub fetch: FetchConfig,
+}
+
+/// Restrictions on fetching user-supplied URLs.
+///
+/// Loopback, link-local and private addresses are refused unless the host or network is
+/// allowlisted.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FetchConfig {
+    /// Hostnames that are allowed to resolve to otherwise refused addresses
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+    /// Networks that are allowed to be fetched, e.g. `10.0.0.0/8`
+    #[serde(default)]
+    pub allowed_networks: Vec<IpNet>,
+    /// The maximum number of redirects to follow
+    #[serde(default = "default_fetch_max_redirects")]
+    pub max_redirects: usize,
+    /// The default maximum number of response body bytes to read
+    #[serde(default = "default_fetch_max_bytes")]
+    pub max_bytes: usize,
+}
+
+impl Default for FetchConfig {
+    fn default() -> Self {
+        Self {
+            allowed_hosts: vec![],
+            allowed_networks: vec![],
+            max_redirects: default_fetch_max_redirects(),
+            max_bytes: default_fetch_max_bytes(),
+        }
+    }
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            cache_capacity: default_http_cache_capacity(),
+            cache_ttl: default_http_cache_ttl(),
+            cache_max_entry_size: default_http_cache_max_entry_size(),
+            max_retries: default_http_max_retries(),
+            retry_backoff: default_http_retry_backoff(),
+            max_retry_delay: default_http_max_retry_delay(),
+            limits: HostLimitsConfig::default(),
+            hosts: HashMap::new(),
+            fetch: FetchConfig::default(),
+        }
+    }
+}
+
+/// Limits on outbound HTTP requests to a single host.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HostLimitsConfig {
+    /// The maximum number of concurrent requests
+    #[serde(default = "default_http_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    /// The maximum number of requests per second. Unlimited if unset
+    #[serde(default)]
+    pub requests_per_second: Option<f64>,
+}
+
+impl Default for HostLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_requests: default_http_max_concurrent_requests(),
+            requests_per_second: None,
+        }
+    }
+}
+
+/// Permission configuration for privileged commands.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct PermissionsConfig {
+    /// Hostmasks (`nick!user@host`, with `*` and `?` wildcards) of users allowed to use
+    /// administrative commands.
+    #[serde(default)]
+    pub admins: Vec<String>,
+}
+
+/// Configuration for individual plugins.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct PluginsConfig {
+    /// Karma plugin configuration
+    #[serde(default)]
+    pub karma: KarmaConfig,
+    /// Channel moderation plugin configuration
+    #[serde(default)]
+    pub moderation: ModerationConfig,
+    /// Link unfurling plugin configuration
+    #[serde(default)]
+    pub unfurl: UnfurlConfig,
+    /// Configuration for how URLs in messages are handed to plugins
+    #[serde(default)]
+    pub urls: UrlsConfig,
+}
+
+/// Karma plugin configuration.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct KarmaConfig {
+    /// The minimum duration between karma changes from the same user in a channel
+    #[serde(default = "default_karma_cooldown", with = "humantime_serde")]
+    pub cooldown: Duration,
+}
+
+impl Default for KarmaConfig {
+    fn default() -> Self {
+        Self {
+            cooldown: DEFAULT_KARMA_COOLDOWN,
+        }
+    }
+}
+
+/// Channel moderation plugin configuration.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ModerationConfig {
+    /// The list mode used to mute users, e.g. `q` for quiets on Solanum, or `b` together with
+    /// an extban prefix on other servers
+    #[serde(default = "default_mute_mode")]
+    pub mute_mode: char,
+    /// The prefix of mute masks, e.g. `m:` on `InspIRCd` or `~quiet:` on `UnrealIRCd`
+    #[serde(default)]
+    pub mute_prefix: String,
+    /// The moderation policies of individual channels
+    #[serde(default)]
+    pub channels: Vec<ModerationPolicy>,
+}
+
+impl Default for ModerationConfig {
+    fn default() -> Self {
+        Self {
+            mute_mode: DEFAULT_MUTE_MODE,
+            mute_prefix: String::new(),
+            channels: vec![],
+        }
+    }
+}
+
+/// The moderation policy of a channel.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ModerationPolicy {
+    /// Name of the channel
+    pub name: String,
+    /// Hostmasks (`nick!user@host`, with wildcards) or services accounts (`$a:account`) of users
+    /// that are opped when they join
+    #[serde(default)]
+    pub auto_op: Vec<String>,
+    /// Hostmasks or services accounts of users that are voiced when they join
+    #[serde(default)]
+    pub auto_voice: Vec<String>,
+    /// The rate of messages a user can send before they're flooding
+    #[serde(default)]
+    pub flood: Option<FloodLimit>,
+    /// The number of times in a row a user can repeat the same message
+    #[serde(default)]
+    pub max_repeats: Option<usize>,
+    /// The share of uppercase letters in a message that counts as shouting
+    #[serde(default)]
+    pub caps: Option<CapsLimit>,
+    /// What to do to users that break a limit
+    #[serde(default)]
+    pub action: ModerationAction,
+    /// How long users are banned for when the action is `ban`
+    #[serde(default = "default_moderation_ban_duration", with = "humantime_serde")]
+    pub ban_duration: Duration,
+}
+
+/// A limit on how many messages a user can send within a duration.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct FloodLimit {
+    /// The maximum number of messages
+    pub lines: usize,
+    /// The duration the messages are counted over
+    #[serde(with = "humantime_serde")]
+    pub per: Duration,
+}
+
+/// A limit on the share of uppercase letters in a message.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct CapsLimit {
+    /// The share of uppercase letters, between 0 and 1, at which a message counts as shouting
+    pub ratio: f64,
+    /// The minimum number of letters in a message before it's checked
+    #[serde(default = "default_caps_min_length")]
+    pub min_length: usize,
+}
+
+/// What to do to users that break a moderation limit.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationAction {
+    /// Kick the user from the channel
+    #[default]
+    Kick,
+    /// Ban the user for the configured duration and kick them
+    Ban,
+}
+
+/// Configuration for how URLs in messages are handed to plugins.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct UrlsConfig {
+    /// The maximum number of URLs handled in a single message. Further URLs are ignored
+    #[serde(default = "default_max_urls_per_message")]
+    pub max_per_message: usize,
+}
+
+impl Default for UrlsConfig {
+    fn default() -> Self {
+        Self {
+            max_per_message: DEFAULT_MAX_URLS_PER_MESSAGE,
+        }
+    }
+}
+
+/// Link unfurling plugin configuration.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct UnfurlConfig {
+    /// Domains whose links are never unfurled, including their subdomains
+    #[serde(default)]
+    pub denied_domains: Vec<String>,
+    /// The maximum number of bytes of a page to read when looking for its title
+    #[serde(default = "default_unfurl_max_bytes")]
+    pub max_bytes: usize,
+}
+
+impl Default for UnfurlConfig {
+    fn default() -> Self {
+        Self {
+            denied_domains: vec![],
+            max_bytes: DEFAULT_UNFURL_MAX_BYTES,
+        }
+    }
 }
 
 /// Configuration for an individual IRC channel.
@@ -53,6 +511,20 @@ pub struct IrcChannelConfig {
     pub name: String,
     /// The shared key to access the channel.
     pub key: Option<String>,
+    /// The locale plugins reply in, unless a user has set their own language.
+    pub locale: Option<Locale>,
+    /// Whether to wait for `NickServ` to confirm that we're identified before joining the
+    /// channel.
+    #[serde(default)]
+    pub requires_identification: bool,
+}
+
+/// Localisation configuration.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct I18nConfig {
+    /// The locale plugins reply in, unless it's set for the channel or the user.
+    #[serde(default)]
+    pub default_locale: Locale,
 }
 
 /// TLS configuration for IRC connection.
@@ -85,15 +557,138 @@ pub struct IrcConfig {
     /// The client's real name.
     pub realname: Option<String>,
     /// Whether the client should use `NickServ` GHOST to reclaim its primary nickname if it is in
-    /// use.
+    /// use when connecting.
     #[serde(default)]
     pub should_ghost: bool,
+    /// The services package of the network, which decides how the primary nickname is reclaimed.
+    pub services: Option<ServicesPackage>,
+    /// How long to wait for services to identify us before joining the channels that require
+    /// identification anyway. They're only joined once we're identified if unset.
+    #[serde(default, with = "humantime_serde::option")]
+    pub identification_timeout: Option<Duration>,
+    /// Nickname recovery configuration.
+    #[serde(default)]
+    pub nick_recovery: NickRecoveryConfig,
+    /// The credentials to become an IRC operator with after connecting.
+    pub oper: Option<OperConfig>,
+    /// The user modes to set after connecting, e.g. `+iB`.
+    pub umodes: Option<String>,
+    /// Configuration for rejoining channels.
+    #[serde(default)]
+    pub rejoin: RejoinConfig,
+    /// Configuration for joining channels we're invited to.
+    #[serde(default)]
+    pub invites: InviteConfig,
     /// TLS configuration.
     pub tls: Option<IrcTlsConfig>,
     /// The client's username.
     pub username: Option<String>,
 }
 
+/// The services package of an IRC network.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServicesPackage {
+    /// Atheme, which reclaims nicknames with `REGAIN`.
+    Atheme,
+    /// Anope, which reclaims nicknames with `RECOVER`.
+    Anope,
+}
+
+impl ServicesPackage {
+    /// Returns the `NickServ` command that reclaims a nickname from whoever is using it.
+    #[must_use]
+    pub const fn reclaim_command(self) -> &'static str {
+        match self {
+            ServicesPackage::Atheme => "REGAIN",
+            ServicesPackage::Anope => "RECOVER",
+        }
+    }
+}
+
+/// Configuration for recovering the primary nickname while we're using an alternative one.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct NickRecoveryConfig {
+    /// Toggle nickname recovery.
+    #[serde(default = "default_nick_recovery_enabled")]
+    pub enabled: bool,
+    /// How often to check whether the primary nickname is available.
+    #[serde(default = "default_nick_recovery_interval", with = "humantime_serde")]
+    pub interval: Duration,
+    /// The longest delay between attempts to reclaim the primary nickname through services. The
+    /// delay starts at `interval` and doubles after every attempt that doesn't recover it.
+    #[serde(
+        default = "default_nick_recovery_max_backoff",
+        with = "humantime_serde"
+    )]
+    pub max_backoff: Duration,
+}
+
+impl Default for NickRecoveryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_nick_recovery_enabled(),
+            interval: DEFAULT_NICK_RECOVERY_INTERVAL,
+            max_backoff: DEFAULT_NICK_RECOVERY_MAX_BACKOFF,
+        }
+    }
+}
+
+/// Configuration for rejoining channels after being kicked or failing to join.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct RejoinConfig {
+    /// Toggle rejoining channels we're kicked from.
+    #[serde(default = "default_rejoin_on_kick")]
+    pub on_kick: bool,
+    /// How long to wait before rejoining a channel we're kicked from.
+    #[serde(default = "default_rejoin_kick_delay", with = "humantime_serde")]
+    pub kick_delay: Duration,
+    /// How long to wait before the first retry when a channel can't be joined because it's full,
+    /// invite-only, we're banned or the key is wrong. The delay doubles with each retry.
+    #[serde(default = "default_rejoin_retry_delay", with = "humantime_serde")]
+    pub retry_delay: Duration,
+    /// The longest delay between retries.
+    #[serde(default = "default_rejoin_max_retry_delay", with = "humantime_serde")]
+    pub max_retry_delay: Duration,
+    /// The number of times to retry joining a channel before giving up. Set to 0 to disable
+    /// retries.
+    #[serde(default = "default_rejoin_max_retries")]
+    pub max_retries: u32,
+}
+
+impl Default for RejoinConfig {
+    fn default() -> Self {
+        Self {
+            on_kick: default_rejoin_on_kick(),
+            kick_delay: DEFAULT_REJOIN_KICK_DELAY,
+            retry_delay: DEFAULT_REJOIN_RETRY_DELAY,
+            max_retry_delay: DEFAULT_REJOIN_MAX_RETRY_DELAY,
+            max_retries: DEFAULT_REJOIN_MAX_RETRIES,
+        }
+    }
+}
+
+/// Configuration for joining channels we're invited to.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct InviteConfig {
+    /// Toggle joining channels when invited by an admin. With the `database` feature, the
+    /// channels are remembered and joined again after reconnecting.
+    #[serde(default)]
+    pub enabled: bool,
+    /// The locale plugins reply in in the channels we're invited to, unless a user has set their
+    /// own language.
+    pub locale: Option<Locale>,
+}
+
+/// IRC operator credentials.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct OperConfig {
+    /// The name of the operator block.
+    pub name: String,
+    /// The password of the operator block.
+    pub password: String,
+}
+
 impl IrcConfig {
     /// Returns the port number to use for this IRC connection.
     #[must_use]
@@ -119,9 +714,11 @@ impl IrcConfig {
 impl From<IrcConfig> for irc::client::data::Config {
     fn from(config: IrcConfig) -> Self {
         let port = config.port();
+        // Channels that require identification are joined once services have confirmed it
         let channels: Vec<String> = config
             .channels
             .iter()
+            .filter(|channel| !channel.requires_identification)
             .map(|channel| channel.name.clone())
             .collect();
         // TODO: channel keys
@@ -136,6 +733,9 @@ impl From<IrcConfig> for irc::client::data::Config {
             channels,
             alt_nicks: config.alt_nicks,
             should_ghost: config.should_ghost,
+            ghost_sequence: config
+                .services
+                .map(|services| vec![services.reclaim_command().to_string()]),
             ..Default::default()
         }
     }
@@ -150,3 +750,158 @@ const fn default_max_db_connections() -> u32 {
 const fn default_db_idle_timeout() -> Duration {
     DEFAULT_DB_IDLE_TIMEOUT
 }
+
+/// Returns the default ratio of traces to sample.
+const fn default_sampling_ratio() -> f64 {
+    DEFAULT_TRACING_SAMPLING_RATIO
+}
+
+/// Returns the default log level filter.
+fn default_tracing_filter() -> String {
+    DEFAULT_TRACING_FILTER.to_string()
+}
+
+/// Returns whether the primary nickname is recovered by default.
+const fn default_nick_recovery_enabled() -> bool {
+    true
+}
+
+/// Returns the default interval between checks of whether the primary nickname is available.
+const fn default_nick_recovery_interval() -> Duration {
+    DEFAULT_NICK_RECOVERY_INTERVAL
+}
+
+/// Returns the default longest delay between attempts to reclaim the primary nickname.
+const fn default_nick_recovery_max_backoff() -> Duration {
+    DEFAULT_NICK_RECOVERY_MAX_BACKOFF
+}
+
+/// Returns whether channels we're kicked from are rejoined by default.
+const fn default_rejoin_on_kick() -> bool {
+    true
+}
+
+/// Returns the default delay before rejoining a channel we're kicked from.
+const fn default_rejoin_kick_delay() -> Duration {
+    DEFAULT_REJOIN_KICK_DELAY
+}
+
+/// Returns the default delay before the first retry of a failed join.
+const fn default_rejoin_retry_delay() -> Duration {
+    DEFAULT_REJOIN_RETRY_DELAY
+}
+
+/// Returns the default longest delay between retries of a failed join.
+const fn default_rejoin_max_retry_delay() -> Duration {
+    DEFAULT_REJOIN_MAX_RETRY_DELAY
+}
+
+/// Returns the default number of times to retry a failed join.
+const fn default_rejoin_max_retries() -> u32 {
+    DEFAULT_REJOIN_MAX_RETRIES
+}
+
+/// Returns whether CTCP queries are answered by default.
+const fn default_ctcp_enabled() -> bool {
+    true
+}
+
+/// Returns the default reply to CTCP `VERSION` queries.
+fn default_ctcp_version() -> String {
+    DEFAULT_CTCP_VERSION.to_string()
+}
+
+/// Returns the default number of CTCP queries answered in a burst.
+const fn default_ctcp_burst() -> u32 {
+    DEFAULT_CTCP_BURST
+}
+
+/// Returns the default interval at which CTCP queries are answered once a burst is used up.
+const fn default_ctcp_interval() -> Duration {
+    DEFAULT_CTCP_INTERVAL
+}
+
+/// Returns the default address for the HTTP server to listen on.
+const fn default_server_address() -> SocketAddr {
+    DEFAULT_SERVER_ADDRESS
+}
+
+/// Returns the default number of responses to keep in the HTTP response cache.
+const fn default_http_cache_capacity() -> usize {
+    DEFAULT_HTTP_CACHE_CAPACITY
+}
+
+/// Returns the default duration to cache HTTP responses for.
+const fn default_http_cache_ttl() -> Duration {
+    DEFAULT_HTTP_CACHE_TTL
+}
+
+/// Returns the default maximum size of a cached HTTP response body.
+const fn default_http_cache_max_entry_size() -> usize {
+    DEFAULT_HTTP_CACHE_MAX_ENTRY_SIZE
+}
+
+/// Returns the default number of times to retry a failed HTTP request.
+const fn default_http_max_retries() -> u32 {
+    DEFAULT_HTTP_MAX_RETRIES
+}
+
+/// Returns the default initial delay between HTTP request retries.
+const fn default_http_retry_backoff() -> Duration {
+    DEFAULT_HTTP_RETRY_BACKOFF
+}
+
+/// Returns the default maximum delay before retrying an HTTP request.
+const fn default_http_max_retry_delay() -> Duration {
+    DEFAULT_HTTP_MAX_RETRY_DELAY
+}
+
+/// Returns the default maximum number of concurrent HTTP requests to a single host.
+const fn default_http_max_concurrent_requests() -> usize {
+    DEFAULT_HTTP_MAX_CONCURRENT_REQUESTS
+}
+
+/// Returns the default maximum number of redirects to follow when fetching user-supplied URLs.
+const fn default_fetch_max_redirects() -> usize {
+    DEFAULT_FETCH_MAX_REDIRECTS
+}
+
+/// Returns the default maximum number of bytes to read when fetching user-supplied URLs.
+const fn default_fetch_max_bytes() -> usize {
+    DEFAULT_FETCH_MAX_BYTES
+}
+
+/// Returns the default directory to write channel logs to.
+fn default_log_directory() -> PathBuf {
+    PathBuf::from(DEFAULT_LOG_DIRECTORY)
+}
+
+/// Returns the default duration a user has to wait between karma changes.
+const fn default_karma_cooldown() -> Duration {
+    DEFAULT_KARMA_COOLDOWN
+}
+
+/// Returns the default maximum number of URLs handled in a single message.
+const fn default_max_urls_per_message() -> usize {
+    DEFAULT_MAX_URLS_PER_MESSAGE
+}
+
+/// Returns the default list mode used to mute users.
+const fn default_mute_mode() -> char {
+    DEFAULT_MUTE_MODE
+}
+
+/// Returns the default duration users are banned for by moderation policies.
+const fn default_moderation_ban_duration() -> Duration {
+    DEFAULT_MODERATION_BAN_DURATION
+}
+
+/// Returns the default minimum number of letters in a message before it's checked for caps.
+const fn default_caps_min_length() -> usize {
+    DEFAULT_CAPS_MIN_LENGTH
+}
+
+/// Returns the default maximum number of bytes of a page to read when unfurling a link.
+const fn default_unfurl_max_bytes() -> usize {
+    DEFAULT_UNFURL_MAX_BYTES
+}
@@ -1,3 +1,4 @@
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::time::Duration;
 
 /// The `User-Agent` header to send when issuing HTTP requests.
@@ -7,6 +8,39 @@ pub const HTTP_USER_AGENT: &str =
 /// The duration before a HTTP request times out.
 pub const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
 
+/// The number of per-host HTTP limiters to keep before idle ones are evicted.
+pub const HTTP_MAX_HOST_LIMITERS: usize = 256;
+
+/// The host label of metrics for requests to user-supplied URLs, which could be any host.
+pub const HTTP_UNTRUSTED_HOST_LABEL: &str = "untrusted";
+
+/// The default number of responses to keep in the HTTP response cache.
+pub const DEFAULT_HTTP_CACHE_CAPACITY: usize = 1024;
+
+/// The default duration to cache HTTP responses for.
+pub const DEFAULT_HTTP_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// The default maximum size of a cached HTTP response body, in bytes.
+pub const DEFAULT_HTTP_CACHE_MAX_ENTRY_SIZE: usize = 1024 * 1024;
+
+/// The default number of times to retry a failed idempotent HTTP request.
+pub const DEFAULT_HTTP_MAX_RETRIES: u32 = 2;
+
+/// The default initial delay between HTTP request retries.
+pub const DEFAULT_HTTP_RETRY_BACKOFF: Duration = Duration::from_millis(250);
+
+/// The default maximum delay before retrying an HTTP request.
+pub const DEFAULT_HTTP_MAX_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// The default maximum number of concurrent HTTP requests to a single host.
+pub const DEFAULT_HTTP_MAX_CONCURRENT_REQUESTS: usize = 4;
+
+/// The default maximum number of redirects to follow when fetching user-supplied URLs.
+pub const DEFAULT_FETCH_MAX_REDIRECTS: usize = 5;
+
+/// The default maximum number of bytes to read when fetching user-supplied URLs.
+pub const DEFAULT_FETCH_MAX_BYTES: usize = 2 * 1024 * 1024;
+
 /// The default value for the maximum number of connections the database connection pool will keep
 /// open at once.
 pub const DEFAULT_MAX_DB_CONNECTIONS: u32 = 5;
@@ -19,3 +53,64 @@ pub const DEFAULT_IRC_PORT: u16 = 6667;
 
 /// The port number to use for secure IRC connections when not otherwise specified.
 pub const DEFAULT_IRC_TLS_PORT: u16 = 6697;
+
+/// The interval between the ticks that are sent to plugins.
+pub const PLUGIN_TICK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The default interval between checks of whether our primary nickname is available.
+pub const DEFAULT_NICK_RECOVERY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The default longest delay between attempts to reclaim our primary nickname through services.
+pub const DEFAULT_NICK_RECOVERY_MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+/// The default delay before rejoining a channel we were kicked from.
+pub const DEFAULT_REJOIN_KICK_DELAY: Duration = Duration::from_secs(5);
+
+/// The default delay before the first retry of a join that failed.
+pub const DEFAULT_REJOIN_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// The default longest delay between retries of a join that failed.
+pub const DEFAULT_REJOIN_MAX_RETRY_DELAY: Duration = Duration::from_secs(30 * 60);
+
+/// The default number of times to retry a join that failed.
+pub const DEFAULT_REJOIN_MAX_RETRIES: u32 = 10;
+
+/// The default duration a user has to wait between giving karma in the same channel.
+pub const DEFAULT_KARMA_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// The default maximum number of URLs handled in a single message.
+pub const DEFAULT_MAX_URLS_PER_MESSAGE: usize = 3;
+
+/// The default list mode used to mute users.
+pub const DEFAULT_MUTE_MODE: char = 'q';
+
+/// The default duration users are banned for by moderation policies.
+pub const DEFAULT_MODERATION_BAN_DURATION: Duration = Duration::from_secs(10 * 60);
+
+/// The default minimum number of letters in a message before it's checked for excessive caps.
+pub const DEFAULT_CAPS_MIN_LENGTH: usize = 10;
+
+/// The default maximum number of bytes of a page to read when unfurling a link.
+pub const DEFAULT_UNFURL_MAX_BYTES: usize = 512 * 1024;
+
+/// The default directory channel logs are written to.
+pub const DEFAULT_LOG_DIRECTORY: &str = "logs";
+
+/// The default address the HTTP server listens on.
+pub const DEFAULT_SERVER_ADDRESS: SocketAddr =
+    SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 9184));
+
+/// The default reply to CTCP `VERSION` queries.
+pub const DEFAULT_CTCP_VERSION: &str = "zeta {version}";
+
+/// The default number of CTCP queries answered in a burst before replies are rate limited.
+pub const DEFAULT_CTCP_BURST: u32 = 5;
+
+/// The default interval at which another CTCP query may be answered once a burst is used up.
+pub const DEFAULT_CTCP_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The default ratio of traces to sample.
+pub const DEFAULT_TRACING_SAMPLING_RATIO: f64 = 1.0;
+
+/// The default log level filter when `RUST_LOG` isn't set.
+pub const DEFAULT_TRACING_FILTER: &str = "zeta=debug,reddit=debug,dendanskeordbog=debug";
@@ -1,8 +1,12 @@
+use std::sync::RwLock;
+
 use hickory_resolver::TokioResolver;
 
 use crate::Config;
 #[cfg(feature = "database")]
 use crate::database::Database;
+use crate::isupport::{CaseMapping, ISupport};
+use crate::state::{Channel, Tracker, User};
 
 /// Shared context for plugin invocations.
 pub struct Context {
@@ -13,21 +17,89 @@ pub struct Context {
     pub dns: TokioResolver,
     /// The bot configuration.
     pub config: Config,
+    /// Features advertised by the server we're connected to.
+    pub isupport: RwLock<ISupport>,
+    /// The state of the channels we're in.
+    pub(crate) state: RwLock<Tracker>,
+    /// Our current nickname.
+    pub(crate) nickname: RwLock<String>,
 }
 
 impl Context {
     /// Creates a new context.
     #[must_use]
-    pub const fn new(
+    pub fn new(
         #[cfg(feature = "database")] db: Database,
         dns: TokioResolver,
         config: Config,
     ) -> Self {
+        let nickname = RwLock::new(config.irc.nickname.clone());
+
         Self {
             #[cfg(feature = "database")]
             db,
             dns,
             config,
+            isupport: RwLock::new(ISupport::new()),
+            state: RwLock::new(Tracker::new()),
+            nickname,
         }
     }
+
+    /// Returns the case mapping used by the server.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is poisoned.
+    #[must_use]
+    pub fn casemapping(&self) -> CaseMapping {
+        self.isupport.read().unwrap().casemapping
+    }
+
+    /// Returns our current nickname.
+    ///
+    /// Unlike [`Client::current_nickname`](irc::client::Client::current_nickname), this follows
+    /// nickname changes after we've connected, such as when the primary nickname is recovered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is poisoned.
+    #[must_use]
+    pub fn nickname(&self) -> String {
+        self.nickname.read().unwrap().clone()
+    }
+
+    /// Returns a snapshot of the channel with the given name, if we're in it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is poisoned.
+    #[must_use]
+    pub fn channel(&self, name: &str) -> Option<Channel> {
+        let name = self.casemapping().fold(name);
+
+        self.state.read().unwrap().channel(&name).cloned()
+    }
+
+    /// Returns a snapshot of all the channels we're in.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is poisoned.
+    #[must_use]
+    pub fn channels(&self) -> Vec<Channel> {
+        self.state.read().unwrap().channels().cloned().collect()
+    }
+
+    /// Returns a snapshot of the user with the given nickname, if we share a channel with them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is poisoned.
+    #[must_use]
+    pub fn user(&self, nick: &str) -> Option<User> {
+        let nick = self.casemapping().fold(nick);
+
+        self.state.read().unwrap().user(&nick).cloned()
+    }
 }
@@ -0,0 +1,294 @@
+//! Client-To-Client Protocol (CTCP) messages.
+//!
+//! CTCP messages are `PRIVMSG`s whose text is wrapped in `\x01`, such as `\x01VERSION\x01`. Queries
+//! are answered with a `NOTICE` to the sender, and `ACTION`s (i.e. `/me`) are handed to plugins as
+//! their own event instead of as ordinary messages.
+//!
+//! Queries sent to a channel are ignored, and replies are rate limited with a token bucket, so a
+//! flood of queries can't make us flood the server in turn.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use irc::client::prelude::ChannelExt;
+use irc::proto::{Command, Message};
+use tracing::debug;
+use zeta_plugin::Client;
+
+use crate::config::CtcpConfig;
+
+/// The CTCP commands we reply to, as listed in replies to `CLIENTINFO`.
+const SUPPORTED: &str = "ACTION CLIENTINFO PING TIME VERSION";
+
+/// A parsed CTCP message.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Ctcp<'a> {
+    /// An action, i.e. `/me`, with its text.
+    Action(&'a str),
+    /// A request for the CTCP commands we support.
+    ClientInfo,
+    /// A ping with a token that's echoed back.
+    Ping(&'a str),
+    /// A request for our local time.
+    Time,
+    /// A request for our client name and version.
+    Version,
+    /// Any other CTCP message.
+    Other {
+        /// The CTCP command, e.g. `SOURCE`.
+        command: &'a str,
+        /// The parameters of the command.
+        params: &'a str,
+    },
+}
+
+/// Replies to CTCP queries.
+pub struct Responder {
+    /// The reply to `VERSION` queries.
+    version: String,
+    /// Limits how often we reply.
+    bucket: Mutex<TokenBucket>,
+}
+
+/// A token bucket that allows a burst of replies, and then one reply per interval.
+struct TokenBucket {
+    /// The maximum number of tokens.
+    capacity: f64,
+    /// The number of tokens added per second.
+    rate: f64,
+    /// The number of tokens available.
+    tokens: f64,
+    /// When the tokens were last refilled.
+    refilled_at: Instant,
+}
+
+impl Ctcp<'_> {
+    /// Parses the text of a `PRIVMSG` as a CTCP message.
+    ///
+    /// Returns `None` if the text isn't a CTCP message. The trailing `\x01` is optional, since
+    /// some clients leave it out.
+    #[must_use]
+    pub fn parse(text: &str) -> Option<Ctcp<'_>> {
+        let inner = text.strip_prefix('\x01')?;
+        let inner = inner.strip_suffix('\x01').unwrap_or(inner);
+        let (command, params) = inner.split_once(' ').unwrap_or((inner, ""));
+
+        Some(match command.to_ascii_uppercase().as_str() {
+            "ACTION" => Ctcp::Action(params),
+            "CLIENTINFO" => Ctcp::ClientInfo,
+            "PING" => Ctcp::Ping(params),
+            "TIME" => Ctcp::Time,
+            "VERSION" => Ctcp::Version,
+            _ => Ctcp::Other { command, params },
+        })
+    }
+}
+
+/// Returns the CTCP message of `message`, if it's a `PRIVMSG` with one.
+#[must_use]
+pub fn from_message(message: &Message) -> Option<Ctcp<'_>> {
+    match message.command {
+        Command::PRIVMSG(_, ref text) => Ctcp::parse(text),
+        _ => None,
+    }
+}
+
+/// Returns the text of `message` if it's an action, i.e. `/me`.
+#[must_use]
+pub fn action(message: &Message) -> Option<&str> {
+    match from_message(message)? {
+        Ctcp::Action(text) => Some(text),
+        _ => None,
+    }
+}
+
+impl Responder {
+    /// Creates a responder from the CTCP configuration.
+    #[must_use]
+    pub fn new(config: &CtcpConfig) -> Responder {
+        let version = config
+            .version
+            .replace("{version}", env!("CARGO_PKG_VERSION"));
+
+        let bucket = Mutex::new(TokenBucket::new(
+            config.burst,
+            config.interval,
+            Instant::now(),
+        ));
+
+        Responder { version, bucket }
+    }
+
+    /// Returns the reply to `ctcp`, without the `\x01` delimiters, or `None` if it's not a query
+    /// we answer.
+    #[must_use]
+    pub fn reply(&self, ctcp: &Ctcp<'_>) -> Option<String> {
+        match ctcp {
+            Ctcp::ClientInfo => Some(format!("CLIENTINFO {SUPPORTED}")),
+            Ctcp::Ping(token) => Some(format!("PING {token}")),
+            Ctcp::Time => Some(format!(
+                "TIME {}",
+                jiff::Zoned::now().strftime("%a, %d %b %Y %H:%M:%S %z")
+            )),
+            Ctcp::Version => Some(format!("VERSION {}", self.version)),
+            Ctcp::Action(_) | Ctcp::Other { .. } => None,
+        }
+    }
+
+    /// Replies to `ctcp` from the sender of `message`, if it's a query we answer.
+    ///
+    /// Queries sent to a channel and queries over the rate limit are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the reply could not be sent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is poisoned.
+    pub fn respond(
+        &self,
+        client: &Client,
+        message: &Message,
+        ctcp: &Ctcp<'_>,
+    ) -> Result<(), irc::error::Error> {
+        if let Command::PRIVMSG(ref target, _) = message.command
+            && target.is_channel_name()
+        {
+            debug!(%target, "ignoring ctcp query to channel");
+
+            return Ok(());
+        }
+
+        if let Some(nick) = message.source_nickname()
+            && let Some(reply) = self.reply(ctcp)
+        {
+            if !self.bucket.lock().unwrap().take(Instant::now()) {
+                debug!(%nick, "ignoring ctcp query over the rate limit");
+
+                return Ok(());
+            }
+
+            client.send_notice(nick, format!("\x01{reply}\x01"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TokenBucket {
+    /// Creates a full bucket of `burst` tokens that's refilled with a token every `interval`.
+    fn new(burst: u32, interval: Duration, now: Instant) -> TokenBucket {
+        let capacity = f64::from(burst.max(1));
+        // A zero interval refills the bucket as soon as any time has passed
+        let rate = if interval.is_zero() {
+            f64::MAX
+        } else {
+            1.0 / interval.as_secs_f64()
+        };
+
+        TokenBucket {
+            capacity,
+            rate,
+            tokens: capacity,
+            refilled_at: now,
+        }
+    }
+
+    /// Takes a token if one is available, and returns whether it did.
+    fn take(&mut self, now: Instant) -> bool {
+        let elapsed = now
+            .saturating_duration_since(self.refilled_at)
+            .as_secs_f64();
+
+        self.tokens = elapsed.mul_add(self.rate, self.tokens).min(self.capacity);
+        self.refilled_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn responder() -> Responder {
+        Responder::new(&CtcpConfig {
+            enabled: true,
+            version: "zeta {version}".to_string(),
+            ..CtcpConfig::default()
+        })
+    }
+
+    #[test]
+    fn it_should_parse_ctcp_messages() {
+        assert_eq!(
+            Ctcp::parse("\x01ACTION waves\x01"),
+            Some(Ctcp::Action("waves"))
+        );
+        assert_eq!(Ctcp::parse("\x01version\x01"), Some(Ctcp::Version));
+        assert_eq!(Ctcp::parse("\x01PING 1234"), Some(Ctcp::Ping("1234")));
+        assert_eq!(
+            Ctcp::parse("\x01SOURCE\x01"),
+            Some(Ctcp::Other {
+                command: "SOURCE",
+                params: ""
+            })
+        );
+        assert_eq!(Ctcp::parse("hello"), None);
+    }
+
+    #[test]
+    fn it_should_reply_to_queries() {
+        let responder = responder();
+
+        assert_eq!(
+            responder.reply(&Ctcp::Version),
+            Some(format!("VERSION zeta {}", env!("CARGO_PKG_VERSION")))
+        );
+        assert_eq!(
+            responder.reply(&Ctcp::Ping("1234")),
+            Some("PING 1234".to_string())
+        );
+        assert_eq!(
+            responder.reply(&Ctcp::ClientInfo),
+            Some(format!("CLIENTINFO {SUPPORTED}"))
+        );
+        assert!(
+            responder
+                .reply(&Ctcp::Time)
+                .is_some_and(|reply| reply.starts_with("TIME "))
+        );
+        assert_eq!(responder.reply(&Ctcp::Action("waves")), None);
+    }
+
+    #[test]
+    fn it_should_limit_replies_to_a_burst() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2, Duration::from_secs(2), start);
+
+        assert!(bucket.take(start));
+        assert!(bucket.take(start));
+        assert!(!bucket.take(start));
+        assert!(!bucket.take(start + Duration::from_secs(1)));
+        assert!(bucket.take(start + Duration::from_secs(2)));
+        assert!(!bucket.take(start + Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn it_should_refill_up_to_the_burst() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2, Duration::from_secs(2), start);
+        let later = start + Duration::from_secs(60);
+
+        assert!(bucket.take(later));
+        assert!(bucket.take(later));
+        assert!(!bucket.take(later));
+    }
+}
@@ -1,10 +1,17 @@
 use sqlx::{
     migrate::Migrator,
-    postgres::{PgPool, PgPoolOptions},
+    postgres::{PgConnection, PgPool, PgPoolOptions},
 };
 
 use crate::Error;
 
+pub mod bans;
+pub mod channels;
+pub mod karma;
+pub mod preferences;
+pub mod quotes;
+pub mod url_history;
+
 static MIGRATOR: Migrator = sqlx::migrate!();
 
 /// Database connection pool.,
@@ -26,12 +33,25 @@ pub async fn connect(url: &str, config: &crate::config::DbConfig) -> Result<Data
     Ok(pool)
 }
 
+/// Creates a connection pool that doesn't connect to the database until it's first used.
+///
+/// # Errors
+///
+/// If the url is invalid, `Err(Error::OpenDatabase)` is returned.
+pub fn connect_lazy(url: &str, config: &crate::config::DbConfig) -> Result<Database, Error> {
+    PgPoolOptions::new()
+        .max_connections(config.max_connections)
+        .idle_timeout(config.idle_timeout)
+        .connect_lazy(url)
+        .map_err(Error::OpenDatabase)
+}
+
 /// Applies migrations to the database.
 ///
 /// # Errors
 ///
-/// If a connection cannot be acquired from the connection pool, `Error::AcquireDatabaseConnection`
-/// is returned.
+/// If a connection cannot be acquired from the connection pool, `Error::DatabasePool` is
+/// returned.
 ///
 /// If an error occurs during migration, `Error::DatabaseMigration` is returned.
 pub async fn migrate(pool: Database) -> Result<(), Error> {
@@ -42,3 +62,44 @@ pub async fn migrate(pool: Database) -> Result<(), Error> {
         .await
         .map_err(Error::DatabaseMigration)
 }
+
+/// Reverts the migrations newer than `target`, or only the latest applied migration if `target`
+/// is `None`, and returns the version the database was reverted to.
+///
+/// # Errors
+///
+/// If a connection cannot be acquired from the connection pool, `Error::DatabasePool` is
+/// returned.
+///
+/// If the applied migrations can't be read, `Error::DatabaseQueryFailed` is returned.
+///
+/// If an error occurs while reverting, `Error::DatabaseMigration` is returned.
+pub async fn revert(pool: Database, target: Option<i64>) -> Result<i64, Error> {
+    let mut conn = pool.acquire().await.map_err(Error::DatabasePool)?;
+    let target = match target {
+        Some(target) => target,
+        None => previous_version(&mut conn).await?,
+    };
+
+    MIGRATOR
+        .undo(&mut conn, target)
+        .await
+        .map_err(Error::DatabaseMigration)?;
+
+    Ok(target)
+}
+
+/// Returns the version of the applied migration before the latest one, or 0 if there's at most
+/// one.
+///
+/// The versions are read from the database rather than the migrations compiled into the binary,
+/// which may be older or newer than the database.
+async fn previous_version(conn: &mut PgConnection) -> Result<i64, Error> {
+    let version: Option<i64> = sqlx::query_scalar(
+        "SELECT version FROM _sqlx_migrations WHERE success ORDER BY version DESC LIMIT 1 OFFSET 1",
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(version.unwrap_or(0))
+}
@@ -0,0 +1,89 @@
+//! Channel bans and mutes that are lifted automatically.
+
+use sqlx::FromRow;
+use time::OffsetDateTime;
+
+use super::Database;
+
+/// A ban or mute set by the bot.
+#[derive(Clone, Debug, FromRow)]
+pub struct Ban {
+    /// The folded name of the channel.
+    pub channel: String,
+    /// The list mode of the ban, e.g. `b` or `q`.
+    pub mode: String,
+    /// The banned mask, e.g. `*!*@example.com`.
+    pub mask: String,
+}
+
+/// Records a ban of `mask` in `channel`, replacing any existing ban of it with the same mode.
+///
+/// Bans without an expiry are recorded so they show up as set by the bot, but they're never
+/// lifted automatically.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn add(
+    db: &Database,
+    channel: &str,
+    mode: char,
+    mask: &str,
+    reason: Option<&str>,
+    set_by: &str,
+    expires_at: Option<OffsetDateTime>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO bans (channel, mode, mask, reason, set_by, expires_at)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (channel, mode, mask) DO UPDATE
+         SET reason = EXCLUDED.reason, set_by = EXCLUDED.set_by,
+             expires_at = EXCLUDED.expires_at, created_at = now()",
+    )
+    .bind(channel)
+    .bind(mode.to_string())
+    .bind(mask)
+    .bind(reason)
+    .bind(set_by)
+    .bind(expires_at)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Forgets the ban of `mask` with `mode` in `channel`, if there is one.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn remove(
+    db: &Database,
+    channel: &str,
+    mode: char,
+    mask: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM bans WHERE channel = $1 AND mode = $2 AND mask = $3")
+        .bind(channel)
+        .bind(mode.to_string())
+        .bind(mask)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Removes and returns the bans in `channels` that have expired.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn take_expired(db: &Database, channels: &[String]) -> Result<Vec<Ban>, sqlx::Error> {
+    sqlx::query_as(
+        "DELETE FROM bans WHERE expires_at <= now() AND channel = ANY($1)
+         RETURNING channel, mode, mask",
+    )
+    .bind(channels)
+    .fetch_all(db)
+    .await
+}
@@ -0,0 +1,54 @@
+//! Channels joined on invitation, which are joined again after reconnecting.
+
+use super::Database;
+
+/// Records that we were invited to `name` by `invited_by`.
+///
+/// `key` is the folded name of the channel.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn add(
+    db: &Database,
+    key: &str,
+    name: &str,
+    invited_by: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO channels (key, name, invited_by) VALUES ($1, $2, $3)
+         ON CONFLICT (key) DO UPDATE SET name = EXCLUDED.name, invited_by = EXCLUDED.invited_by",
+    )
+    .bind(key)
+    .bind(name)
+    .bind(invited_by)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Forgets the channel with the folded name `key`, if it's recorded.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn remove(db: &Database, key: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM channels WHERE key = $1")
+        .bind(key)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Returns the names of all recorded channels.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn all(db: &Database) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT name FROM channels ORDER BY created_at")
+        .fetch_all(db)
+        .await
+}
@@ -0,0 +1,137 @@
+//! Per-channel karma storage.
+
+use sqlx::FromRow;
+
+use super::Database;
+
+/// The karma score of a subject in a channel.
+#[derive(Clone, Debug, FromRow)]
+pub struct Entry {
+    /// The subject as it was last written by a user.
+    pub display_name: String,
+    /// The current score.
+    pub score: i64,
+}
+
+/// Adjusts the karma of `subject` in `channel` by `delta` and records who changed it and why.
+///
+/// `subject` must already be normalized, while `display_name` is the form that was written by
+/// the user. Returns the new score.
+///
+/// # Errors
+///
+/// Returns an error if the query fails, in which case the score is left unchanged.
+pub async fn adjust(
+    db: &Database,
+    channel: &str,
+    subject: &str,
+    display_name: &str,
+    giver: &str,
+    delta: i16,
+    reason: Option<&str>,
+) -> Result<i64, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let (score,): (i64,) = sqlx::query_as(
+        "INSERT INTO karma (channel, subject, display_name, score) VALUES ($1, $2, $3, $4)
+         ON CONFLICT (channel, subject) DO UPDATE
+         SET score = karma.score + EXCLUDED.score,
+             display_name = EXCLUDED.display_name,
+             updated_at = now()
+         RETURNING score",
+    )
+    .bind(channel)
+    .bind(subject)
+    .bind(display_name)
+    .bind(i64::from(delta))
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO karma_events (channel, subject, giver, delta, reason)
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(channel)
+    .bind(subject)
+    .bind(giver)
+    .bind(delta)
+    .bind(reason)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(score)
+}
+
+/// Returns the karma of `subject` in `channel`, if it has any.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn get(
+    db: &Database,
+    channel: &str,
+    subject: &str,
+) -> Result<Option<Entry>, sqlx::Error> {
+    sqlx::query_as("SELECT display_name, score FROM karma WHERE channel = $1 AND subject = $2")
+        .bind(channel)
+        .bind(subject)
+        .fetch_optional(db)
+        .await
+}
+
+/// Returns the most recent reasons given for karma changes to `subject` in `channel`.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn recent_reasons(
+    db: &Database,
+    channel: &str,
+    subject: &str,
+    limit: i64,
+) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT reason FROM karma_events
+         WHERE channel = $1 AND subject = $2 AND reason IS NOT NULL
+         ORDER BY created_at DESC LIMIT $3",
+    )
+    .bind(channel)
+    .bind(subject)
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+/// Returns the subjects with the highest karma in `channel`, highest first.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn top(db: &Database, channel: &str, limit: i64) -> Result<Vec<Entry>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT display_name, score FROM karma WHERE channel = $1
+         ORDER BY score DESC, updated_at DESC LIMIT $2",
+    )
+    .bind(channel)
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+/// Returns the subjects with the lowest karma in `channel`, lowest first.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn bottom(db: &Database, channel: &str, limit: i64) -> Result<Vec<Entry>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT display_name, score FROM karma WHERE channel = $1
+         ORDER BY score ASC, updated_at DESC LIMIT $2",
+    )
+    .bind(channel)
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
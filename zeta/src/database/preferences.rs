@@ -0,0 +1,77 @@
+//! Per-user preference storage.
+
+use super::Database;
+
+/// Returns all preferences saved by the owner, as `(key, value)` pairs.
+///
+/// `owner_kind` is either `account` or `nick`, and `owner` must already be normalized.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn all(
+    db: &Database,
+    owner_kind: &str,
+    owner: &str,
+) -> Result<Vec<(String, String)>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT key, value FROM user_preferences WHERE owner_kind = $1 AND owner = $2
+         ORDER BY key",
+    )
+    .bind(owner_kind)
+    .bind(owner)
+    .fetch_all(db)
+    .await
+}
+
+/// Saves the preference `key` as `value` for the owner, replacing any previous value.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn set(
+    db: &Database,
+    owner_kind: &str,
+    owner: &str,
+    key: &str,
+    value: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO user_preferences (owner_kind, owner, key, value) VALUES ($1, $2, $3, $4)
+         ON CONFLICT (owner_kind, owner, key) DO UPDATE
+         SET value = EXCLUDED.value, updated_at = now()",
+    )
+    .bind(owner_kind)
+    .bind(owner)
+    .bind(key)
+    .bind(value)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Deletes the preference `key` for the owner.
+///
+/// Returns whether the preference was set.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn unset(
+    db: &Database,
+    owner_kind: &str,
+    owner: &str,
+    key: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "DELETE FROM user_preferences WHERE owner_kind = $1 AND owner = $2 AND key = $3",
+    )
+    .bind(owner_kind)
+    .bind(owner)
+    .bind(key)
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
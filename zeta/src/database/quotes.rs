@@ -0,0 +1,203 @@
+//! Per-channel quote storage.
+
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use time::OffsetDateTime;
+
+use super::Database;
+
+/// A stored quote.
+#[derive(Clone, Debug, FromRow)]
+pub struct Quote {
+    /// Unique identifier of the quote.
+    pub id: i64,
+    /// The channel the quote belongs to.
+    pub channel: String,
+    /// The nickname of the user who added the quote.
+    pub author: String,
+    /// The quoted text.
+    pub content: String,
+    /// When the quote was added.
+    pub created_at: OffsetDateTime,
+}
+
+/// The portable representation of a quote, used for export and import.
+///
+/// Quotes are exported as JSON Lines with one record per line. Identifiers are not part of the
+/// format, so imported quotes are assigned new ones.
+#[derive(Clone, Debug, Eq, PartialEq, FromRow, Deserialize, Serialize)]
+pub struct QuoteRecord {
+    /// The channel the quote belongs to.
+    pub channel: String,
+    /// The nickname of the user who added the quote.
+    pub author: String,
+    /// The quoted text.
+    pub content: String,
+    /// When the quote was added.
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+/// Adds a new quote to `channel` and returns it.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn insert(
+    db: &Database,
+    channel: &str,
+    author: &str,
+    content: &str,
+) -> Result<Quote, sqlx::Error> {
+    sqlx::query_as(
+        "INSERT INTO quotes (channel, author, content) VALUES ($1, $2, $3)
+         RETURNING id, channel, author, content, created_at",
+    )
+    .bind(channel)
+    .bind(author)
+    .bind(content)
+    .fetch_one(db)
+    .await
+}
+
+/// Returns the quote with the given `id` in `channel`, if it exists.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn get(db: &Database, channel: &str, id: i64) -> Result<Option<Quote>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT id, channel, author, content, created_at FROM quotes
+         WHERE channel = $1 AND id = $2",
+    )
+    .bind(channel)
+    .bind(id)
+    .fetch_optional(db)
+    .await
+}
+
+/// Returns a random quote from `channel`, if there are any.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn random(db: &Database, channel: &str) -> Result<Option<Quote>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT id, channel, author, content, created_at FROM quotes
+         WHERE channel = $1 ORDER BY random() LIMIT 1",
+    )
+    .bind(channel)
+    .fetch_optional(db)
+    .await
+}
+
+/// Performs a full-text search for quotes in `channel`, best matches first.
+///
+/// The query uses the `websearch_to_tsquery` syntax, so quoted phrases, `or` and `-word` are
+/// supported.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn search(
+    db: &Database,
+    channel: &str,
+    query: &str,
+    limit: i64,
+) -> Result<Vec<Quote>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT id, channel, author, content, created_at FROM quotes
+         WHERE channel = $1 AND search @@ websearch_to_tsquery('simple', $2)
+         ORDER BY ts_rank(search, websearch_to_tsquery('simple', $2)) DESC, id
+         LIMIT $3",
+    )
+    .bind(channel)
+    .bind(query)
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+/// Deletes the quote with the given `id` from `channel`.
+///
+/// Returns `true` if a quote was deleted.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn delete(db: &Database, channel: &str, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM quotes WHERE channel = $1 AND id = $2")
+        .bind(channel)
+        .bind(id)
+        .execute(db)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Returns all quotes, optionally limited to a single `channel`, in the order they were added.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn export(db: &Database, channel: Option<&str>) -> Result<Vec<QuoteRecord>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT channel, author, content, created_at FROM quotes
+         WHERE $1::TEXT IS NULL OR channel = $1 ORDER BY id",
+    )
+    .bind(channel)
+    .fetch_all(db)
+    .await
+}
+
+/// Imports the given quotes in a single transaction, returning the number of quotes imported.
+///
+/// # Errors
+///
+/// Returns an error if any of the inserts fail, in which case nothing is imported.
+pub async fn import(db: &Database, records: &[QuoteRecord]) -> Result<u64, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let mut count = 0;
+
+    for record in records {
+        sqlx::query(
+            "INSERT INTO quotes (channel, author, content, created_at) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(&record.channel)
+        .bind(&record.author)
+        .bind(&record.content)
+        .bind(record.created_at)
+        .execute(&mut *tx)
+        .await?;
+
+        count += 1;
+    }
+
+    tx.commit().await?;
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn quote_record_round_trips_as_json() {
+        let record = QuoteRecord {
+            channel: "#zeta".to_string(),
+            author: "mk".to_string(),
+            content: "<mk> hello world".to_string(),
+            created_at: datetime!(2026-10-18 12:00:00 UTC),
+        };
+        let json = serde_json::to_string(&record).unwrap();
+
+        assert_eq!(
+            json,
+            r##"{"channel":"#zeta","author":"mk","content":"<mk> hello world","created_at":"2026-10-18T12:00:00Z"}"##
+        );
+        assert_eq!(serde_json::from_str::<QuoteRecord>(&json).unwrap(), record);
+    }
+}
@@ -0,0 +1,107 @@
+//! History of URLs posted in channels.
+
+use sqlx::FromRow;
+use time::OffsetDateTime;
+
+use super::Database;
+
+/// A URL that was posted in a channel.
+#[derive(Clone, Debug, FromRow)]
+pub struct Post {
+    /// The URL as it was posted.
+    pub original_url: String,
+    /// The nickname of the user who posted the URL.
+    pub poster: String,
+    /// When the URL was posted.
+    pub posted_at: OffsetDateTime,
+}
+
+/// Returns the first time the normalized `url` was posted in `channel`, if ever.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn first_post(
+    db: &Database,
+    channel: &str,
+    url: &str,
+) -> Result<Option<Post>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT original_url, poster, posted_at FROM url_history
+         WHERE channel = $1 AND url = $2 ORDER BY posted_at LIMIT 1",
+    )
+    .bind(channel)
+    .bind(url)
+    .fetch_optional(db)
+    .await
+}
+
+/// Records that `poster` posted `original_url` in `channel`.
+///
+/// `url` is the normalized form of `original_url` that is used to detect reposts.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn record(
+    db: &Database,
+    channel: &str,
+    url: &str,
+    original_url: &str,
+    poster: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO url_history (channel, url, original_url, poster) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(channel)
+    .bind(url)
+    .bind(original_url)
+    .bind(poster)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Returns the most recently posted URLs in `channel`, optionally only those containing `query`.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn search(
+    db: &Database,
+    channel: &str,
+    query: Option<&str>,
+    limit: i64,
+) -> Result<Vec<Post>, sqlx::Error> {
+    let pattern = query.map(|query| format!("%{}%", escape_like(query)));
+
+    sqlx::query_as(
+        "SELECT original_url, poster, posted_at FROM url_history
+         WHERE channel = $1 AND ($2::TEXT IS NULL OR original_url ILIKE $2)
+         ORDER BY posted_at DESC LIMIT $3",
+    )
+    .bind(channel)
+    .bind(pattern)
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+/// Escapes the special characters of a `LIKE` pattern.
+fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_escape_like_patterns() {
+        assert_eq!(escape_like("100%_sure\\"), "100\\%\\_sure\\\\");
+        assert_eq!(escape_like("example.com"), "example.com");
+    }
+}
@@ -31,6 +31,9 @@ pub enum Error {
     /// General IRC communication error.
     #[error("IRC error")]
     Irc(#[from] IrcError),
+    /// Failed to bind the HTTP server to its address.
+    #[error("Could not bind HTTP server")]
+    BindServer(#[source] std::io::Error),
     /// Plugin system error.
     #[error("Plugin error: {0}")]
     Plugin(#[from] PluginError),
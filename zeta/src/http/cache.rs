@@ -0,0 +1,448 @@
+//! In-memory response cache.
+//!
+//! Only responses that say how long they're fresh for, with `Cache-Control: max-age` or
+//! `Expires`, are cached, and for at most the configured TTL.
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use futures::{StreamExt, future, stream};
+use hyper::body::Bytes;
+use jiff::Timestamp;
+use lru::LruCache;
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Body, Method, Request, Response, ResponseBuilderExt, StatusCode};
+use url::Url;
+
+use crate::config::HttpConfig;
+
+/// Identifies a cached response by the URL and headers of the request.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct CacheKey {
+    /// The full request URL.
+    url: String,
+    /// A hash of the request headers.
+    headers: u64,
+}
+
+/// A cached response.
+#[derive(Clone, Debug)]
+struct Entry {
+    /// The final URL of the response, after redirects.
+    url: Url,
+    /// The response status.
+    status: StatusCode,
+    /// The response headers.
+    headers: HeaderMap,
+    /// The response body.
+    body: Bytes,
+    /// When the entry expires.
+    expires_at: Instant,
+}
+
+/// A TTL/LRU cache of successful `GET` responses.
+pub struct ResponseCache {
+    /// The cached entries, or `None` if caching is disabled.
+    entries: Option<Mutex<LruCache<CacheKey, Entry>>>,
+    /// The maximum duration to cache a response for.
+    ttl: Duration,
+    /// The maximum size of a cached response body.
+    max_entry_size: usize,
+}
+
+impl CacheKey {
+    /// Returns the cache key for `request`, or `None` if the request isn't cacheable.
+    ///
+    /// Only `GET` requests are cached, and requests can opt out of the cache by sending
+    /// `Cache-Control: no-cache` or `no-store`.
+    pub fn for_request(request: &Request) -> Option<CacheKey> {
+        let headers = request.headers();
+
+        if request.method() != Method::GET
+            || has_directive(headers, "no-cache")
+            || has_directive(headers, "no-store")
+        {
+            return None;
+        }
+
+        let mut headers: Vec<_> = headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_bytes()))
+            .collect();
+        let mut hasher = DefaultHasher::new();
+
+        headers.sort_unstable();
+        headers.hash(&mut hasher);
+
+        Some(CacheKey {
+            url: request.url().to_string(),
+            headers: hasher.finish(),
+        })
+    }
+}
+
+impl ResponseCache {
+    /// Creates a new response cache with the configured capacity and limits.
+    pub fn new(config: &HttpConfig) -> ResponseCache {
+        ResponseCache {
+            entries: NonZeroUsize::new(config.cache_capacity)
+                .map(|capacity| Mutex::new(LruCache::new(capacity))),
+            ttl: config.cache_ttl,
+            max_entry_size: config.cache_max_entry_size,
+        }
+    }
+
+    /// Returns whether the cache is enabled.
+    pub const fn is_enabled(&self) -> bool {
+        self.entries.is_some()
+    }
+
+    /// Returns a copy of the cached response for `key`, if it's cached and fresh.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is poisoned.
+    pub fn get(&self, key: &CacheKey) -> Option<Response> {
+        let mut entries = self.entries.as_ref()?.lock().unwrap();
+        let entry = entries.get(key)?;
+
+        if entry.expires_at <= Instant::now() {
+            entries.pop(key);
+
+            return None;
+        }
+
+        Some(entry.to_response())
+    }
+
+    /// Caches `response` under `key` if it's cacheable, and returns a response with the same
+    /// contents.
+    ///
+    /// The body of a cacheable response has to be read to be cached, so this fails if reading it
+    /// fails. Bodies larger than the maximum entry size are only read up to that size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is poisoned.
+    pub async fn store(&self, key: CacheKey, response: Response) -> reqwest::Result<Response> {
+        let Some(entries) = &self.entries else {
+            return Ok(response);
+        };
+        let Some(ttl) = self.ttl_for(&response) else {
+            return Ok(response);
+        };
+
+        let mut response = response;
+        let url = response.url().clone();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let mut body = Vec::new();
+
+        while let Some(chunk) = response.chunk().await? {
+            body.extend_from_slice(&chunk);
+
+            // The body is too large to cache, so pass what we've read so far along with the rest
+            // of the body
+            if body.len() > self.max_entry_size {
+                let head = stream::once(future::ready(Ok(Bytes::from(body))));
+                let body = Body::wrap_stream(head.chain(response.bytes_stream()));
+
+                return Ok(build_response(&url, status, headers, body));
+            }
+        }
+
+        let entry = Entry {
+            url,
+            status,
+            headers,
+            body: Bytes::from(body),
+            expires_at: Instant::now() + ttl,
+        };
+        let response = entry.to_response();
+
+        entries.lock().unwrap().put(key, entry);
+
+        Ok(response)
+    }
+
+    /// Returns how long `response` may be cached for, or `None` if it can't be cached.
+    ///
+    /// Responses are only cached if they say how long they're fresh for, and for at most the
+    /// configured TTL.
+    fn ttl_for(&self, response: &Response) -> Option<Duration> {
+        let headers = response.headers();
+
+        if response.status() != StatusCode::OK
+            || headers.contains_key(header::SET_COOKIE)
+            || has_directive(headers, "no-store")
+            || has_directive(headers, "no-cache")
+            || response
+                .content_length()
+                .is_some_and(|len| len > self.max_entry_size as u64)
+        {
+            return None;
+        }
+
+        let ttl = max_age(headers).or_else(|| expires(headers))?.min(self.ttl);
+
+        (!ttl.is_zero()).then_some(ttl)
+    }
+}
+
+impl Entry {
+    /// Builds a response from the cached entry.
+    fn to_response(&self) -> Response {
+        build_response(
+            &self.url,
+            self.status,
+            self.headers.clone(),
+            self.body.clone(),
+        )
+    }
+}
+
+/// Builds a response from its parts.
+///
+/// The URL has to be set explicitly, since [`Response::url`] of a response built from parts is a
+/// placeholder otherwise.
+///
+/// # Panics
+///
+/// Panics if the response can't be built, which can't happen since the parts are already valid.
+fn build_response(
+    url: &Url,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: impl Into<Body>,
+) -> Response {
+    let mut builder = hyper::Response::builder().status(status).url(url.clone());
+
+    if let Some(response_headers) = builder.headers_mut() {
+        *response_headers = headers;
+    }
+
+    Response::from(
+        builder
+            .body(body.into())
+            .expect("could not build response from valid parts"),
+    )
+}
+
+/// Returns whether the `Cache-Control` header has the given directive.
+fn has_directive(headers: &HeaderMap, directive: &str) -> bool {
+    cache_control(headers).any(|d| d.eq_ignore_ascii_case(directive))
+}
+
+/// Returns the `max-age` directive of the `Cache-Control` header.
+fn max_age(headers: &HeaderMap) -> Option<Duration> {
+    cache_control(headers)
+        .find_map(|directive| {
+            let (name, value) = directive.split_once('=')?;
+
+            name.trim()
+                .eq_ignore_ascii_case("max-age")
+                .then(|| value.trim().parse().ok())
+                .flatten()
+        })
+        .map(Duration::from_secs)
+}
+
+/// Returns how long until the response expires according to the `Expires` header.
+///
+/// The expiry is relative to the `Date` header if there is one, so a skewed clock on either side
+/// doesn't matter. Invalid dates, such as `0`, mean that the response has already expired.
+fn expires(headers: &HeaderMap) -> Option<Duration> {
+    let date = |name: header::HeaderName| -> Option<Timestamp> {
+        let value = headers.get(name)?.to_str().ok()?;
+
+        jiff::fmt::rfc2822::parse(value.trim())
+            .ok()
+            .map(|date| date.timestamp())
+    };
+    let value = headers.get(header::EXPIRES)?;
+    let Some(expires) = date(header::EXPIRES) else {
+        return (!value.is_empty()).then_some(Duration::ZERO);
+    };
+    let now = date(header::DATE).unwrap_or_else(Timestamp::now);
+
+    Some(Duration::try_from(expires.duration_since(now)).unwrap_or_default())
+}
+
+/// Returns the directives of the `Cache-Control` headers.
+fn cache_control(headers: &HeaderMap) -> impl Iterator<Item = &str> {
+    headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    fn config() -> HttpConfig {
+        HttpConfig {
+            cache_capacity: 2,
+            cache_ttl: Duration::from_secs(60),
+            cache_max_entry_size: 16,
+            ..HttpConfig::default()
+        }
+    }
+
+    fn request(method: Method, url: &str) -> Request {
+        Request::new(method, url.parse().unwrap())
+    }
+
+    fn response(status: u16, cache_control: Option<&'static str>, body: &'static str) -> Response {
+        let mut headers = HeaderMap::new();
+
+        if let Some(value) = cache_control {
+            headers.insert(header::CACHE_CONTROL, HeaderValue::from_static(value));
+        }
+
+        response_with_headers(status, headers, body)
+    }
+
+    fn response_with_headers(status: u16, headers: HeaderMap, body: &'static str) -> Response {
+        build_response(
+            &"https://example.com/a".parse().unwrap(),
+            StatusCode::from_u16(status).unwrap(),
+            headers,
+            body,
+        )
+    }
+
+    #[test]
+    fn it_should_only_key_cacheable_requests() {
+        let get = request(Method::GET, "https://example.com/a");
+        let post = request(Method::POST, "https://example.com/a");
+        let mut no_cache = request(Method::GET, "https://example.com/a");
+
+        no_cache
+            .headers_mut()
+            .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+
+        assert!(CacheKey::for_request(&get).is_some());
+        assert!(CacheKey::for_request(&post).is_none());
+        assert!(CacheKey::for_request(&no_cache).is_none());
+    }
+
+    #[test]
+    fn it_should_key_requests_by_headers() {
+        let plain = request(Method::GET, "https://example.com/a");
+        let mut authorized = request(Method::GET, "https://example.com/a");
+
+        authorized
+            .headers_mut()
+            .insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer x"));
+
+        assert_ne!(
+            CacheKey::for_request(&plain),
+            CacheKey::for_request(&authorized)
+        );
+    }
+
+    #[test]
+    fn it_should_respect_cache_control() {
+        let cache = ResponseCache::new(&config());
+
+        assert_eq!(cache.ttl_for(&response(200, None, "")), None);
+        assert_eq!(
+            cache.ttl_for(&response(200, Some("public, max-age=10"), "")),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(
+            cache.ttl_for(&response(200, Some("max-age=3600"), "")),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(cache.ttl_for(&response(200, Some("max-age=0"), "")), None);
+        assert_eq!(cache.ttl_for(&response(200, Some("no-store"), "")), None);
+        assert_eq!(cache.ttl_for(&response(404, Some("max-age=10"), "")), None);
+    }
+
+    #[test]
+    fn it_should_respect_expires() {
+        let cache = ResponseCache::new(&config());
+        let headers = |expires: &'static str| {
+            let mut headers = HeaderMap::new();
+
+            headers.insert(
+                header::DATE,
+                HeaderValue::from_static("Sun, 18 Oct 2026 12:00:00 GMT"),
+            );
+            headers.insert(header::EXPIRES, HeaderValue::from_static(expires));
+            headers
+        };
+
+        assert_eq!(
+            cache.ttl_for(&response_with_headers(
+                200,
+                headers("Sun, 18 Oct 2026 12:00:30 GMT"),
+                ""
+            )),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            cache.ttl_for(&response_with_headers(
+                200,
+                headers("Mon, 19 Oct 2026 12:00:00 GMT"),
+                ""
+            )),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            cache.ttl_for(&response_with_headers(
+                200,
+                headers("Sun, 18 Oct 2026 11:00:00 GMT"),
+                ""
+            )),
+            None
+        );
+        assert_eq!(
+            cache.ttl_for(&response_with_headers(200, headers("0"), "")),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn it_should_cache_responses() {
+        let cache = ResponseCache::new(&config());
+        let key = CacheKey::for_request(&request(Method::GET, "https://example.com/a")).unwrap();
+
+        assert!(cache.get(&key).is_none());
+
+        let stored = cache
+            .store(key.clone(), response(200, Some("max-age=10"), "hello"))
+            .await
+            .unwrap();
+
+        assert_eq!(stored.text().await.unwrap(), "hello");
+
+        let cached = cache.get(&key).unwrap();
+
+        assert_eq!(cached.status(), StatusCode::OK);
+        assert_eq!(cached.url().as_str(), "https://example.com/a");
+        assert_eq!(cached.text().await.unwrap(), "hello");
+    }
+
+    #[tokio::test]
+    async fn it_should_not_cache_large_responses() {
+        let cache = ResponseCache::new(&config());
+        let key = CacheKey::for_request(&request(Method::GET, "https://example.com/a")).unwrap();
+        let body = "this body is larger than sixteen bytes";
+
+        let stored = cache
+            .store(key.clone(), response(200, Some("max-age=10"), body))
+            .await
+            .unwrap();
+
+        assert_eq!(stored.text().await.unwrap(), body);
+        assert!(cache.get(&key).is_none());
+    }
+}
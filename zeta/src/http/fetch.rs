@@ -0,0 +1,563 @@
+//! Guarded fetching of user-supplied URLs.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+use hickory_resolver::TokioResolver;
+use hyper::body::Bytes;
+use ipnet::IpNet;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Method, StatusCode};
+use tracing::debug;
+use url::{Host, Url};
+
+use super::send_untrusted;
+use crate::config::FetchConfig;
+use crate::context::Context;
+
+/// Errors that can occur when fetching a user-supplied URL.
+#[derive(Debug, thiserror::Error)]
+pub enum FetchError {
+    /// The URL doesn't use HTTP or HTTPS.
+    #[error("unsupported url scheme {0:?}")]
+    UnsupportedScheme(String),
+    /// The URL has no host.
+    #[error("url has no host")]
+    MissingHost,
+    /// The host only resolves to refused addresses.
+    #[error("refusing to fetch {0}: address is not publicly routable")]
+    RefusedAddress(String),
+    /// The host couldn't be resolved.
+    #[error("could not resolve {host}: {source}")]
+    Resolve {
+        /// The host that couldn't be resolved.
+        host: String,
+        /// The resolver error.
+        #[source]
+        source: hickory_resolver::net::NetError,
+    },
+    /// The request failed.
+    #[error("request error: {0}")]
+    Request(#[from] reqwest::Error),
+    /// More redirects than allowed were returned.
+    #[error("too many redirects")]
+    TooManyRedirects,
+    /// A redirect was expected, but not returned.
+    #[error("expected a redirect, got status {0}")]
+    NotRedirected(StatusCode),
+    /// The `Location` header of a redirect is missing or invalid.
+    #[error("redirect has a missing or invalid location")]
+    InvalidRedirect,
+    /// The response has an unsuccessful status.
+    #[error("server returned status {0}")]
+    Status(StatusCode),
+    /// The response has a content type that isn't accepted.
+    #[error("unexpected content type {0:?}")]
+    UnexpectedContentType(String),
+    /// The response is larger than allowed.
+    #[error("response is larger than {0} bytes")]
+    TooLarge(usize),
+}
+
+/// Options for a single fetch.
+#[derive(Clone, Debug)]
+pub struct FetchOptions {
+    /// The request method.
+    pub method: Method,
+    /// Accepted content types, e.g. `text/html`. Entries ending with `/` match any subtype, e.g.
+    /// `image/`. Any content type is accepted if empty.
+    pub content_types: &'static [&'static str],
+    /// The maximum number of body bytes to read, or the configured default if unset.
+    pub max_bytes: Option<usize>,
+    /// Whether a body larger than `max_bytes` is truncated rather than refused.
+    pub truncate: bool,
+}
+
+/// A fetched response.
+#[derive(Clone, Debug)]
+pub struct Fetched {
+    /// The final URL, after following redirects.
+    pub url: Url,
+    /// The response status.
+    pub status: StatusCode,
+    /// The response headers.
+    pub headers: HeaderMap,
+    /// The response body, up to the maximum size.
+    pub body: Bytes,
+    /// Whether the body was truncated.
+    pub truncated: bool,
+}
+
+/// Decides which addresses may be fetched.
+#[derive(Debug)]
+struct AddressPolicy {
+    /// Hosts that may resolve to any address.
+    allowed_hosts: Vec<String>,
+    /// Networks that may be fetched even if they aren't publicly routable.
+    allowed_networks: Vec<IpNet>,
+}
+
+/// A DNS resolver that leaves out refused addresses, so that hosts can't resolve to a different,
+/// refused address between checking the URL and connecting.
+struct GuardedResolver {
+    /// The shared DNS resolver.
+    dns: TokioResolver,
+    /// The address policy.
+    policy: Arc<AddressPolicy>,
+}
+
+/// An HTTP client for fetching user-supplied URLs.
+///
+/// Requests go through the [shared HTTP layer](super), but without the response cache and trace
+/// context propagation, and never through a proxy. Hosts are resolved with [`Context::dns`] and
+/// refused if they only resolve to loopback, link-local, private or otherwise non-public
+/// addresses, unless they're allowlisted. Redirects are followed manually, so each hop is checked,
+/// and response bodies and content types are limited.
+pub struct GuardedClient {
+    /// The HTTP client, using the guarded resolver.
+    client: reqwest::Client,
+    /// The shared DNS resolver.
+    dns: TokioResolver,
+    /// The address policy.
+    policy: Arc<AddressPolicy>,
+    /// The maximum number of redirects to follow.
+    max_redirects: usize,
+    /// The default maximum number of body bytes to read.
+    max_bytes: usize,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            method: Method::GET,
+            content_types: &[],
+            max_bytes: None,
+            truncate: false,
+        }
+    }
+}
+
+impl Fetched {
+    /// Returns the media type of the response, without parameters, in lowercase.
+    #[must_use]
+    pub fn content_type(&self) -> Option<String> {
+        content_type(&self.headers)
+    }
+
+    /// Returns the body as text, replacing invalid UTF-8.
+    #[must_use]
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+impl AddressPolicy {
+    /// Creates the policy from the configuration.
+    fn new(config: &FetchConfig) -> AddressPolicy {
+        AddressPolicy {
+            allowed_hosts: config
+                .allowed_hosts
+                .iter()
+                .map(|host| host.to_ascii_lowercase())
+                .collect(),
+            allowed_networks: config.allowed_networks.clone(),
+        }
+    }
+
+    /// Returns whether `host` may be fetched at `ip`.
+    fn allows(&self, host: &str, ip: IpAddr) -> bool {
+        is_public(ip)
+            || self
+                .allowed_hosts
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(host))
+            || self
+                .allowed_networks
+                .iter()
+                .any(|network| network.contains(&ip))
+    }
+}
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let dns = self.dns.clone();
+        let policy = Arc::clone(&self.policy);
+
+        Box::pin(async move {
+            let host = name.as_str();
+            let lookup = dns.lookup_ip(host).await?;
+            let addrs: Vec<SocketAddr> = lookup
+                .iter()
+                .filter(|ip| policy.allows(host, *ip))
+                .map(|ip| SocketAddr::new(ip, 0))
+                .collect();
+
+            if addrs.is_empty() {
+                return Err(FetchError::RefusedAddress(host.to_string()).into());
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+impl GuardedClient {
+    /// Creates a new guarded client using the DNS resolver and configuration of `ctx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the HTTP client fails to build.
+    #[must_use]
+    pub fn new(ctx: &Context) -> GuardedClient {
+        let config = &ctx.config.http.fetch;
+        let policy = Arc::new(AddressPolicy::new(config));
+        let resolver = GuardedResolver {
+            dns: ctx.dns.clone(),
+            policy: Arc::clone(&policy),
+        };
+        // A proxy would resolve the host itself, bypassing the guarded resolver
+        let client = super::client::builder()
+            .dns_resolver(Arc::new(resolver))
+            .no_proxy()
+            .build()
+            .expect("could not build http client");
+
+        GuardedClient {
+            client,
+            dns: ctx.dns.clone(),
+            policy,
+            max_redirects: config.max_redirects,
+            max_bytes: config.max_bytes,
+        }
+    }
+
+    /// Fetches `url`, following redirects.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any URL along the way is refused, the request fails, too many
+    /// redirects are returned, or the final response is unsuccessful, has an unaccepted content
+    /// type or is too large.
+    pub async fn fetch(&self, url: &Url, options: &FetchOptions) -> Result<Fetched, FetchError> {
+        let max_bytes = options.max_bytes.unwrap_or(self.max_bytes);
+        let mut url = url.clone();
+        let mut redirects = 0;
+
+        let mut response = loop {
+            self.check(&url).await?;
+
+            let response =
+                send_untrusted(self.client.request(options.method.clone(), url.clone())).await?;
+
+            if !response.status().is_redirection() {
+                break response;
+            }
+
+            if redirects == self.max_redirects {
+                return Err(FetchError::TooManyRedirects);
+            }
+
+            url = location(&url, response.headers())?;
+            redirects += 1;
+            debug!(%url, redirects, "following redirect");
+        };
+
+        let status = response.status();
+
+        if !status.is_success() {
+            return Err(FetchError::Status(status));
+        }
+
+        if !options.content_types.is_empty() {
+            let content_type = content_type(response.headers()).unwrap_or_default();
+
+            if !is_accepted(&content_type, options.content_types) {
+                return Err(FetchError::UnexpectedContentType(content_type));
+            }
+        }
+
+        if !options.truncate
+            && response
+                .content_length()
+                .is_some_and(|len| len > max_bytes as u64)
+        {
+            return Err(FetchError::TooLarge(max_bytes));
+        }
+
+        let headers = response.headers().clone();
+        let mut body = Vec::new();
+        let mut truncated = false;
+
+        while let Some(chunk) = response.chunk().await? {
+            let remaining = max_bytes - body.len();
+
+            if chunk.len() > remaining {
+                if !options.truncate {
+                    return Err(FetchError::TooLarge(max_bytes));
+                }
+
+                body.extend_from_slice(&chunk[..remaining]);
+                truncated = true;
+
+                break;
+            }
+
+            body.extend_from_slice(&chunk);
+        }
+
+        Ok(Fetched {
+            url,
+            status,
+            headers,
+            body: Bytes::from(body),
+            truncated,
+        })
+    }
+
+    /// Sends a single `method` request to `url` and returns the location it redirects to,
+    /// without following it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `url` is refused, the request fails or the response isn't a redirect.
+    pub async fn redirect_location(&self, method: Method, url: &Url) -> Result<Url, FetchError> {
+        self.check(url).await?;
+
+        let response = send_untrusted(self.client.request(method, url.clone())).await?;
+
+        if !response.status().is_redirection() {
+            return Err(FetchError::NotRedirected(response.status()));
+        }
+
+        location(url, response.headers())
+    }
+
+    /// Returns an error if `url` isn't an HTTP(S) URL, or its host is refused.
+    ///
+    /// The resolver used for requests also leaves out refused addresses, but checking up front
+    /// gives a better error.
+    async fn check(&self, url: &Url) -> Result<(), FetchError> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(FetchError::UnsupportedScheme(url.scheme().to_string()));
+        }
+
+        let ips: Vec<IpAddr> = match url.host().ok_or(FetchError::MissingHost)? {
+            Host::Ipv4(ip) => vec![IpAddr::V4(ip)],
+            Host::Ipv6(ip) => vec![IpAddr::V6(ip)],
+            Host::Domain(domain) => self
+                .dns
+                .lookup_ip(domain)
+                .await
+                .map_err(|source| FetchError::Resolve {
+                    host: domain.to_string(),
+                    source,
+                })?
+                .iter()
+                .collect(),
+        };
+        let host = url.host_str().unwrap_or_default();
+
+        if ips.iter().any(|ip| self.policy.allows(host, *ip)) {
+            Ok(())
+        } else {
+            Err(FetchError::RefusedAddress(host.to_string()))
+        }
+    }
+}
+
+/// Returns whether `ip` is publicly routable, i.e. not loopback, link-local, private,
+/// multicast or reserved for some other use.
+///
+/// IPv6 addresses that embed an IPv4 address are judged by the IPv4 address.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+
+            !(ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_private()
+                || ip.is_multicast()
+                || ip.is_broadcast()
+                // 0.0.0.0/8, "this network"
+                || a == 0
+                // 100.64.0.0/10, carrier-grade NAT
+                || (a == 100 && (b & 0xc0) == 64)
+                // 198.18.0.0/15, benchmarking
+                || (a == 198 && (b & 0xfe) == 18)
+                // 240.0.0.0/4, reserved
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => match embedded_ipv4(ip) {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_unicast_link_local()
+                    || ip.is_unique_local()
+                    || ip.is_multicast())
+            }
+        },
+    }
+}
+
+/// Returns the IPv4 address embedded in `ip`, if it's an IPv4-mapped, NAT64 (`64:ff9b::/96`) or
+/// 6to4 (`2002::/16`) address.
+fn embedded_ipv4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    let octets = ip.octets();
+
+    match ip.segments() {
+        [0x64, 0xff9b, 0, 0, 0, 0, ..] => Some(Ipv4Addr::new(
+            octets[12], octets[13], octets[14], octets[15],
+        )),
+        [0x2002, ..] => Some(Ipv4Addr::new(octets[2], octets[3], octets[4], octets[5])),
+        _ => ip.to_ipv4_mapped(),
+    }
+}
+
+/// Returns the media type of `headers`, without parameters, in lowercase.
+fn content_type(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get(header::CONTENT_TYPE)?.to_str().ok()?;
+    let essence = value.split(';').next().unwrap_or_default().trim();
+
+    Some(essence.to_ascii_lowercase())
+}
+
+/// Returns whether `content_type` matches one of the `accepted` content types.
+fn is_accepted(content_type: &str, accepted: &[&str]) -> bool {
+    accepted.iter().any(|accepted| {
+        if accepted.ends_with('/') {
+            content_type.starts_with(accepted)
+        } else {
+            content_type == *accepted
+        }
+    })
+}
+
+/// Returns the redirect location in `headers`, resolved relative to `url`.
+fn location(url: &Url, headers: &HeaderMap) -> Result<Url, FetchError> {
+    headers
+        .get(header::LOCATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|location| url.join(location).ok())
+        .ok_or(FetchError::InvalidRedirect)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_refuse_non_public_addresses() {
+        let refused = [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "0.0.0.0",
+            "::1",
+            "fe80::1",
+            "fd00::1",
+            "::ffff:127.0.0.1",
+        ];
+
+        for ip in refused {
+            assert!(!is_public(ip.parse().unwrap()), "{ip}");
+        }
+
+        for ip in ["1.1.1.1", "2606:4700:4700::1111", "172.32.0.1"] {
+            assert!(is_public(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[test]
+    fn it_should_refuse_this_network() {
+        assert!(!is_public("0.1.2.3".parse().unwrap()));
+        assert!(is_public("1.0.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn it_should_refuse_carrier_grade_nat() {
+        assert!(!is_public("100.64.0.1".parse().unwrap()));
+        assert!(!is_public("100.127.255.254".parse().unwrap()));
+        assert!(is_public("100.63.255.255".parse().unwrap()));
+        assert!(is_public("100.128.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn it_should_refuse_benchmarking_networks() {
+        assert!(!is_public("198.18.0.1".parse().unwrap()));
+        assert!(!is_public("198.19.255.254".parse().unwrap()));
+        assert!(is_public("198.20.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn it_should_refuse_multicast_and_reserved() {
+        assert!(!is_public("224.0.0.1".parse().unwrap()));
+        assert!(!is_public("239.255.255.250".parse().unwrap()));
+        assert!(!is_public("240.0.0.1".parse().unwrap()));
+        assert!(!is_public("255.255.255.255".parse().unwrap()));
+        assert!(!is_public("ff02::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn it_should_check_nat64_addresses() {
+        assert!(!is_public("64:ff9b::7f00:1".parse().unwrap()));
+        assert!(!is_public("64:ff9b::a9fe:a9fe".parse().unwrap()));
+        assert!(is_public("64:ff9b::101:101".parse().unwrap()));
+    }
+
+    #[test]
+    fn it_should_check_6to4_addresses() {
+        assert!(!is_public("2002:7f00:1::1".parse().unwrap()));
+        assert!(!is_public("2002:c0a8:101::1".parse().unwrap()));
+        assert!(is_public("2002:101:101::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn it_should_allow_allowlisted_hosts_and_networks() {
+        let policy = AddressPolicy::new(&FetchConfig {
+            allowed_hosts: vec!["Intranet.example".to_string()],
+            allowed_networks: vec!["10.0.0.0/8".parse().unwrap()],
+            ..FetchConfig::default()
+        });
+        let private = "192.168.1.1".parse().unwrap();
+
+        assert!(policy.allows("intranet.example", private));
+        assert!(!policy.allows("example.com", private));
+        assert!(policy.allows("example.com", "10.1.2.3".parse().unwrap()));
+    }
+
+    #[test]
+    fn it_should_match_content_types() {
+        let mut headers = HeaderMap::new();
+
+        headers.insert(
+            header::CONTENT_TYPE,
+            "Text/HTML; charset=utf-8".parse().unwrap(),
+        );
+
+        let content_type = content_type(&headers).unwrap();
+
+        assert_eq!(content_type, "text/html");
+        assert!(is_accepted(&content_type, &["text/html"]));
+        assert!(is_accepted(&content_type, &["image/", "text/"]));
+        assert!(!is_accepted(&content_type, &["application/json"]));
+    }
+
+    #[test]
+    fn it_should_resolve_relative_locations() {
+        let url = Url::parse("https://vm.tiktok.com/abc/").unwrap();
+        let mut headers = HeaderMap::new();
+
+        headers.insert(header::LOCATION, "/@user/video/1".parse().unwrap());
+
+        assert_eq!(
+            location(&url, &headers).unwrap().as_str(),
+            "https://vm.tiktok.com/@user/video/1"
+        );
+        assert!(location(&url, &HeaderMap::new()).is_err());
+    }
+}
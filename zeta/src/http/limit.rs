@@ -0,0 +1,189 @@
+//! Per-host concurrency and rate limits.
+//!
+//! Limiters are created for every host a request is sent to, including hosts in URLs posted by
+//! users, so idle limiters are evicted once there are more than [`HTTP_MAX_HOST_LIMITERS`].
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+use crate::config::{HostLimitsConfig, HttpConfig};
+use crate::consts::HTTP_MAX_HOST_LIMITERS;
+
+/// Limits for a single host.
+struct HostLimiter {
+    /// Limits the number of concurrent requests.
+    semaphore: Arc<Semaphore>,
+    /// The minimum interval between the start of two requests, if rate limited.
+    interval: Option<Duration>,
+    /// The earliest time the next request may start.
+    next_start: Mutex<Instant>,
+}
+
+/// Per-host concurrency and rate limits.
+pub struct HostLimits {
+    /// The default limits.
+    default: HostLimitsConfig,
+    /// Per-host overrides.
+    overrides: HashMap<String, HostLimitsConfig>,
+    /// Limiters by host, created on first use.
+    limiters: Mutex<HashMap<String, Arc<HostLimiter>>>,
+}
+
+/// Permission to send a request, released when dropped.
+pub struct Permit {
+    _permit: OwnedSemaphorePermit,
+}
+
+impl HostLimiter {
+    /// Creates a new limiter with the given limits.
+    fn new(config: &HostLimitsConfig) -> HostLimiter {
+        HostLimiter {
+            semaphore: Arc::new(Semaphore::new(config.max_concurrent_requests.max(1))),
+            interval: config
+                .requests_per_second
+                .filter(|rate| rate.is_finite() && *rate > 0.0)
+                .map(|rate| Duration::from_secs_f64(1.0 / rate)),
+            next_start: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Waits until a request may be sent.
+    async fn acquire(&self) -> Permit {
+        let permit = Arc::clone(&self.semaphore)
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed");
+
+        if let Some(interval) = self.interval {
+            let start = {
+                let mut next_start = self.next_start.lock().unwrap();
+                let start = (*next_start).max(Instant::now());
+
+                *next_start = start + interval;
+
+                start
+            };
+
+            tokio::time::sleep_until(start).await;
+        }
+
+        Permit { _permit: permit }
+    }
+
+    /// Returns whether the limiter has no requests in flight or waiting, and no rate limit that
+    /// would delay the next request, so it can be replaced by a new one without any difference.
+    fn is_idle(self: &Arc<Self>, now: Instant) -> bool {
+        // Waiters hold a reference to the limiter, and permits hold one to the semaphore
+        Arc::strong_count(self) == 1
+            && Arc::strong_count(&self.semaphore) == 1
+            && *self.next_start.lock().unwrap() <= now
+    }
+}
+
+impl HostLimits {
+    /// Creates the per-host limits from the configuration.
+    pub fn new(config: &HttpConfig) -> HostLimits {
+        let overrides = config
+            .hosts
+            .iter()
+            .map(|(host, limits)| (host.to_ascii_lowercase(), limits.clone()))
+            .collect();
+
+        HostLimits {
+            default: config.limits.clone(),
+            overrides,
+            limiters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Waits until a request to `host` may be sent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is poisoned.
+    pub async fn acquire(&self, host: &str) -> Permit {
+        let limiter = {
+            let mut limiters = self.limiters.lock().unwrap();
+
+            if limiters.len() >= HTTP_MAX_HOST_LIMITERS && !limiters.contains_key(host) {
+                let now = Instant::now();
+
+                limiters.retain(|_, limiter| !limiter.is_idle(now));
+            }
+
+            Arc::clone(limiters.entry(host.to_string()).or_insert_with(|| {
+                let config = self.overrides.get(host).unwrap_or(&self.default);
+
+                Arc::new(HostLimiter::new(config))
+            }))
+        };
+
+        limiter.acquire().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(max_concurrent_requests: usize, requests_per_second: Option<f64>) -> HostLimits {
+        HostLimits::new(&HttpConfig {
+            limits: HostLimitsConfig {
+                max_concurrent_requests,
+                requests_per_second,
+            },
+            ..HttpConfig::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn it_should_limit_concurrency_per_host() {
+        let limits = limits(1, None);
+        let permit = limits.acquire("example.com").await;
+
+        // Other hosts aren't affected
+        let _other = limits.acquire("example.org").await;
+
+        let blocked =
+            tokio::time::timeout(Duration::from_millis(20), limits.acquire("example.com")).await;
+        assert!(blocked.is_err());
+
+        drop(permit);
+
+        let unblocked =
+            tokio::time::timeout(Duration::from_millis(20), limits.acquire("example.com")).await;
+        assert!(unblocked.is_ok());
+    }
+
+    #[tokio::test]
+    async fn it_should_evict_idle_limiters() {
+        let limits = limits(1, None);
+        let _busy = limits.acquire("example.com").await;
+
+        for i in 0..HTTP_MAX_HOST_LIMITERS * 2 {
+            drop(limits.acquire(&format!("{i}.example.org")).await);
+        }
+
+        let limiters = limits.limiters.lock().unwrap();
+
+        assert!(limiters.len() <= HTTP_MAX_HOST_LIMITERS);
+        // Limiters with requests in flight are kept
+        assert!(limiters.contains_key("example.com"));
+    }
+
+    #[tokio::test]
+    async fn it_should_space_out_rate_limited_requests() {
+        let limits = limits(4, Some(20.0));
+        let started = Instant::now();
+
+        for _ in 0..3 {
+            drop(limits.acquire("example.com").await);
+        }
+
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,155 @@
+//! Retries with exponential backoff.
+
+use std::time::Duration;
+
+use jiff::Timestamp;
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Method, StatusCode};
+
+use crate::config::HttpConfig;
+
+/// When and how often to retry failed requests.
+pub struct RetryPolicy {
+    /// The maximum number of retries.
+    pub max_retries: u32,
+    /// The initial delay between retries.
+    backoff: Duration,
+    /// The maximum delay before a retry.
+    max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Creates a new retry policy from the configuration.
+    pub const fn new(config: &HttpConfig) -> RetryPolicy {
+        RetryPolicy {
+            max_retries: config.max_retries,
+            backoff: config.retry_backoff,
+            max_delay: config.max_retry_delay,
+        }
+    }
+
+    /// Returns the delay before retry number `attempt` (starting at 0), or `None` if the server
+    /// asked us to wait for longer than the maximum delay.
+    ///
+    /// The server's `retry_after` is used if present, otherwise the delay is the backoff doubled
+    /// for every attempt, with jitter.
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if let Some(retry_after) = retry_after {
+            return (retry_after <= self.max_delay).then_some(retry_after);
+        }
+
+        let delay = self
+            .backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+
+        Some(delay.mul_f64(rand::random_range(0.5..=1.0)))
+    }
+}
+
+/// Returns whether requests with `method` can safely be retried.
+pub fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE | Method::TRACE
+    )
+}
+
+/// Returns whether a response with `status` is worth retrying.
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+        || (status.is_server_error()
+            && status != StatusCode::NOT_IMPLEMENTED
+            && status != StatusCode::HTTP_VERSION_NOT_SUPPORTED)
+}
+
+/// Returns the delay requested by the `Retry-After` header, given either as a number of seconds
+/// or as an HTTP date.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(header::RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = jiff::fmt::rfc2822::parse(value).ok()?.timestamp();
+    let delay = date.duration_since(Timestamp::now());
+
+    Some(Duration::try_from(delay).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::new(&HttpConfig {
+            retry_backoff: Duration::from_millis(100),
+            max_retry_delay: Duration::from_secs(1),
+            ..HttpConfig::default()
+        })
+    }
+
+    #[test]
+    fn it_should_back_off_exponentially() {
+        let policy = policy();
+
+        for (attempt, max) in [
+            (0, 100),
+            (1, 200),
+            (2, 400),
+            (3, 800),
+            (4, 1000),
+            (40, 1000),
+        ] {
+            let delay = policy.delay(attempt, None).unwrap();
+            let max = Duration::from_millis(max);
+
+            assert!(delay <= max && delay >= max / 2, "{attempt}: {delay:?}");
+        }
+    }
+
+    #[test]
+    fn it_should_honor_retry_after() {
+        let policy = policy();
+
+        assert_eq!(
+            policy.delay(0, Some(Duration::from_millis(500))),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(policy.delay(0, Some(Duration::from_secs(60))), None);
+    }
+
+    #[test]
+    fn it_should_parse_retry_after() {
+        let mut headers = HeaderMap::new();
+
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(header::RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(120)));
+
+        headers.insert(
+            header::RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert(header::RETRY_AFTER, HeaderValue::from_static("soon"));
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn it_should_classify_statuses() {
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::BAD_GATEWAY));
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_retryable_status(StatusCode::NOT_IMPLEMENTED));
+        assert!(!is_retryable_status(StatusCode::NOT_FOUND));
+        assert!(is_idempotent(&Method::GET));
+        assert!(!is_idempotent(&Method::POST));
+    }
+}
@@ -0,0 +1,444 @@
+//! Localisation of plugin output.
+//!
+//! Messages are looked up by key in a catalogue per [`Locale`]. The catalogues in `locales/` use
+//! the basic message syntax of [Fluent](https://projectfluent.org): one `key = value` message per
+//! line, `#` comments, and `{ $name }` placeables that are replaced by the argument `name`.
+//! Select expressions aren't supported - plurals are written as separate `key-one` and
+//! `key-other` messages and picked with [`Locale::plural`].
+//!
+//! The locale of a message is the `language` preference of the user who sent it, falling back to
+//! the locale configured for the channel and then to the default locale. Channels that aren't
+//! configured were joined on invitation, and use the locale configured for invited channels.
+//!
+//! Plugins understand the phrases of every locale, so only their replies depend on the locale.
+
+use std::collections::HashMap;
+use std::fmt::{self, Write as _};
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+use irc::client::prelude::ChannelExt;
+use irc::proto::Message;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use zeta_plugin::reply::Reply;
+
+use crate::config::{Config, IrcConfig};
+use crate::context::Context;
+use crate::isupport::CaseMapping;
+use crate::preferences::{self, UserPreferences};
+
+/// Arguments for the placeables of a message.
+pub type Args<'a> = [(&'a str, &'a dyn fmt::Display)];
+
+/// The message catalogues, in the order of [`Locale::ALL`].
+static CATALOGUES: LazyLock<[Catalogue; 2]> = LazyLock::new(|| {
+    [
+        Catalogue::parse(include_str!("../locales/en.ftl")),
+        Catalogue::parse(include_str!("../locales/da.ftl")),
+    ]
+});
+
+/// Builds [`Args`] from `name = value` pairs.
+///
+/// ```ignore
+/// locale.format("isitopen-opens-at", args!(nick = nick, name = place.name, opening = time))
+/// ```
+macro_rules! args {
+    ($($name:ident = $value:expr),* $(,)?) => {
+        &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),*]
+    };
+}
+
+pub(crate) use args;
+
+/// A locale that plugin output can be translated to.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub enum Locale {
+    /// English.
+    #[serde(rename = "en")]
+    English,
+    /// Danish.
+    #[default]
+    #[serde(rename = "da")]
+    Danish,
+}
+
+/// Errors that can occur when parsing a locale.
+#[derive(Debug, thiserror::Error)]
+#[error("unsupported locale {0:?}, expected one of: en, da")]
+pub struct UnsupportedLocale(String);
+
+/// The messages of a single locale.
+struct Catalogue {
+    /// Message templates by key.
+    messages: HashMap<&'static str, &'static str>,
+}
+
+/// A part of a message template.
+#[derive(Debug, Eq, PartialEq)]
+enum Piece<'a> {
+    /// Literal text.
+    Text(&'a str),
+    /// The name of an argument.
+    Placeable(&'a str),
+}
+
+impl Locale {
+    /// All supported locales.
+    pub const ALL: [Locale; 2] = [Locale::English, Locale::Danish];
+
+    /// Returns the language tag of the locale.
+    #[must_use]
+    pub const fn tag(self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::Danish => "da",
+        }
+    }
+
+    /// Returns the locale for a language tag such as `da` or `en-GB`, if it's supported.
+    ///
+    /// Only the primary language subtag is considered.
+    #[must_use]
+    pub fn from_tag(tag: &str) -> Option<Locale> {
+        let language = tag.split(['-', '_']).next()?;
+
+        Locale::ALL
+            .into_iter()
+            .find(|locale| locale.tag().eq_ignore_ascii_case(language))
+    }
+
+    /// Returns the template of the message with the given key, falling back to English.
+    #[must_use]
+    pub fn message(self, key: &str) -> Option<&'static str> {
+        self.catalogue()
+            .get(key)
+            .or_else(|| Locale::English.catalogue().get(key))
+    }
+
+    /// Formats the message with the given key.
+    ///
+    /// Messages that don't exist in any locale are formatted as their key, so a missing
+    /// translation is noticeable without breaking the plugin.
+    #[must_use]
+    pub fn format(self, key: &str, args: &Args<'_>) -> String {
+        let Some(template) = self.message(key) else {
+            warn!(%key, locale = self.tag(), "missing message");
+
+            return key.to_string();
+        };
+
+        let mut formatted = String::with_capacity(template.len());
+
+        for piece in pieces(template) {
+            match piece {
+                Piece::Text(text) => formatted.push_str(text),
+                Piece::Placeable(name) => match argument(args, name) {
+                    Some(value) => {
+                        let _ = write!(formatted, "{value}");
+                    }
+                    None => formatted.push_str(name),
+                },
+            }
+        }
+
+        formatted
+    }
+
+    /// Appends the message with the given key to `reply`, with its arguments as values.
+    pub fn reply(self, mut reply: Reply, key: &str, args: &Args<'_>) -> Reply {
+        let Some(template) = self.message(key) else {
+            warn!(%key, locale = self.tag(), "missing message");
+
+            return reply.text(key);
+        };
+
+        for piece in pieces(template) {
+            reply = match piece {
+                Piece::Text(text) => reply.text(text),
+                Piece::Placeable(name) => match argument(args, name) {
+                    Some(value) => reply.value(value),
+                    None => reply.text(name),
+                },
+            };
+        }
+
+        reply
+    }
+
+    /// Formats the message `{key}-one` if `count` is one, and `{key}-other` otherwise, with
+    /// `count` as an argument.
+    #[must_use]
+    pub fn plural(self, key: &str, count: i64) -> String {
+        let key = if count == 1 {
+            format!("{key}-one")
+        } else {
+            format!("{key}-other")
+        };
+
+        self.format(&key, args!(count = count))
+    }
+
+    /// Joins `items` into a sentence, e.g. `a, b and c`.
+    #[must_use]
+    pub fn list<S: AsRef<str>>(self, items: &[S]) -> String {
+        match items {
+            [] => String::new(),
+            [item] => item.as_ref().to_string(),
+            [first, last] => self.format(
+                "list-pair",
+                args!(first = first.as_ref(), last = last.as_ref()),
+            ),
+            [rest @ .., last] => {
+                let rest: Vec<&str> = rest.iter().map(AsRef::as_ref).collect();
+
+                self.format(
+                    "list-many",
+                    args!(rest = rest.join(", "), last = last.as_ref()),
+                )
+            }
+        }
+    }
+
+    /// Returns the catalogue of the locale.
+    fn catalogue(self) -> &'static Catalogue {
+        let idx = Locale::ALL
+            .iter()
+            .position(|locale| *locale == self)
+            .unwrap_or_default();
+
+        &CATALOGUES[idx]
+    }
+}
+
+impl FromStr for Locale {
+    type Err = UnsupportedLocale;
+
+    fn from_str(s: &str) -> Result<Locale, UnsupportedLocale> {
+        Locale::from_tag(s).ok_or_else(|| UnsupportedLocale(s.to_string()))
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.tag())
+    }
+}
+
+impl Catalogue {
+    /// Parses a catalogue.
+    ///
+    /// Lines that aren't comments or messages are ignored.
+    fn parse(source: &'static str) -> Catalogue {
+        let messages = source
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .filter_map(|line| {
+                let (key, value) = line.split_once('=')?;
+                let key = key.trim();
+                let is_valid = key.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && key
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+                is_valid.then(|| (key, value.trim()))
+            })
+            .collect();
+
+        Catalogue { messages }
+    }
+
+    /// Returns the template of the message with the given key.
+    fn get(&self, key: &str) -> Option<&'static str> {
+        self.messages.get(key).copied()
+    }
+}
+
+/// Splits a message template into text and placeables.
+fn pieces(template: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+            break;
+        };
+
+        if start > 0 {
+            pieces.push(Piece::Text(&rest[..start]));
+        }
+
+        let inner = rest[start + 1..end].trim();
+
+        match inner.strip_prefix('$') {
+            Some(name) => pieces.push(Piece::Placeable(name)),
+            // String literals, e.g. `{ "{" }`
+            None => pieces.push(Piece::Text(inner.trim_matches('"'))),
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    if !rest.is_empty() {
+        pieces.push(Piece::Text(rest));
+    }
+
+    pieces
+}
+
+/// Returns the argument with the given name.
+fn argument<'a>(args: &'a Args<'a>, name: &str) -> Option<&'a dyn fmt::Display> {
+    args.iter()
+        .find_map(|(arg, value)| (*arg == name).then_some(*value))
+}
+
+/// Returns the locale to reply to `message` in.
+pub async fn locale(ctx: &Context, message: &Message) -> Locale {
+    let preferences = preferences::load(ctx, message).await;
+
+    preferred_locale(ctx, message, &preferences)
+}
+
+/// Returns the locale to reply to `message` in, given the already loaded `preferences` of the
+/// user who sent it.
+#[must_use]
+pub fn preferred_locale(ctx: &Context, message: &Message, preferences: &UserPreferences) -> Locale {
+    resolve(
+        &ctx.config,
+        ctx.casemapping(),
+        preferences.language.as_deref(),
+        message.response_target(),
+    )
+}
+
+/// Resolves the locale from the language preference of a user and the channel they're in.
+fn resolve(
+    config: &Config,
+    casemapping: CaseMapping,
+    language: Option<&str>,
+    channel: Option<&str>,
+) -> Locale {
+    language
+        .and_then(Locale::from_tag)
+        .or_else(|| channel_locale(&config.irc, casemapping, channel?))
+        .unwrap_or(config.i18n.default_locale)
+}
+
+/// Returns the locale configured for `channel`, if any.
+fn channel_locale(config: &IrcConfig, casemapping: CaseMapping, channel: &str) -> Option<Locale> {
+    if !channel.is_channel_name() {
+        return None;
+    }
+
+    let channel = casemapping.fold(channel);
+
+    match config
+        .channels
+        .iter()
+        .find(|config| casemapping.fold(&config.name) == channel)
+    {
+        Some(config) => config.locale,
+        // Channels that aren't configured were joined on invitation
+        None => config.invites.locale,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::config::IrcChannelConfig;
+
+    #[test]
+    fn it_should_format_messages() {
+        assert_eq!(
+            Locale::English.format("list-pair", args!(first = "a", last = "b")),
+            "a and b"
+        );
+        assert_eq!(
+            Locale::Danish.format("list-pair", args!(first = "a", last = "b")),
+            "a og b"
+        );
+        assert_eq!(
+            Locale::English.format("no-such-message", &[]),
+            "no-such-message"
+        );
+    }
+
+    #[test]
+    fn it_should_split_templates() {
+        assert_eq!(
+            pieces("Hello { $nick }, { \"{\" }x{$y}"),
+            [
+                Piece::Text("Hello "),
+                Piece::Placeable("nick"),
+                Piece::Text(", "),
+                Piece::Text("{"),
+                Piece::Text("x"),
+                Piece::Placeable("y"),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_pick_plurals() {
+        assert_eq!(Locale::English.plural("duration-day", 1), "1 day");
+        assert_eq!(Locale::English.plural("duration-day", 2), "2 days");
+        assert_eq!(Locale::Danish.plural("duration-day", 2), "2 dage");
+    }
+
+    #[test]
+    fn it_should_join_lists() {
+        assert_eq!(Locale::English.list(&["a", "b", "c"]), "a, b and c");
+        assert_eq!(Locale::Danish.list(&["a", "b", "c"]), "a, b og c");
+        assert_eq!(Locale::Danish.list(&["a"]), "a");
+    }
+
+    #[test]
+    fn it_should_parse_language_tags() {
+        assert_eq!(Locale::from_tag("da-dk"), Some(Locale::Danish));
+        assert_eq!(Locale::from_tag("EN"), Some(Locale::English));
+        assert_eq!(Locale::from_tag("de"), None);
+    }
+
+    #[test]
+    fn it_should_resolve_channel_locales() {
+        let mut config = IrcConfig::default();
+        let casemapping = CaseMapping::default();
+
+        config.channels.push(IrcChannelConfig {
+            name: "#English".to_string(),
+            locale: Some(Locale::English),
+            ..IrcChannelConfig::default()
+        });
+        config.invites.locale = Some(Locale::Danish);
+
+        assert_eq!(
+            channel_locale(&config, casemapping, "#english"),
+            Some(Locale::English)
+        );
+        assert_eq!(
+            channel_locale(&config, casemapping, "#invited"),
+            Some(Locale::Danish)
+        );
+        assert_eq!(channel_locale(&config, casemapping, "nick"), None);
+    }
+
+    #[test]
+    fn it_should_translate_every_message() {
+        let english = Locale::English.catalogue();
+
+        for locale in Locale::ALL {
+            let catalogue = locale.catalogue();
+
+            for key in english.messages.keys() {
+                assert!(catalogue.get(key).is_some(), "{locale} is missing {key}");
+            }
+
+            for key in catalogue.messages.keys() {
+                assert!(english.get(key).is_some(), "{key} is only in {locale}");
+            }
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! Server features advertised through `RPL_ISUPPORT`.
+
+/// The case mapping rules used by the server to compare nicknames and channel names.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CaseMapping {
+    /// Only `A-Z` and `a-z` are considered equivalent.
+    Ascii,
+    /// Like `ascii`, but `[]\~` are also the uppercase forms of `{}|^`.
+    #[default]
+    Rfc1459,
+    /// Like `rfc1459`, except `~` and `^` are distinct.
+    StrictRfc1459,
+}
+
+/// Server features advertised in `RPL_ISUPPORT` (numeric `005`).
+#[derive(Clone, Debug)]
+pub struct ISupport {
+    /// The case mapping used by the server.
+    pub casemapping: CaseMapping,
+    /// The channel membership modes and their nickname prefixes, e.g. `('o', '@')`, from the
+    /// highest ranked to the lowest.
+    pub prefixes: Vec<(char, char)>,
+    /// Whether the server supports `MONITOR`.
+    pub monitor: bool,
+}
+
+impl CaseMapping {
+    /// Parses the value of a `CASEMAPPING` token.
+    ///
+    /// Returns `None` if the case mapping is unknown.
+    #[must_use]
+    pub fn from_token(value: &str) -> Option<CaseMapping> {
+        match value.to_ascii_lowercase().as_str() {
+            "ascii" => Some(CaseMapping::Ascii),
+            "rfc1459" => Some(CaseMapping::Rfc1459),
+            "strict-rfc1459" => Some(CaseMapping::StrictRfc1459),
+            _ => None,
+        }
+    }
+
+    /// Returns the lowercase form of `c` under this case mapping.
+    #[must_use]
+    pub const fn fold_char(self, c: char) -> char {
+        match (self, c) {
+            (_, 'A'..='Z') => c.to_ascii_lowercase(),
+            (CaseMapping::Rfc1459 | CaseMapping::StrictRfc1459, '[') => '{',
+            (CaseMapping::Rfc1459 | CaseMapping::StrictRfc1459, ']') => '}',
+            (CaseMapping::Rfc1459 | CaseMapping::StrictRfc1459, '\\') => '|',
+            (CaseMapping::Rfc1459, '~') => '^',
+            _ => c,
+        }
+    }
+
+    /// Returns the lowercase form of `s` under this case mapping, suitable for comparing
+    /// nicknames and channel names.
+    #[must_use]
+    pub fn fold(self, s: &str) -> String {
+        s.chars().map(|c| self.fold_char(c)).collect()
+    }
+}
+
+impl ISupport {
+    /// Creates a new set of features with the defaults assumed before the server has advertised
+    /// any.
+    #[must_use]
+    pub fn new() -> ISupport {
+        ISupport {
+            casemapping: CaseMapping::Rfc1459,
+            prefixes: vec![('o', '@'), ('v', '+')],
+            monitor: false,
+        }
+    }
+
+    /// Returns the membership mode of a nickname `prefix`, e.g. `o` for `@`.
+    #[must_use]
+    pub fn prefix_mode(&self, prefix: char) -> Option<char> {
+        self.prefixes
+            .iter()
+            .find_map(|&(mode, symbol)| (symbol == prefix).then_some(mode))
+    }
+
+    /// Returns the nickname prefix of a membership `mode`, e.g. `@` for `o`.
+    #[must_use]
+    pub fn mode_prefix(&self, mode: char) -> Option<char> {
+        self.prefixes
+            .iter()
+            .find_map(|&(candidate, symbol)| (candidate == mode).then_some(symbol))
+    }
+
+    /// Returns the rank of a membership `mode`, where 0 is the highest, or `None` if it's not a
+    /// membership mode.
+    #[must_use]
+    pub fn mode_rank(&self, mode: char) -> Option<usize> {
+        self.prefixes
+            .iter()
+            .position(|&(candidate, _)| candidate == mode)
+    }
+
+    /// Updates the features from the arguments of an `RPL_ISUPPORT` reply.
+    ///
+    /// The first argument is our own nickname and the last is a human-readable trailer, so only
+    /// the tokens in between are considered.
+    pub fn update(&mut self, args: &[String]) {
+        let Some(tokens) = args.get(1..args.len().saturating_sub(1)) else {
+            return;
+        };
+
+        for token in tokens {
+            let (key, value) = token.split_once('=').unwrap_or((token, ""));
+
+            match key {
+                "CASEMAPPING" => {
+                    if let Some(casemapping) = CaseMapping::from_token(value) {
+                        self.casemapping = casemapping;
+                    }
+                }
+                "PREFIX" => {
+                    if let Some(prefixes) = parse_prefix(value) {
+                        self.prefixes = prefixes;
+                    }
+                }
+                "MONITOR" => self.monitor = true,
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Default for ISupport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parses the value of a `PREFIX` token, e.g. `(ov)@+`.
+///
+/// An empty value means that the server has no membership modes.
+fn parse_prefix(value: &str) -> Option<Vec<(char, char)>> {
+    if value.is_empty() {
+        return Some(vec![]);
+    }
+
+    let (modes, prefixes) = value.strip_prefix('(')?.split_once(')')?;
+
+    (modes.chars().count() == prefixes.chars().count())
+        .then(|| modes.chars().zip(prefixes.chars()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_fold_rfc1459() {
+        assert_eq!(CaseMapping::Rfc1459.fold("Nick[A]\\~"), "nick{a}|^");
+        assert_eq!(CaseMapping::StrictRfc1459.fold("Nick[A]\\~"), "nick{a}|~");
+        assert_eq!(CaseMapping::Ascii.fold("Nick[A]\\~"), "nick[a]\\~");
+    }
+
+    #[test]
+    fn it_should_update_casemapping() {
+        let mut isupport = ISupport::new();
+        let args: Vec<String> = ["zeta", "CHANTYPES=#", "CASEMAPPING=ascii", "are supported"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        isupport.update(&args);
+
+        assert_eq!(isupport.casemapping, CaseMapping::Ascii);
+        assert!(!isupport.monitor);
+    }
+
+    #[test]
+    fn it_should_detect_monitor() {
+        let mut isupport = ISupport::new();
+        let args: Vec<String> = ["zeta", "MONITOR=100", "are supported"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        isupport.update(&args);
+
+        assert!(isupport.monitor);
+    }
+
+    #[test]
+    fn it_should_update_prefixes() {
+        let mut isupport = ISupport::new();
+        let args: Vec<String> = ["zeta", "PREFIX=(qaohv)~&@%+", "are supported"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        isupport.update(&args);
+
+        assert_eq!(isupport.prefix_mode('%'), Some('h'));
+        assert_eq!(isupport.mode_prefix('q'), Some('~'));
+        assert_eq!(isupport.mode_rank('v'), Some(4));
+        assert_eq!(isupport.mode_rank('b'), None);
+    }
+}
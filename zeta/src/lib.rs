@@ -9,17 +9,30 @@ pub mod config;
 pub mod consts;
 /// Shared context for plugins
 pub mod context;
+pub mod ctcp;
 /// Database integration
 pub mod database;
 /// DNS resolution
 pub mod dns;
 mod error;
-mod http;
+pub mod http;
+pub mod i18n;
+pub mod isupport;
+pub mod logger;
+pub mod message;
+pub mod metrics;
+pub mod permissions;
 mod plugin;
+pub mod preferences;
+pub mod rejoin;
+pub mod server;
+pub mod services;
+pub mod state;
+pub mod urls;
 mod utils;
 mod zeta;
 
 pub use config::Config;
 pub use error::Error;
-pub use plugin::{Plugin, Registry};
+pub use plugin::{Plugin, Registry, bundled_plugins};
 pub use zeta::Zeta;
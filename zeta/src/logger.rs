@@ -0,0 +1,727 @@
+//! Channel logging.
+//!
+//! Channel events are written to daily rotated files in a directory per channel, in either an
+//! irssi-compatible, WeeChat-compatible or JSON Lines format. Timestamps are taken from the IRCv3
+//! `server-time` tag when the server provides it, and dates are in UTC.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use irc::client::prelude::ChannelExt;
+use irc::proto::message::Tag;
+use irc::proto::{ChannelMode, Command, Message, Mode, Prefix, Response};
+use serde::Serialize;
+use time::format_description::{FormatItem, well_known::Rfc3339};
+use time::macros::format_description;
+use time::{Date, OffsetDateTime};
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, warn};
+
+use crate::config::{LogFormat, LoggerConfig};
+use crate::ctcp::Ctcp;
+use crate::isupport::CaseMapping;
+use crate::state::Channel;
+
+/// The format of the date in log file names.
+const DATE_FORMAT: &[FormatItem<'_>] = format_description!("[year]-[month]-[day]");
+
+/// The format of timestamps in irssi logs.
+const IRSSI_TIME_FORMAT: &[FormatItem<'_>] = format_description!("[hour]:[minute]:[second]");
+
+/// The format of the date in the irssi `Log opened` header.
+const IRSSI_OPENED_FORMAT: &[FormatItem<'_>] = format_description!(
+    "[weekday repr:short] [month repr:short] [day] [hour]:[minute]:[second] [year]"
+);
+
+/// The format of timestamps in WeeChat logs.
+const WEECHAT_TIME_FORMAT: &[FormatItem<'_>] =
+    format_description!("[year]-[month]-[day] [hour]:[minute]:[second]");
+
+/// The user or server that caused an event.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct Source {
+    /// The nickname of the user, or the name of the server.
+    pub nick: String,
+    /// The username of the user.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub user: String,
+    /// The hostname of the user.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub host: String,
+}
+
+/// A channel event.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Event {
+    /// A regular message.
+    Privmsg { text: String },
+    /// A CTCP `ACTION`, i.e. `/me`.
+    Action { text: String },
+    /// A notice.
+    Notice { text: String },
+    /// The user joined the channel.
+    Join,
+    /// The user left the channel.
+    Part { reason: Option<String> },
+    /// The user kicked `target` from the channel.
+    Kick {
+        target: String,
+        reason: Option<String>,
+    },
+    /// The user changed the channel modes.
+    Mode { modes: String },
+    /// The user changed the topic.
+    Topic { topic: String },
+    /// The user changed their nickname.
+    Nick { new_nick: String },
+    /// The user disconnected.
+    Quit { reason: Option<String> },
+}
+
+/// A single channel log entry.
+#[derive(Clone, Debug, Serialize)]
+pub struct Entry {
+    /// When the event happened.
+    #[serde(with = "time::serde::rfc3339")]
+    pub time: OffsetDateTime,
+    /// The channel the event happened in.
+    pub channel: String,
+    /// Who caused the event.
+    #[serde(flatten)]
+    pub source: Source,
+    /// The event itself.
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+/// An open log file.
+struct LogFile {
+    /// The date the file is for.
+    date: Date,
+    /// The file handle.
+    file: File,
+}
+
+/// Writes channel events to disk.
+pub struct ChannelLogger {
+    /// Logging configuration.
+    config: LoggerConfig,
+    /// Open log files by (case-folded) channel.
+    files: tokio::sync::Mutex<HashMap<String, LogFile>>,
+}
+
+impl ChannelLogger {
+    /// Creates a new channel logger.
+    #[must_use]
+    pub fn new(config: LoggerConfig) -> ChannelLogger {
+        ChannelLogger {
+            config,
+            files: tokio::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Logs the channel events caused by `message`.
+    ///
+    /// `channels` is a snapshot of the channels we were in before `message` was received, which
+    /// `QUIT` and `NICK` events are logged to if the user was in them. Errors are logged rather
+    /// than returned, so logging never interferes with the rest of the bot.
+    pub async fn log(&self, message: &Message, channels: &[Channel], casemapping: CaseMapping) {
+        for entry in entries(message, channels, casemapping) {
+            if let Err(err) = self.write(&entry, casemapping).await {
+                warn!(channel = %entry.channel, error = %err, "could not write channel log");
+            }
+        }
+    }
+
+    /// Appends `entry` to the log file of its channel, opening a new file if the date changed.
+    async fn write(&self, entry: &Entry, casemapping: CaseMapping) -> io::Result<()> {
+        let channel_key = casemapping.fold(&entry.channel);
+
+        if self.is_excluded(&channel_key, casemapping) {
+            return Ok(());
+        }
+
+        let date = entry.time.date();
+        let mut files = self.files.lock().await;
+
+        if files.get(&channel_key).is_none_or(|file| file.date != date) {
+            let directory = self.channel_directory(&channel_key);
+            let file = self.open(&directory, entry.time).await?;
+
+            files.insert(channel_key.clone(), file);
+
+            if let Some(retention) = self.retention(&channel_key, casemapping)
+                && let Err(err) = prune(&directory, date, retention).await
+            {
+                warn!(directory = %directory.display(), error = %err, "could not prune old logs");
+            }
+        }
+
+        if let Some(log_file) = files.get_mut(&channel_key) {
+            let mut line = format_entry(self.config.format, entry);
+            line.push('\n');
+
+            log_file.file.write_all(line.as_bytes()).await?;
+            log_file.file.flush().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Opens the log file for the date of `time` in `directory` for appending.
+    async fn open(&self, directory: &Path, time: OffsetDateTime) -> io::Result<LogFile> {
+        let date = time.date();
+        let file_name = format!(
+            "{}.{}",
+            date.format(DATE_FORMAT).map_err(io::Error::other)?,
+            match self.config.format {
+                LogFormat::Irssi | LogFormat::Weechat => "log",
+                LogFormat::Jsonl => "jsonl",
+            }
+        );
+        let path = directory.join(file_name);
+
+        debug!(path = %path.display(), "opening channel log");
+        fs::create_dir_all(directory).await?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+
+        if self.config.format == LogFormat::Irssi {
+            let opened = time.format(IRSSI_OPENED_FORMAT).map_err(io::Error::other)?;
+
+            file.write_all(format!("--- Log opened {opened}\n").as_bytes())
+                .await?;
+        }
+
+        Ok(LogFile { date, file })
+    }
+
+    /// Returns the directory that logs for `channel_key` are written to.
+    fn channel_directory(&self, channel_key: &str) -> PathBuf {
+        let name: String = channel_key
+            .chars()
+            .map(|c| if matches!(c, '/' | '\\') { '_' } else { c })
+            .collect();
+
+        self.config.directory.join(name)
+    }
+
+    /// Returns whether logging is disabled for `channel_key`.
+    fn is_excluded(&self, channel_key: &str, casemapping: CaseMapping) -> bool {
+        self.config
+            .exclude
+            .iter()
+            .any(|channel| casemapping.fold(channel) == channel_key)
+    }
+
+    /// Returns how long logs for `channel_key` should be kept, if not forever.
+    ///
+    /// Channels without a retention of their own use the global retention.
+    fn retention(&self, channel_key: &str, casemapping: CaseMapping) -> Option<Duration> {
+        self.config
+            .channels
+            .iter()
+            .find(|channel| casemapping.fold(&channel.name) == channel_key)
+            .and_then(|channel| channel.retention)
+            .or(self.config.retention)
+    }
+}
+
+/// Returns the log entries for `message`.
+///
+/// `QUIT` and `NICK` events are logged to those of `channels` that the user is a member of.
+fn entries(message: &Message, channels: &[Channel], casemapping: CaseMapping) -> Vec<Entry> {
+    let Some(source) = source(message) else {
+        return vec![];
+    };
+
+    let time = server_time(message).unwrap_or_else(OffsetDateTime::now_utc);
+    let nick = casemapping.fold(&source.nick);
+    let entry = |channel: &str, event: Event| Entry {
+        time,
+        channel: channel.to_string(),
+        source: source.clone(),
+        event,
+    };
+    let shared_channels = || {
+        channels
+            .iter()
+            .filter(|channel| channel.member(&nick).is_some())
+            .map(|channel| channel.name.as_str())
+    };
+
+    match message.command {
+        Command::PRIVMSG(ref target, ref text) if target.is_channel_name() => {
+            match Ctcp::parse(text) {
+                Some(Ctcp::Action(action)) => {
+                    let text = action.to_string();
+
+                    vec![entry(target, Event::Action { text })]
+                }
+                Some(_) => vec![],
+                None => vec![entry(target, Event::Privmsg { text: text.clone() })],
+            }
+        }
+        Command::NOTICE(ref target, ref text) if target.is_channel_name() => {
+            vec![entry(target, Event::Notice { text: text.clone() })]
+        }
+        Command::JOIN(ref channels, _, _) => channels
+            .split(',')
+            .map(|channel| entry(channel, Event::Join))
+            .collect(),
+        Command::PART(ref channels, ref reason) => channels
+            .split(',')
+            .map(|channel| {
+                entry(
+                    channel,
+                    Event::Part {
+                        reason: reason.clone(),
+                    },
+                )
+            })
+            .collect(),
+        Command::KICK(ref channel, ref target, ref reason) => {
+            vec![entry(
+                channel,
+                Event::Kick {
+                    target: target.clone(),
+                    reason: reason.clone(),
+                },
+            )]
+        }
+        Command::ChannelMODE(ref channel, ref modes) => {
+            let modes = format_modes(modes);
+
+            vec![entry(channel, Event::Mode { modes })]
+        }
+        Command::TOPIC(ref channel, Some(ref topic)) => {
+            vec![entry(
+                channel,
+                Event::Topic {
+                    topic: topic.clone(),
+                },
+            )]
+        }
+        Command::NICK(ref new_nick) => shared_channels()
+            .map(|channel| {
+                let new_nick = new_nick.clone();
+
+                entry(channel, Event::Nick { new_nick })
+            })
+            .collect(),
+        Command::QUIT(ref reason) => shared_channels()
+            .map(|channel| {
+                let reason = reason.clone();
+
+                entry(channel, Event::Quit { reason })
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Formats `entry` as a single line in the given format, without a trailing newline.
+#[must_use]
+pub fn format_entry(format: LogFormat, entry: &Entry) -> String {
+    match format {
+        LogFormat::Irssi => format_irssi(entry),
+        LogFormat::Weechat => format_weechat(entry),
+        LogFormat::Jsonl => serde_json::to_string(entry).unwrap_or_default(),
+    }
+}
+
+fn format_irssi(entry: &Entry) -> String {
+    let Entry {
+        channel,
+        source: Source { nick, user, host },
+        ..
+    } = entry;
+    let mut line = entry.time.format(IRSSI_TIME_FORMAT).unwrap_or_default();
+
+    let _ = match &entry.event {
+        Event::Privmsg { text } => write!(line, " <{nick}> {text}"),
+        Event::Action { text } => write!(line, "  * {nick} {text}"),
+        Event::Notice { text } => write!(line, " -{nick}:{channel}- {text}"),
+        Event::Join => write!(line, " -!- {nick} [{user}@{host}] has joined {channel}"),
+        Event::Part { reason } => write!(
+            line,
+            " -!- {nick} [{user}@{host}] has left {channel} [{}]",
+            reason.as_deref().unwrap_or_default()
+        ),
+        Event::Kick { target, reason } => write!(
+            line,
+            " -!- {target} was kicked from {channel} by {nick} [{}]",
+            reason.as_deref().unwrap_or_default()
+        ),
+        Event::Mode { modes } => write!(line, " -!- mode/{channel} [{modes}] by {nick}"),
+        Event::Topic { topic } => {
+            write!(
+                line,
+                " -!- {nick} changed the topic of {channel} to: {topic}"
+            )
+        }
+        Event::Nick { new_nick } => write!(line, " -!- {nick} is now known as {new_nick}"),
+        Event::Quit { reason } => write!(
+            line,
+            " -!- {nick} [{user}@{host}] has quit [{}]",
+            reason.as_deref().unwrap_or_default()
+        ),
+    };
+
+    line
+}
+
+fn format_weechat(entry: &Entry) -> String {
+    let Entry {
+        channel,
+        source: Source { nick, user, host },
+        ..
+    } = entry;
+    let mut line = entry.time.format(WEECHAT_TIME_FORMAT).unwrap_or_default();
+
+    let _ = match &entry.event {
+        Event::Privmsg { text } => write!(line, "\t{nick}\t{text}"),
+        Event::Action { text } => write!(line, "\t *\t{nick} {text}"),
+        Event::Notice { text } => write!(line, "\t--\tNotice({nick}) -> {channel}: {text}"),
+        Event::Join => write!(line, "\t-->\t{nick} ({user}@{host}) has joined {channel}"),
+        Event::Part { reason } => write!(
+            line,
+            "\t<--\t{nick} ({user}@{host}) has left {channel} ({})",
+            reason.as_deref().unwrap_or_default()
+        ),
+        Event::Kick { target, reason } => write!(
+            line,
+            "\t<--\t{nick} has kicked {target} ({})",
+            reason.as_deref().unwrap_or_default()
+        ),
+        Event::Mode { modes } => write!(line, "\t--\tMode {channel} [{modes}] by {nick}"),
+        Event::Topic { topic } => {
+            write!(
+                line,
+                "\t--\t{nick} has changed topic for {channel} to \"{topic}\""
+            )
+        }
+        Event::Nick { new_nick } => write!(line, "\t--\t{nick} is now known as {new_nick}"),
+        Event::Quit { reason } => write!(
+            line,
+            "\t<--\t{nick} ({user}@{host}) has quit ({})",
+            reason.as_deref().unwrap_or_default()
+        ),
+    };
+
+    line
+}
+
+/// Formats channel mode changes the way they appear on the wire, e.g. `+ov-b nick nick mask`.
+fn format_modes(modes: &[Mode<ChannelMode>]) -> String {
+    let mut flags = String::new();
+    let mut args = vec![];
+    let mut current_sign = None;
+
+    for mode in modes {
+        let (sign, mode, arg) = match mode {
+            Mode::Plus(mode, arg) => (Some('+'), mode, arg.as_deref()),
+            Mode::Minus(mode, arg) => (Some('-'), mode, arg.as_deref()),
+            Mode::NoPrefix(mode) => (None, mode, None),
+        };
+
+        if sign != current_sign {
+            flags.extend(sign);
+            current_sign = sign;
+        }
+
+        let _ = write!(flags, "{mode}");
+        args.extend(arg);
+    }
+
+    std::iter::once(flags.as_str())
+        .chain(args)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns the user or server that sent `message`.
+fn source(message: &Message) -> Option<Source> {
+    match message.prefix.as_ref()? {
+        Prefix::Nickname(nick, user, host) => Some(Source {
+            nick: nick.clone(),
+            user: user.clone(),
+            host: host.clone(),
+        }),
+        Prefix::ServerName(name) => Some(Source {
+            nick: name.clone(),
+            ..Source::default()
+        }),
+    }
+}
+
+/// Returns the time of `message` from its IRCv3 `server-time` tag, if present.
+fn server_time(message: &Message) -> Option<OffsetDateTime> {
+    message
+        .tags
+        .as_ref()?
+        .iter()
+        .find_map(|Tag(key, value)| (key == "time").then_some(value.as_deref()).flatten())
+        .and_then(|value| OffsetDateTime::parse(value, &Rfc3339).ok())
+}
+
+/// Deletes log files in `directory` that are older than `retention`, relative to `today`.
+async fn prune(directory: &Path, today: Date, retention: Duration) -> io::Result<()> {
+    let Some(cutoff) = time::Duration::try_from(retention)
+        .ok()
+        .and_then(|retention| today.checked_sub(retention))
+    else {
+        return Ok(());
+    };
+
+    let mut entries = fs::read_dir(directory).await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let date = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| Date::parse(stem, DATE_FORMAT).ok());
+
+        if let Some(date) = date
+            && date < cutoff
+        {
+            debug!(path = %path.display(), "deleting expired channel log");
+            fs::remove_file(&path).await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::config::ChannelLoggerConfig;
+    use crate::isupport::ISupport;
+    use crate::state::Tracker;
+
+    fn message(s: &str) -> Message {
+        s.parse().unwrap()
+    }
+
+    fn privmsg_entry() -> Entry {
+        Entry {
+            time: datetime!(2026-10-18 12:34:56 UTC),
+            channel: "#zeta".to_string(),
+            source: Source {
+                nick: "mk".to_string(),
+                user: "mk".to_string(),
+                host: "maero.dk".to_string(),
+            },
+            event: Event::Privmsg {
+                text: "hello world".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn it_should_format_irssi_lines() {
+        let mut entry = privmsg_entry();
+        assert_eq!(
+            format_entry(LogFormat::Irssi, &entry),
+            "12:34:56 <mk> hello world"
+        );
+
+        entry.event = Event::Join;
+        assert_eq!(
+            format_entry(LogFormat::Irssi, &entry),
+            "12:34:56 -!- mk [mk@maero.dk] has joined #zeta"
+        );
+
+        entry.event = Event::Kick {
+            target: "zeta".to_string(),
+            reason: Some("bye".to_string()),
+        };
+        assert_eq!(
+            format_entry(LogFormat::Irssi, &entry),
+            "12:34:56 -!- zeta was kicked from #zeta by mk [bye]"
+        );
+    }
+
+    #[test]
+    fn it_should_format_weechat_lines() {
+        let mut entry = privmsg_entry();
+        assert_eq!(
+            format_entry(LogFormat::Weechat, &entry),
+            "2026-10-18 12:34:56\tmk\thello world"
+        );
+
+        entry.event = Event::Action {
+            text: "waves".to_string(),
+        };
+        assert_eq!(
+            format_entry(LogFormat::Weechat, &entry),
+            "2026-10-18 12:34:56\t *\tmk waves"
+        );
+    }
+
+    #[test]
+    fn it_should_format_json_lines() {
+        let entry = privmsg_entry();
+
+        assert_eq!(
+            format_entry(LogFormat::Jsonl, &entry),
+            r##"{"time":"2026-10-18T12:34:56Z","channel":"#zeta","nick":"mk","user":"mk","host":"maero.dk","type":"privmsg","text":"hello world"}"##
+        );
+    }
+
+    #[test]
+    fn it_should_parse_actions_and_server_time() {
+        let entries = entries(
+            &message(
+                "@time=2026-10-18T12:34:56.000Z :mk!mk@maero.dk PRIVMSG #zeta :\x01ACTION waves\x01\r\n",
+            ),
+            &[],
+            CaseMapping::Rfc1459,
+        );
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].time, datetime!(2026-10-18 12:34:56 UTC));
+        assert_eq!(
+            entries[0].event,
+            Event::Action {
+                text: "waves".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_log_quits_to_joined_channels() {
+        let isupport = ISupport::default();
+        let casemapping = isupport.casemapping;
+        let mut tracker = Tracker::new();
+
+        for line in [
+            ":zeta!zeta@maero.dk JOIN #zeta\r\n",
+            ":irc.example.com 353 zeta = #zeta :zeta @MK +other\r\n",
+            ":zeta!zeta@maero.dk JOIN #other\r\n",
+            ":irc.example.com 353 zeta = #other :zeta other\r\n",
+        ] {
+            tracker.update(&message(line), "zeta", &isupport);
+        }
+
+        let channels: Vec<Channel> = tracker.channels().cloned().collect();
+        let quits = entries(
+            &message(":mk!mk@maero.dk QUIT :Quit: bye\r\n"),
+            &channels,
+            casemapping,
+        );
+
+        assert_eq!(quits.len(), 1);
+        assert_eq!(quits[0].channel, "#zeta");
+
+        let mut logged_to: Vec<String> = entries(
+            &message(":other!o@example.com NICK another\r\n"),
+            &channels,
+            casemapping,
+        )
+        .into_iter()
+        .map(|entry| entry.channel)
+        .collect();
+        logged_to.sort();
+
+        assert_eq!(logged_to, vec!["#other", "#zeta"]);
+    }
+
+    #[test]
+    fn it_should_fall_back_to_the_global_retention() {
+        let week = Duration::from_secs(7 * 24 * 60 * 60);
+        let day = Duration::from_secs(24 * 60 * 60);
+        let logger = ChannelLogger::new(LoggerConfig {
+            retention: Some(week),
+            channels: vec![
+                ChannelLoggerConfig {
+                    name: "#Zeta".to_string(),
+                    retention: Some(day),
+                },
+                ChannelLoggerConfig {
+                    name: "#other".to_string(),
+                    retention: None,
+                },
+            ],
+            ..LoggerConfig::default()
+        });
+        let casemapping = CaseMapping::Rfc1459;
+
+        assert_eq!(logger.retention("#zeta", casemapping), Some(day));
+        assert_eq!(logger.retention("#other", casemapping), Some(week));
+        assert_eq!(logger.retention("#unknown", casemapping), Some(week));
+    }
+
+    #[test]
+    fn it_should_format_modes() {
+        let modes = vec![
+            Mode::Plus(ChannelMode::Oper, Some("mk".to_string())),
+            Mode::Plus(ChannelMode::Voice, Some("zeta".to_string())),
+            Mode::Minus(ChannelMode::Ban, Some("*!*@example.com".to_string())),
+        ];
+
+        assert_eq!(format_modes(&modes), "+ov-b mk zeta *!*@example.com");
+    }
+
+    #[tokio::test]
+    async fn it_should_write_and_prune_logs() {
+        let directory = tempfile::tempdir().unwrap();
+        let config = LoggerConfig {
+            enabled: true,
+            directory: directory.path().to_path_buf(),
+            retention: Some(Duration::from_secs(7 * 24 * 60 * 60)),
+            ..LoggerConfig::default()
+        };
+        let channel_directory = directory.path().join("#zeta");
+        std::fs::create_dir_all(&channel_directory).unwrap();
+        std::fs::write(channel_directory.join("2026-10-01.log"), "old").unwrap();
+        std::fs::write(channel_directory.join("2026-10-15.log"), "recent").unwrap();
+
+        let logger = ChannelLogger::new(config);
+        logger
+            .write(&privmsg_entry(), CaseMapping::Rfc1459)
+            .await
+            .unwrap();
+
+        let contents = std::fs::read_to_string(channel_directory.join("2026-10-18.log")).unwrap();
+
+        assert_eq!(
+            contents,
+            "--- Log opened Sun Oct 18 12:34:56 2026\n12:34:56 <mk> hello world\n"
+        );
+        assert!(!channel_directory.join("2026-10-01.log").exists());
+        assert!(channel_directory.join("2026-10-15.log").exists());
+    }
+
+    #[tokio::test]
+    async fn it_should_not_write_excluded_channels() {
+        let directory = tempfile::tempdir().unwrap();
+        let config = LoggerConfig {
+            enabled: true,
+            directory: directory.path().to_path_buf(),
+            exclude: vec!["#ZETA".to_string()],
+            ..LoggerConfig::default()
+        };
+        let logger = ChannelLogger::new(config);
+
+        logger
+            .write(&privmsg_entry(), CaseMapping::Rfc1459)
+            .await
+            .unwrap();
+
+        assert!(!directory.path().join("#zeta").exists());
+    }
+}
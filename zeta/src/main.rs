@@ -14,16 +14,27 @@ use zeta::database;
 use zeta::{Config, Zeta};
 pub use zeta::{Error, config};
 
+use cli::Command;
+
 #[tokio::main]
 async fn main() -> miette::Result<()> {
     let opts: cli::Opts = argh::from_env();
-    let config: Config = Figment::new()
-        .merge(Toml::file(opts.config_path))
-        .merge(Env::prefixed("ZETA_").lowercase(false).split("__"))
-        .extract()
-        .into_diagnostic()?;
+    let figment = Figment::new()
+        .merge(Toml::file(&opts.config_path))
+        .merge(Env::prefixed("ZETA_").lowercase(false).split("__"));
+    let config: Config = figment.extract().into_diagnostic()?;
+    let command = opts
+        .command
+        .unwrap_or_else(|| Command::Run(cli::RunCommand::default()));
+
+    // These only print their results, and don't need tracing or a database connection
+    match &command {
+        Command::CheckConfig(check) => return cli::check_config::run(check, &figment, &config),
+        Command::ListPlugins(_) => return cli::plugins::list(&config),
+        _ => {}
+    }
 
-    tracing::try_init(&config.tracing)?;
+    let _tracing = tracing::try_init(&config.tracing)?;
 
     #[cfg(feature = "database")]
     let db = {
@@ -33,12 +44,41 @@ async fn main() -> miette::Result<()> {
         let db = database::connect(config.database.url.as_str(), &config.database).await?;
         debug!("connected to database");
 
-        debug!("running database migrations");
-        database::migrate(db.clone()).await?;
-        debug!("database migrations complete");
+        // Migrations are managed explicitly by the migrate command
+        if !matches!(command, Command::Migrate(_)) {
+            debug!("running database migrations");
+            database::migrate(db.clone()).await?;
+            debug!("database migrations complete");
+        }
+
         db
     };
 
+    match command {
+        #[cfg(feature = "database")]
+        Command::Migrate(command) => return cli::migrate::run(command, db).await,
+        #[cfg(feature = "database")]
+        Command::Quotes(command) => return cli::quotes::run(command, &db).await,
+        #[cfg(not(feature = "database"))]
+        Command::Migrate(_) => {
+            miette::bail!("migrations require zeta to be built with the `database` feature")
+        }
+        #[cfg(not(feature = "database"))]
+        Command::Quotes(_) => {
+            miette::bail!("managing quotes requires zeta to be built with the `database` feature")
+        }
+        Command::Repl(command) => {
+            return cli::repl::run(
+                command,
+                config,
+                #[cfg(feature = "database")]
+                db,
+            )
+            .await;
+        }
+        Command::Run(_) | Command::CheckConfig(_) | Command::ListPlugins(_) => {}
+    }
+
     let dns = zeta::dns::new();
 
     let mut z = Zeta::new(
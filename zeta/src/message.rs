@@ -0,0 +1,75 @@
+//! Helpers for IRC messages.
+
+use irc::client::prelude::ChannelExt;
+use irc::proto::{Command, Message};
+
+/// Extension methods for IRC messages.
+pub trait MessageExt {
+    /// Returns where to reply to the message: the channel it was sent to, or the nickname of the
+    /// sender if it was sent privately to us.
+    ///
+    /// Returns `None` if the message isn't a `PRIVMSG` or `NOTICE`, or if it was sent privately by
+    /// the server.
+    fn reply_target(&self) -> Option<&str>;
+
+    /// Returns whether the message is a `PRIVMSG` or `NOTICE` that was sent privately to us rather
+    /// than to a channel.
+    fn is_private(&self) -> bool;
+}
+
+impl MessageExt for Message {
+    fn reply_target(&self) -> Option<&str> {
+        match self.command {
+            Command::PRIVMSG(ref target, _) | Command::NOTICE(ref target, _) => {
+                if target.is_channel_name() {
+                    Some(target)
+                } else {
+                    self.source_nickname()
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn is_private(&self) -> bool {
+        match self.command {
+            Command::PRIVMSG(ref target, _) | Command::NOTICE(ref target, _) => {
+                !target.is_channel_name()
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(line: &str) -> Message {
+        line.parse().expect("invalid message")
+    }
+
+    #[test]
+    fn it_should_reply_to_channels() {
+        let message = message(":mk!mk@maero.dk PRIVMSG #zeta :.r 1+1\r\n");
+
+        assert_eq!(message.reply_target(), Some("#zeta"));
+        assert!(!message.is_private());
+    }
+
+    #[test]
+    fn it_should_reply_privately_to_queries() {
+        let message = message(":mk!mk@maero.dk PRIVMSG zeta :.r 1+1\r\n");
+
+        assert_eq!(message.reply_target(), Some("mk"));
+        assert!(message.is_private());
+    }
+
+    #[test]
+    fn it_should_not_reply_to_other_commands() {
+        let message = message(":mk!mk@maero.dk JOIN #zeta\r\n");
+
+        assert_eq!(message.reply_target(), None);
+        assert!(!message.is_private());
+    }
+}
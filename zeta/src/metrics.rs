@@ -0,0 +1,550 @@
+//! Process and bot metrics.
+//!
+//! Metrics are collected in a process-wide [`Metrics`] registry and rendered in the Prometheus
+//! text exposition format by the `/metrics` endpoint of the HTTP server.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use tokio::runtime::Handle;
+
+/// The upper bounds of the latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The process-wide metrics registry.
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::default);
+
+/// Returns the process-wide metrics registry.
+#[must_use]
+pub fn global() -> &'static Metrics {
+    &METRICS
+}
+
+/// Process telemetry snapshot.
+pub struct Snapshot {
+    /// The RSS memory usage, as bytes.
+    pub phys_mem: f64,
+    /// The VMS memory usage, as bytes.
+    pub virt_mem: f64,
+    /// The number of tasks currently scheduled in the runtime's global queue.
+    pub global_queue_depth: usize,
+    /// The current number of alive tasks in the runtime.
+    pub num_alive_tasks: usize,
+    /// The number of worker threads used by the runtime.
+    pub num_workers: usize,
+}
+
+impl Snapshot {
+    /// Captures the current memory usage and tokio runtime metrics.
+    ///
+    /// Returns `None` if memory usage is unavailable on this platform.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime.
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn capture() -> Option<Snapshot> {
+        if let Some(memory) = memory_stats::memory_stats() {
+            // Capture memory information
+            let phys_mem = memory.physical_mem as f64;
+            let virt_mem = memory.virtual_mem as f64;
+
+            // Capture tokio runtime information
+            let metrics = Handle::current().metrics();
+            let num_workers = metrics.num_workers();
+            let num_alive_tasks = metrics.num_alive_tasks();
+            let global_queue_depth = metrics.global_queue_depth();
+
+            return Some(Snapshot {
+                phys_mem,
+                virt_mem,
+                global_queue_depth,
+                num_alive_tasks,
+                num_workers,
+            });
+        }
+
+        None
+    }
+}
+
+/// A cumulative histogram with fixed buckets.
+#[derive(Clone, Debug, Default)]
+struct Histogram {
+    /// The number of observations in each bucket of [`LATENCY_BUCKETS`].
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    /// The sum of all observations.
+    sum: f64,
+    /// The number of observations.
+    count: u64,
+}
+
+impl Histogram {
+    /// Records a single observation.
+    fn observe(&mut self, value: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if value <= bound {
+                *bucket += 1;
+            }
+        }
+
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+/// Metrics for a single plugin.
+#[derive(Clone, Debug, Default)]
+struct PluginMetrics {
+    /// The number of commands and URLs handled by the plugin.
+    invocations: u64,
+    /// The number of dispatches that returned an error.
+    errors: u64,
+    /// The time spent handling messages.
+    latency: Histogram,
+}
+
+/// Metrics registry.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// Metrics by plugin name.
+    plugins: Mutex<BTreeMap<String, PluginMetrics>>,
+    /// Outbound IRC messages by command.
+    messages_sent: Mutex<BTreeMap<String, u64>>,
+    /// HTTP requests by upstream host and status.
+    http_requests: Mutex<BTreeMap<(String, String), u64>>,
+    /// HTTP request latency by upstream host, including retries.
+    http_latency: Mutex<BTreeMap<String, Histogram>>,
+    /// HTTP request retries by upstream host.
+    http_retries: Mutex<BTreeMap<String, u64>>,
+    /// HTTP requests served from the response cache.
+    http_cache_hits: AtomicU64,
+    /// Cacheable HTTP requests that weren't in the response cache.
+    http_cache_misses: AtomicU64,
+    /// Whether the IRC connection is established.
+    irc_connected: AtomicBool,
+    /// Whether we've registered with the IRC server.
+    irc_registered: AtomicBool,
+}
+
+impl Metrics {
+    /// Records that `plugin` handled a command or URL in `elapsed` time, and whether it failed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is poisoned.
+    pub fn record_plugin(&self, plugin: &str, elapsed: Duration, failed: bool) {
+        let mut plugins = self.plugins.lock().unwrap();
+        let metrics = plugins.entry(plugin.to_string()).or_default();
+
+        metrics.invocations += 1;
+        metrics.errors += u64::from(failed);
+        metrics.latency.observe(elapsed.as_secs_f64());
+    }
+
+    /// Records that an IRC message with the given `command` was sent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is poisoned.
+    pub fn record_message_sent(&self, command: &str) {
+        let mut messages_sent = self.messages_sent.lock().unwrap();
+
+        *messages_sent.entry(command.to_string()).or_default() += 1;
+    }
+
+    /// Records an HTTP request to `host` that finished with `status` after `elapsed` time, or
+    /// `error` if no response was received.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    pub fn record_http_request(&self, host: &str, status: Option<u16>, elapsed: Duration) {
+        let status = status.map_or_else(|| "error".to_string(), |status| status.to_string());
+
+        *self
+            .http_requests
+            .lock()
+            .unwrap()
+            .entry((host.to_string(), status))
+            .or_default() += 1;
+        self.http_latency
+            .lock()
+            .unwrap()
+            .entry(host.to_string())
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Records that a request to `host` is being retried.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is poisoned.
+    pub fn record_http_retry(&self, host: &str) {
+        *self
+            .http_retries
+            .lock()
+            .unwrap()
+            .entry(host.to_string())
+            .or_default() += 1;
+    }
+
+    /// Records a lookup in the HTTP response cache, and whether it was a `hit`.
+    pub fn record_http_cache(&self, hit: bool) {
+        let counter = if hit {
+            &self.http_cache_hits
+        } else {
+            &self.http_cache_misses
+        };
+
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Sets whether the IRC connection is established.
+    pub fn set_irc_connected(&self, connected: bool) {
+        self.irc_connected.store(connected, Ordering::Relaxed);
+
+        if !connected {
+            self.set_irc_registered(false);
+        }
+    }
+
+    /// Sets whether we've registered with the IRC server.
+    pub fn set_irc_registered(&self, registered: bool) {
+        self.irc_registered.store(registered, Ordering::Relaxed);
+    }
+
+    /// Returns whether we've registered with the IRC server.
+    #[must_use]
+    pub fn irc_registered(&self) -> bool {
+        self.irc_registered.load(Ordering::Relaxed)
+    }
+
+    /// Renders all metrics, along with the process metrics in `snapshot`, in the Prometheus text
+    /// exposition format.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    #[must_use]
+    pub fn render(&self, snapshot: Option<&Snapshot>) -> String {
+        let mut out = String::new();
+
+        if let Some(snapshot) = snapshot {
+            render_snapshot(&mut out, snapshot);
+        }
+
+        header(
+            &mut out,
+            "zeta_irc_connected",
+            "gauge",
+            "Whether the IRC connection is established.",
+        );
+        let _ = writeln!(
+            out,
+            "zeta_irc_connected {}",
+            u8::from(self.irc_connected.load(Ordering::Relaxed))
+        );
+        header(
+            &mut out,
+            "zeta_irc_registered",
+            "gauge",
+            "Whether the bot is registered with the IRC server.",
+        );
+        let _ = writeln!(
+            out,
+            "zeta_irc_registered {}",
+            u8::from(self.irc_registered())
+        );
+
+        let plugins = self.plugins.lock().unwrap();
+
+        header(
+            &mut out,
+            "zeta_plugin_invocations_total",
+            "counter",
+            "Commands and URLs handled by each plugin.",
+        );
+        for (plugin, metrics) in plugins.iter() {
+            let plugin = escape_label(plugin);
+            let _ = writeln!(
+                out,
+                "zeta_plugin_invocations_total{{plugin=\"{plugin}\"}} {}",
+                metrics.invocations
+            );
+        }
+
+        header(
+            &mut out,
+            "zeta_plugin_errors_total",
+            "counter",
+            "Plugin invocations that returned an error.",
+        );
+        for (plugin, metrics) in plugins.iter() {
+            let plugin = escape_label(plugin);
+            let _ = writeln!(
+                out,
+                "zeta_plugin_errors_total{{plugin=\"{plugin}\"}} {}",
+                metrics.errors
+            );
+        }
+
+        header(
+            &mut out,
+            "zeta_plugin_duration_seconds",
+            "histogram",
+            "Time spent handling a command or URL in each plugin.",
+        );
+        for (plugin, metrics) in plugins.iter() {
+            render_histogram(
+                &mut out,
+                "zeta_plugin_duration_seconds",
+                ("plugin", plugin),
+                &metrics.latency,
+            );
+        }
+
+        drop(plugins);
+
+        header(
+            &mut out,
+            "zeta_irc_messages_sent_total",
+            "counter",
+            "IRC messages sent by the bot.",
+        );
+        for (command, count) in self.messages_sent.lock().unwrap().iter() {
+            let command = escape_label(command);
+            let _ = writeln!(
+                out,
+                "zeta_irc_messages_sent_total{{command=\"{command}\"}} {count}"
+            );
+        }
+
+        header(
+            &mut out,
+            "zeta_http_requests_total",
+            "counter",
+            "Outbound HTTP requests by upstream host and response status.",
+        );
+        for ((host, status), count) in self.http_requests.lock().unwrap().iter() {
+            let host = escape_label(host);
+            let _ = writeln!(
+                out,
+                "zeta_http_requests_total{{host=\"{host}\",status=\"{status}\"}} {count}"
+            );
+        }
+
+        header(
+            &mut out,
+            "zeta_http_request_duration_seconds",
+            "histogram",
+            "Time spent on outbound HTTP requests by upstream host, including retries.",
+        );
+        for (host, histogram) in self.http_latency.lock().unwrap().iter() {
+            render_histogram(
+                &mut out,
+                "zeta_http_request_duration_seconds",
+                ("host", host),
+                histogram,
+            );
+        }
+
+        header(
+            &mut out,
+            "zeta_http_retries_total",
+            "counter",
+            "Outbound HTTP requests that were retried, by upstream host.",
+        );
+        for (host, count) in self.http_retries.lock().unwrap().iter() {
+            let host = escape_label(host);
+            let _ = writeln!(out, "zeta_http_retries_total{{host=\"{host}\"}} {count}");
+        }
+
+        let counters = [
+            (
+                "zeta_http_cache_hits_total",
+                "HTTP requests served from the response cache.",
+                &self.http_cache_hits,
+            ),
+            (
+                "zeta_http_cache_misses_total",
+                "Cacheable HTTP requests that weren't in the response cache.",
+                &self.http_cache_misses,
+            ),
+        ];
+
+        for (name, help, counter) in counters {
+            header(&mut out, name, "counter", help);
+            let _ = writeln!(out, "{name} {}", counter.load(Ordering::Relaxed));
+        }
+
+        out
+    }
+}
+
+/// Renders the process metrics in `snapshot`.
+fn render_snapshot(out: &mut String, snapshot: &Snapshot) {
+    let gauges: [(&str, &str, f64); 2] = [
+        (
+            "process_resident_memory_bytes",
+            "Resident memory size in bytes.",
+            snapshot.phys_mem,
+        ),
+        (
+            "process_virtual_memory_bytes",
+            "Virtual memory size in bytes.",
+            snapshot.virt_mem,
+        ),
+    ];
+
+    for (name, help, value) in gauges {
+        header(out, name, "gauge", help);
+        let _ = writeln!(out, "{name} {value}");
+    }
+
+    let gauges: [(&str, &str, usize); 3] = [
+        (
+            "tokio_workers",
+            "Number of worker threads used by the runtime.",
+            snapshot.num_workers,
+        ),
+        (
+            "tokio_alive_tasks",
+            "Number of alive tasks in the runtime.",
+            snapshot.num_alive_tasks,
+        ),
+        (
+            "tokio_global_queue_depth",
+            "Number of tasks scheduled in the runtime's global queue.",
+            snapshot.global_queue_depth,
+        ),
+    ];
+
+    for (name, help, value) in gauges {
+        header(out, name, "gauge", help);
+        let _ = writeln!(out, "{name} {value}");
+    }
+}
+
+/// Renders the buckets, sum and count of `histogram` with the given label.
+fn render_histogram(
+    out: &mut String,
+    name: &str,
+    (label, value): (&str, &str),
+    histogram: &Histogram,
+) {
+    let value = escape_label(value);
+
+    for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+        let _ = writeln!(
+            out,
+            "{name}_bucket{{{label}=\"{value}\",le=\"{bound}\"}} {count}"
+        );
+    }
+
+    let _ = writeln!(
+        out,
+        "{name}_bucket{{{label}=\"{value}\",le=\"+Inf\"}} {}",
+        histogram.count
+    );
+    let _ = writeln!(out, "{name}_sum{{{label}=\"{value}\"}} {}", histogram.sum);
+    let _ = writeln!(
+        out,
+        "{name}_count{{{label}=\"{value}\"}} {}",
+        histogram.count
+    );
+}
+
+/// Writes the `HELP` and `TYPE` lines of a metric.
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Escapes a label value.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_render_plugin_metrics() {
+        let metrics = Metrics::default();
+
+        metrics.record_plugin("karma", Duration::from_millis(3), false);
+        metrics.record_plugin("karma", Duration::from_millis(300), true);
+
+        let rendered = metrics.render(None);
+
+        assert!(rendered.contains("zeta_plugin_invocations_total{plugin=\"karma\"} 2\n"));
+        assert!(rendered.contains("zeta_plugin_errors_total{plugin=\"karma\"} 1\n"));
+        assert!(
+            rendered
+                .contains("zeta_plugin_duration_seconds_bucket{plugin=\"karma\",le=\"0.005\"} 1\n")
+        );
+        assert!(
+            rendered
+                .contains("zeta_plugin_duration_seconds_bucket{plugin=\"karma\",le=\"0.5\"} 2\n")
+        );
+        assert!(
+            rendered
+                .contains("zeta_plugin_duration_seconds_bucket{plugin=\"karma\",le=\"+Inf\"} 2\n")
+        );
+        assert!(rendered.contains("zeta_plugin_duration_seconds_count{plugin=\"karma\"} 2\n"));
+    }
+
+    #[test]
+    fn it_should_render_http_and_irc_metrics() {
+        let metrics = Metrics::default();
+
+        metrics.set_irc_connected(true);
+        metrics.set_irc_registered(true);
+        metrics.record_message_sent("PRIVMSG");
+        metrics.record_http_request("api.github.com", Some(200), Duration::from_millis(20));
+        metrics.record_http_request("api.github.com", None, Duration::from_secs(30));
+        metrics.record_http_retry("api.github.com");
+        metrics.record_http_cache(true);
+
+        let rendered = metrics.render(None);
+
+        assert!(rendered.contains("zeta_irc_connected 1\n"));
+        assert!(rendered.contains("zeta_irc_registered 1\n"));
+        assert!(rendered.contains("zeta_irc_messages_sent_total{command=\"PRIVMSG\"} 1\n"));
+        assert!(
+            rendered
+                .contains("zeta_http_requests_total{host=\"api.github.com\",status=\"200\"} 1\n")
+        );
+        assert!(
+            rendered
+                .contains("zeta_http_requests_total{host=\"api.github.com\",status=\"error\"} 1\n")
+        );
+        assert!(rendered.contains(
+            "zeta_http_request_duration_seconds_bucket{host=\"api.github.com\",le=\"0.025\"} 1\n"
+        ));
+        assert!(rendered.contains("zeta_http_retries_total{host=\"api.github.com\"} 1\n"));
+        assert!(rendered.contains("zeta_http_cache_hits_total 1\n"));
+        assert!(rendered.contains("zeta_http_cache_misses_total 0\n"));
+
+        metrics.set_irc_connected(false);
+
+        assert!(!metrics.irc_registered());
+    }
+
+    #[test]
+    fn it_should_escape_labels() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
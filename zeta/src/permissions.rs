@@ -0,0 +1,58 @@
+//! Access control for privileged commands.
+
+use irc::proto::{Message, Prefix};
+use wildmatch::WildMatch;
+
+use crate::config::PermissionsConfig;
+
+/// Returns the `nick!user@host` hostmask of the sender of `message`, if it was sent by a user.
+#[must_use]
+pub fn hostmask(message: &Message) -> Option<String> {
+    match message.prefix.as_ref()? {
+        Prefix::Nickname(nick, user, host) => Some(format!("{nick}!{user}@{host}")),
+        Prefix::ServerName(_) => None,
+    }
+}
+
+/// Returns whether the sender of `message` matches one of the configured admin hostmasks.
+#[must_use]
+pub fn is_admin(config: &PermissionsConfig, message: &Message) -> bool {
+    hostmask(message).is_some_and(|hostmask| matches_any(&config.admins, &hostmask))
+}
+
+/// Returns whether `hostmask` matches any of the wildcard `patterns`, ignoring case.
+#[must_use]
+pub fn matches_any(patterns: &[String], hostmask: &str) -> bool {
+    let hostmask = hostmask.to_lowercase();
+
+    patterns
+        .iter()
+        .any(|pattern| WildMatch::new(&pattern.to_lowercase()).matches(&hostmask))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_match_hostmask_patterns() {
+        let patterns = vec!["mk!*@*.maero.dk".to_string(), "*!*@staff/*".to_string()];
+
+        assert!(matches_any(&patterns, "mk!mk@irc.maero.dk"));
+        assert!(matches_any(&patterns, "MK!mk@IRC.maero.dk"));
+        assert!(matches_any(&patterns, "someone!user@staff/someone"));
+        assert!(!matches_any(&patterns, "mk!mk@example.com"));
+    }
+
+    #[test]
+    fn it_should_only_grant_admin_to_users() {
+        let config = PermissionsConfig {
+            admins: vec!["*".to_string()],
+        };
+        let user: Message = ":mk!mk@maero.dk PRIVMSG #zeta :hi\r\n".parse().unwrap();
+        let server: Message = ":irc.example.com NOTICE #zeta :hi\r\n".parse().unwrap();
+
+        assert!(is_admin(&config, &user));
+        assert!(!is_admin(&config, &server));
+    }
+}
@@ -3,6 +3,8 @@
 use tracing::{debug, warn};
 use url::Url;
 
+use crate::urls;
+
 pub use crate::context::Context;
 
 pub use zeta_plugin::{Author, Error, Metadata, Name, Plugin};
@@ -11,13 +13,13 @@ pub use zeta_plugin::{Author, Error, Metadata, Name, Plugin};
 #[allow(unused)]
 mod prelude {
     pub use async_trait::async_trait;
-    pub use irc::client::Client;
     pub use irc::proto::{Command, Message};
     pub use zeta_plugin::Error as ZetaError;
-    pub use zeta_plugin::prelude::{BoxError, plugin_err, require_env};
+    pub use zeta_plugin::prelude::{BoxError, Client, Reply, plugin_err, require_env};
 
     pub use super::{Author, Context, Metadata, Name, Plugin};
-    pub use crate::command::Prefix;
+    pub use crate::command::{Prefix, Typed};
+    pub use crate::message::MessageExt;
 }
 
 /// Declares plugin modules and generates a registry helper to avoid boilerplate.
@@ -25,6 +27,7 @@ mod prelude {
 /// For each entry, it generates:
 /// 1. `pub mod $mod_name;` (with feature gates and docs).
 /// 2. A call to `register::<$mod_name::$struct_name>()` inside `Registry::register_bundled_plugins`.
+/// 3. An entry in the list returned by `bundled_plugins`.
 macro_rules! declare_plugins {
     (
         $(
@@ -52,6 +55,20 @@ macro_rules! declare_plugins {
                 )*
             }
         }
+
+        /// Returns the name and feature flag of every plugin compiled into this build, in the
+        /// order they're registered.
+        #[must_use]
+        pub fn bundled_plugins() -> Vec<(Name, &'static str)> {
+            let mut plugins = Vec::new();
+
+            $(
+                #[cfg(feature = $feature)]
+                plugins.push(($mod_name::$struct_name::metadata().name, $feature));
+            )*
+
+            plugins
+        }
     }
 }
 
@@ -88,6 +105,10 @@ declare_plugins! {
     #[cfg(feature = "plugin-health")]
     health::Health,
 
+    /// Usage of commands
+    #[cfg(feature = "plugin-help")]
+    help::Help,
+
     /// Howlongtobeat.com integration
     #[cfg(feature = "plugin-howlongtobeat")]
     howlongtobeat::HowLongToBeat,
@@ -100,6 +121,14 @@ declare_plugins! {
     #[cfg(feature = "plugin-kagi")]
     kagi::KagiPlugin,
 
+    /// Karma tracking
+    #[cfg(feature = "plugin-karma")]
+    karma::Karma,
+
+    /// Channel moderation
+    #[cfg(feature = "plugin-moderation")]
+    moderation::Moderation,
+
     /// Weather service integration
     #[cfg(feature = "plugin-openweathermap")]
     openweathermap::OpenWeatherMap,
@@ -107,6 +136,14 @@ declare_plugins! {
     #[cfg(feature = "plugin-pornhub")]
     pornhub::PornHub,
 
+    /// User preference commands
+    #[cfg(feature = "plugin-preferences")]
+    preferences::Preferences,
+
+    /// Per-channel quote database
+    #[cfg(feature = "plugin-quotes")]
+    quotes::Quotes,
+
     /// Reddit plugin integration
     #[cfg(feature = "plugin-reddit")]
     reddit::Reddit,
@@ -146,6 +183,10 @@ declare_plugins! {
     #[cfg(feature = "plugin-twitch")]
     twitch::Twitch,
 
+    /// URL history and repost detection
+    #[cfg(feature = "plugin-url-history")]
+    url_history::UrlHistory,
+
     /// Urban Dictionary integration
     #[cfg(feature = "plugin-urban-dictionary")]
     urban_dictionary::UrbanDictionary,
@@ -153,6 +194,11 @@ declare_plugins! {
     /// YouTube integration
     #[cfg(feature = "plugin-youtube")]
     youtube::YouTube,
+
+    // Claims any URL, so it must be registered last to only get URLs no other plugin claims.
+    /// Fallback link unfurling
+    #[cfg(feature = "plugin-unfurl")]
+    unfurl::Unfurl,
 }
 
 /// Plugin registry.
@@ -216,13 +262,11 @@ impl Registry {
 }
 
 /// Extracts HTTP(s) URLs from a string.
+///
+/// See [`urls::extract`] for how URLs are found.
 #[must_use]
 pub fn extract_urls(s: &str) -> Option<Vec<Url>> {
-    let urls: Vec<Url> = s
-        .split(' ')
-        .filter(|word| word.to_ascii_lowercase().starts_with("http"))
-        .filter_map(|word| Url::parse(word).ok())
-        .collect();
+    let urls = urls::extract(s);
 
     (!urls.is_empty()).then_some(urls)
 }
@@ -8,10 +8,8 @@ use serde::Deserialize;
 use tracing::debug;
 use url::Url;
 
-use crate::{
-    http,
-    plugin::{self, prelude::*},
-};
+use crate::http::{FetchError, FetchOptions, GuardedClient};
+use crate::plugin::prelude::*;
 
 /// The hostname for Chaturbate URLs.
 const CHATURBATE_HOST: &str = "chaturbate.com";
@@ -21,15 +19,17 @@ const CHATURBATE_WWW_HOST: &str = "www.chaturbate.com";
 
 /// Plugin for handling Chaturbate URLs and fetching broadcaster room info.
 pub struct Chaturbate {
-    client: reqwest::Client,
+    client: GuardedClient,
     room_dossier_re: Regex,
 }
 
 /// Errors that can occur when fetching or parsing a Chaturbate room.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
-    #[error("request error: {0}")]
-    Request(#[from] reqwest::Error),
+    #[error(transparent)]
+    Fetch(#[from] FetchError),
+    #[error("invalid broadcaster username")]
+    InvalidUsername,
     #[error("room dossier not found in page")]
     DossierNotFound,
     #[error("failed to deserialize room dossier: {0}")]
@@ -62,8 +62,8 @@ pub struct RoomDossier {
 
 #[async_trait]
 impl Plugin<Context> for Chaturbate {
-    fn new(_ctx: &Context) -> Result<Self, ZetaError> {
-        Ok(Self::new())
+    fn new(ctx: &Context) -> Result<Self, ZetaError> {
+        Ok(Self::new(ctx))
     }
 
     fn metadata() -> Metadata {
@@ -73,42 +73,35 @@ impl Plugin<Context> for Chaturbate {
         }
     }
 
-    async fn handle_message(
+    fn claims_url(&self, url: &Url) -> bool {
+        extract_username(url).is_some()
+    }
+
+    async fn handle_url(
         &self,
         _ctx: &Context,
         client: &Client,
         message: &Message,
+        url: &Url,
     ) -> Result<(), ZetaError> {
-        self.handle_command_logic(client, message).await?;
-        Ok(())
-    }
-}
-
-impl Chaturbate {
-    async fn handle_command_logic(&self, client: &Client, message: &Message) -> Result<(), Error> {
-        if let Command::PRIVMSG(ref channel, ref user_message) = message.command
-            && let Some(urls) = plugin::extract_urls(user_message)
+        if let Command::PRIVMSG(ref channel, _) = message.command
+            && let Some(username) = extract_username(url)
         {
-            for url in urls {
-                if let Some(username) = extract_username(&url) {
-                    debug!(%username, "processing chaturbate url");
-                    if let Err(e) = self.process_broadcaster(&username, channel, client).await {
-                        client.send_privmsg(channel, format_message(&e.to_string()))?;
-                    }
-                }
+            debug!(%username, "processing chaturbate url");
+            if let Err(e) = self.process_broadcaster(&username, channel, client).await {
+                client.send_privmsg(channel, Reply::tagged("Chaturbate").text(e))?;
             }
         }
 
         Ok(())
     }
+}
 
+impl Chaturbate {
     /// Creates a new [`Chaturbate`] plugin instance.
-    pub fn new() -> Self {
-        let client = http::build_client();
-        // The dossier is assigned as a JSON-encoded string literal, terminated by a semicolon
-        // before the closing </script> tag.
-        let room_dossier_re =
-            Regex::new(r#"window\.initialRoomDossier\s*=\s*("(?:[^"\\]|\\.)*");"#).unwrap();
+    pub fn new(ctx: &Context) -> Self {
+        let client = GuardedClient::new(ctx);
+        let room_dossier_re = room_dossier_regex();
 
         Self {
             client,
@@ -125,27 +118,38 @@ impl Chaturbate {
         let url = format!("https://chaturbate.com/{username}/");
         debug!(%url, "fetching chaturbate page");
 
-        let response = self.client.get(&url).send().await?;
-        let html = response.text().await?;
+        let url = Url::parse(&url).map_err(|_| Error::InvalidUsername)?;
+        let options = FetchOptions {
+            content_types: &["text/html"],
+            ..FetchOptions::default()
+        };
+        let html = self.client.fetch(&url, &options).await?.text();
 
         let dossier = parse_room_dossier_with_re(&self.room_dossier_re, &html)?;
         debug!(?dossier, "parsed room dossier");
 
-        let msg = if dossier.room_status == "offline" {
-            format!("{} (\x0foffline\x0310)", dossier.broadcaster_username)
+        let reply = Reply::tagged("Chaturbate").text(format!("{} (", dossier.broadcaster_username));
+        let reply = if dossier.room_status == "offline" {
+            reply.value("offline").text(")")
         } else {
-            format!(
-                "{} (\x0f{}\x0310) - {}",
-                dossier.broadcaster_username, dossier.broadcaster_gender, dossier.room_title
-            )
+            reply
+                .value(&dossier.broadcaster_gender)
+                .text(format!(") - {}", dossier.room_title))
         };
 
-        client.send_privmsg(channel, format_message(&msg))?;
+        client.send_privmsg(channel, reply)?;
 
         Ok(())
     }
 }
 
+/// Returns the regex that matches the room dossier in a room page.
+fn room_dossier_regex() -> Regex {
+    // The dossier is assigned as a JSON-encoded string literal, terminated by a semicolon before
+    // the closing </script> tag.
+    Regex::new(r#"window\.initialRoomDossier\s*=\s*("(?:[^"\\]|\\.)*");"#).unwrap()
+}
+
 fn parse_room_dossier_with_re(re: &Regex, html: &str) -> Result<RoomDossier, Error> {
     let caps = re.captures(html).ok_or(Error::DossierNotFound)?;
     // The captured group is the outer JSON string, e.g. `"{ ... }"`.
@@ -175,10 +179,6 @@ fn extract_username(url: &Url) -> Option<String> {
     }
 }
 
-fn format_message(msg: &str) -> String {
-    format!("\x0310>\x0F \x02Chaturbate:\x02\x0310 {msg}")
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -192,9 +192,8 @@ mod tests {
     #[test]
     fn test_parse_room_dossier_fiery_redhead() {
         let html = fixture_html("fiery_redhead");
-        let plugin = Chaturbate::new();
-        let dossier = parse_room_dossier_with_re(&plugin.room_dossier_re, &html)
-            .expect("should parse dossier");
+        let dossier =
+            parse_room_dossier_with_re(&room_dossier_regex(), &html).expect("should parse dossier");
 
         assert_eq!(dossier.broadcaster_username, "fiery_redhead");
         assert_eq!(dossier.room_status, "public");
@@ -208,9 +207,8 @@ mod tests {
     #[test]
     fn test_parse_room_dossier_milabunny() {
         let html = fixture_html("milabunny_");
-        let plugin = Chaturbate::new();
-        let dossier = parse_room_dossier_with_re(&plugin.room_dossier_re, &html)
-            .expect("should parse dossier");
+        let dossier =
+            parse_room_dossier_with_re(&room_dossier_regex(), &html).expect("should parse dossier");
 
         assert_eq!(dossier.broadcaster_username, "milabunny_");
         assert_eq!(dossier.room_status, "public");
@@ -1,5 +1,6 @@
 use rand::prelude::IteratorRandom;
 
+use crate::i18n::Locale;
 use crate::plugin::prelude::*;
 
 pub struct Choices;
@@ -19,14 +20,14 @@ impl Plugin<Context> for Choices {
 
     async fn handle_message(
         &self,
-        _ctx: &Context,
+        ctx: &Context,
         client: &Client,
         message: &Message,
     ) -> Result<(), ZetaError> {
         if let Command::PRIVMSG(ref channel, ref inner_message) = message.command {
-            let current_nickname = client.current_nickname();
+            let current_nickname = ctx.nickname();
 
-            if let Some(msg) = strip_nick_prefix(inner_message, current_nickname)
+            if let Some(msg) = strip_nick_prefix(inner_message, &current_nickname)
                 && let Some(options) = extract_options(msg)
             {
                 let source_nickname = message.source_nickname().unwrap_or("");
@@ -51,23 +52,18 @@ fn strip_nick_prefix<'a>(s: &'a str, current_nickname: &'a str) -> Option<&'a st
     })
 }
 
+/// Extracts the options from a question such as `a, b or c?` in any locale.
 fn extract_options(s: &str) -> Option<Vec<&str>> {
-    let mut parts = s.splitn(2, " eller ");
-
-    if let (Some(first), Some(last)) = (parts.next(), parts.next()) {
+    Locale::ALL.into_iter().find_map(|locale| {
+        let separator = format!(" {} ", locale.format("choices-or", &[]));
+        let (first, last) = s.split_once(separator.as_str())?;
         let mut options: Vec<&str> = first.split(", ").collect();
 
         // If the last option ends with a question mark, skip it.
-        if let Some(last) = last.strip_suffix('?') {
-            options.push(last);
-        } else {
-            options.push(last);
-        }
-
-        return Some(options);
-    }
+        options.push(last.strip_suffix('?').unwrap_or(last));
 
-    None
+        Some(options)
+    })
 }
 
 #[cfg(test)]
@@ -100,6 +96,12 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_should_extract_options_in_every_locale() {
+        assert_eq!(extract_options("a or b?"), Some(vec!["a", "b"]));
+        assert_eq!(extract_options("a eller b?"), Some(vec!["a", "b"]));
+    }
+
     #[test]
     fn it_should_extract_options_stripping_questionmark() {
         assert_eq!(extract_options("a eller b?"), Some(vec!["a", "b"]));
@@ -1,5 +1,3 @@
-use std::fmt::{self, Display};
-
 use dendanskeordbog::DictionaryDocument;
 
 use crate::{http, plugin::prelude::*};
@@ -9,43 +7,36 @@ pub struct DenDanskeOrdbog {
     command: Prefix,
 }
 
-struct MessageFormatter(DictionaryDocument);
-
-impl Display for MessageFormatter {
-    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if let Some(entry) = self.0.entries.first() {
-            let word = &entry.head.keyword;
-            write!(fmt, "\x0310>\x0f\x02 DDO:\x02\x0310 {word}")?;
+/// Returns a reply that presents the first entry of the document.
+fn reply(document: &DictionaryDocument) -> Reply {
+    let Some(entry) = document.entries.first() else {
+        return Reply::tagged("DDO").text("No results");
+    };
+    let mut reply = Reply::tagged("DDO").text(&entry.head.keyword);
 
-            if let Some(phonetic) = &entry.phonetic {
-                write!(fmt, " {phonetic}")?;
-            }
+    if let Some(phonetic) = &entry.phonetic {
+        reply = reply.text(format!(" {phonetic}"));
+    }
 
-            let pos = &entry.pos;
-            write!(fmt, " (\x0f{pos}\x0310)")?;
+    reply = reply.text(" (").value(&entry.pos).text(")");
 
-            if let Some(inflection) = &entry.morphology {
-                write!(fmt, " Bøjning:\x0f {inflection}\x0310")?;
-            }
+    if let Some(inflection) = &entry.morphology {
+        reply = reply.field("Bøjning", inflection);
+    }
 
-            if let Some(etymology) = &entry.etymology {
-                write!(fmt, " Oprindelse:\x0f {etymology}\x0310")?;
-            }
+    if let Some(etymology) = &entry.etymology {
+        reply = reply.field("Oprindelse", etymology);
+    }
 
-            if let Some(definition) = &entry.definitions.first() {
-                let description = &definition.description;
-                write!(fmt, " Definition:\x0f {description}\x0310")?;
+    if let Some(definition) = &entry.definitions.first() {
+        reply = reply.field("Definition", &definition.description);
 
-                if let Some(example) = &definition.examples.first() {
-                    write!(fmt, " Eksempel:\x0f {example}\x0310")?;
-                }
-            }
-        } else {
-            write!(fmt, "\x0310> No results")?;
+        if let Some(example) = &definition.examples.first() {
+            reply = reply.field("Eksempel", example);
         }
-
-        Ok(())
     }
+
+    reply
 }
 
 #[async_trait]
@@ -71,14 +62,20 @@ impl Plugin<Context> for DenDanskeOrdbog {
             && let Some(args) = self.command.parse(user_message)
         {
             if args.is_empty() {
-                client.send_privmsg(channel, "\x0310> Usage: .ddo\x0f <query>")?;
+                client.send_privmsg(
+                    channel,
+                    Reply::tagged("DDO").text("Usage: .ddo").value(" <query>"),
+                )?;
             } else {
                 match self.client.query(args).await {
                     Ok(document) => {
-                        client.send_privmsg(channel, MessageFormatter(document).to_string())?;
+                        client.send_privmsg(channel, reply(&document))?;
                     }
                     Err(err) => {
-                        client.send_privmsg(channel, format!("\x0310> Error: {err}"))?;
+                        client.send_privmsg(
+                            channel,
+                            Reply::tagged("DDO").text(format!("Error: {err}")),
+                        )?;
                     }
                 }
             }
@@ -91,7 +88,7 @@ impl Plugin<Context> for DenDanskeOrdbog {
 impl DenDanskeOrdbog {
     pub fn new() -> DenDanskeOrdbog {
         let http_client = http::build_client();
-        let client = dendanskeordbog::Client::with_client(http_client);
+        let client = dendanskeordbog::Client::with_client(http_client).with_send(http::send_boxed);
         let command = Prefix::new(".ddo");
 
         DenDanskeOrdbog { client, command }
@@ -1,4 +1,3 @@
-use std::fmt::Display;
 use std::str::FromStr;
 
 use argh::FromArgs;
@@ -17,7 +16,7 @@ use crate::plugin::prelude::*;
 /// DNS lookup utility
 #[derive(FromArgs, Debug)]
 pub struct Opts {
-    /// the name of the domain to look to look up
+    /// the name of the domain to look up
     #[argh(positional)]
     name: String,
     /// the type of record to look up
@@ -31,21 +30,22 @@ pub struct Opts {
 
 #[derive(Error, Debug, Diagnostic)]
 pub enum Error {
-    #[error("could not parse arguments")]
-    ParseArguments,
     #[error("could not resolve domain: {0}")]
     Resolve(#[source] NetError),
 }
 
 pub struct Dig {
-    command: Prefix,
+    command: Typed<Opts>,
     resolver: TokioResolver,
 }
 
 pub struct LookupResult(Lookup);
 
-impl Display for LookupResult {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl LookupResult {
+    /// Returns a reply for each answer of the lookup.
+    fn replies(&self) -> Vec<Reply> {
+        let mut replies = Vec::new();
+
         for lookup in self.0.answers() {
             // We need to convert the fields to strings for string padding to work.
             let name = lookup.name.to_string();
@@ -54,14 +54,12 @@ impl Display for LookupResult {
             let record_type = lookup.record_type().to_string();
             let data = &lookup.data;
 
-            write!(fmt, "\x0310>\x0f\x02 Dig:\x02\x0310 ")?;
-            writeln!(
-                fmt,
+            replies.push(Reply::tagged("Dig").text(format!(
                 "{name:<25} {ttl:<7} {dns_class:<7} {record_type:<7} {data}"
-            )?;
+            )));
         }
 
-        Ok(())
+        replies
     }
 }
 
@@ -75,8 +73,9 @@ impl Plugin<Context> for Dig {
         opts.ip_strategy = LookupIpStrategy::Ipv6thenIpv4;
         let resolver = Resolver::builder_with_config(config, TokioRuntimeProvider::default())
             .with_options(opts)
-            .build().map_err(plugin_err)?;
-        let command = Prefix::new(".dig");
+            .build()
+            .map_err(plugin_err)?;
+        let command = Typed::new(".dig", "Dig");
 
         Ok(Dig { command, resolver })
     }
@@ -88,38 +87,28 @@ impl Plugin<Context> for Dig {
         }
     }
 
+    fn accepts_private_messages(&self) -> bool {
+        true
+    }
+
     async fn handle_message(
         &self,
         _ctx: &Context,
         client: &Client,
         message: &Message,
     ) -> Result<(), ZetaError> {
-        if let Command::PRIVMSG(ref channel, ref user_message) = message.command
-            && let Some(args) = self.command.parse(user_message)
+        if let Command::PRIVMSG(_, ref user_message) = message.command
+            && let Some(target) = message.reply_target()
+            && let Some(opts) = self.command.handle(client, target, user_message)?
         {
-            let sub_args = shlex::split(args)
-                .ok_or_else(|| ZetaError::Plugin(Box::new(Error::ParseArguments)))?;
-            let sub_args_ref = sub_args.iter().map(String::as_ref).collect::<Vec<_>>();
-
-            match Opts::from_args(&[".dig"], &sub_args_ref) {
-                Ok(opts) => match self.resolve(&opts.name, opts.record_type).await {
-                    Ok(result) => {
-                        for line in result.to_string().lines() {
-                            client.send_privmsg(channel, line)?;
-                        }
-                    }
-                    Err(err) => {
-                        client.send_privmsg(
-                            channel,
-                            format!("\x0310>\x03\x02 Dig:\x02\x0310 {err}"),
-                        )?;
+            match self.resolve(&opts.name, opts.record_type).await {
+                Ok(result) => {
+                    for reply in result.replies() {
+                        client.send_privmsg(target, reply)?;
                     }
-                },
+                }
                 Err(err) => {
-                    client.send_privmsg(
-                        channel,
-                        format!("\x0310>\x03\x02 Dig:\x02\x0310 {}", err.output),
-                    )?;
+                    client.send_privmsg(target, Reply::tagged("Dig").text(err))?;
                 }
             }
         }
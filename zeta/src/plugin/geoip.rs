@@ -1,21 +1,18 @@
-
-use std::fmt::Display;
-
 use argh::FromArgs;
-use reqwest::redirect::Policy;
 use serde::Deserialize;
 use thiserror::Error;
 use tracing::{debug, error, info};
 use url::Host;
 
-use crate::{consts::HTTP_TIMEOUT, plugin::prelude::*};
+use crate::http::{self, RequestBuilderExt};
+use crate::plugin::prelude::*;
 
 const BASE_URL: &str = "https://api.ip2location.io";
 
 pub struct GeoIp {
     pub client: reqwest::Client,
     api_key: String,
-    command: Prefix,
+    command: Typed<Opts>,
 }
 
 #[derive(Default)]
@@ -23,8 +20,6 @@ pub struct LookupResult(IpInfo);
 
 #[derive(Debug, Error)]
 pub enum Error {
-    #[error("could not parse arguments")]
-    ParseArguments,
     #[error("could not deserialize response: {0}")]
     Deserialize(#[source] reqwest::Error),
     #[error("http request failed")]
@@ -40,7 +35,7 @@ pub enum Error {
 /// Geographical lookup utility based on IP address
 #[derive(FromArgs, Debug)]
 pub struct Opts {
-    /// the name of the domain to look to look up
+    /// the domain name or ip address to look up
     #[argh(positional)]
     name: String,
 }
@@ -83,12 +78,9 @@ impl Plugin<Context> for GeoIp {
     fn new(_ctx: &Context) -> Result<GeoIp, ZetaError> {
         let api_key = require_env("GEOIP_API_KEY")?;
 
-        let client = reqwest::Client::builder()
-            .redirect(Policy::none())
-            .timeout(HTTP_TIMEOUT)
-            .build().map_err(plugin_err)?;
+        let client = http::build_client();
 
-        let command = Prefix::new(".geoip");
+        let command = Typed::new(".geoip", "GeoIP");
 
         Ok(GeoIp {
             client,
@@ -110,32 +102,15 @@ impl Plugin<Context> for GeoIp {
         client: &Client,
         message: &Message,
     ) -> Result<(), ZetaError> {
-        if let Command::PRIVMSG(ref channel, ref message) = message.command
-            && let Some(args) = self.command.parse(message)
+        if let Command::PRIVMSG(ref channel, ref user_message) = message.command
+            && let Some(opts) = self.command.handle(client, channel, user_message)?
         {
-            let sub_args = shlex::split(args)
-                .ok_or_else(|| ZetaError::Plugin(Box::new(Error::ParseArguments)))?;
-            let sub_args_ref = sub_args.iter().map(String::as_ref).collect::<Vec<_>>();
-
-            match Opts::from_args(&[".geoip"], &sub_args_ref) {
-                Ok(opts) => match self.resolve(&opts.name).await {
-                    Ok(result) => {
-                        for line in result.to_string().lines() {
-                            client.send_privmsg(channel, line)?;
-                        }
-                    }
-                    Err(err) => {
-                        client.send_privmsg(
-                            channel,
-                            format!("\x0310>\x03\x02 GeoIP:\x02\x0310 {err}"),
-                        )?;
-                    }
-                },
+            match self.resolve(&opts.name).await {
+                Ok(result) => {
+                    client.send_privmsg(channel, result.reply())?;
+                }
                 Err(err) => {
-                    client.send_privmsg(
-                        channel,
-                        format!("\x0310>\x03\x02 GeoIP:\x02\x0310 {}", err.output),
-                    )?;
+                    client.send_privmsg(channel, Reply::tagged("GeoIP").text(err))?;
                 }
             }
         }
@@ -144,47 +119,30 @@ impl Plugin<Context> for GeoIp {
     }
 }
 
-impl Display for IpInfo {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut parts = Vec::new();
-
-        if !self.asn_name.is_empty() {
-            parts.push(format!("AS:\x03 {}\x0310", self.asn_name));
-        }
-
-        if !self.asn.is_empty() {
-            parts.push(format!("ASN:\x03 {}\x0310", self.asn));
-        }
-
-        if !self.country_name.is_empty() {
-            parts.push(format!("Country:\x03 {}\x0310", self.country_name));
-        }
-
-        if !self.region_name.is_empty() {
-            parts.push(format!("Region:\x03 {}\x0310", self.region_name));
-        }
+impl LookupResult {
+    /// Returns a reply that presents the location and network of the IP address.
+    fn reply(&self) -> Reply {
+        let info = &self.0;
+        let fields = [
+            ("AS", &info.asn_name),
+            ("ASN", &info.asn),
+            ("Country", &info.country_name),
+            ("Region", &info.region_name),
+            ("City", &info.city_name),
+        ];
+        let mut reply = Reply::tagged("GeoIP").value(&info.ip).text(" -");
 
-        if !self.city_name.is_empty() {
-            parts.push(format!("City:\x03 {}\x0310", self.city_name));
+        if fields.iter().all(|(_, value)| value.is_empty()) {
+            return reply.text(" No location data available");
         }
 
-        if parts.is_empty() {
-            write!(fmt, "No location data available")
-        } else {
-            write!(fmt, "{}", parts.join("\x0310 "))
+        for (key, value) in fields {
+            if !value.is_empty() {
+                reply = reply.field(key, value);
+            }
         }
-    }
-}
-
-impl Display for LookupResult {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let info = &self.0;
-        let ip = &info.ip;
 
-        write!(
-            fmt,
-            "\x0310>\x03\x02 GeoIP\x02\x0310 (\x0f{ip}\x0310): {info}"
-        )
+        reply
     }
 }
 
@@ -216,7 +174,7 @@ impl GeoIp {
             ("format", "json"),
         ];
         let request = self.client.get(BASE_URL).query(&params);
-        let response = request.send().await?;
+        let response = request.send_shared().await?;
 
         match response.error_for_status() {
             Ok(response) => {
@@ -1,5 +1,3 @@
-use std::fmt::Write;
-
 use miette::{Diagnostic, Result};
 use reqwest::{
     self,
@@ -9,15 +7,12 @@ use serde::Deserialize;
 use thiserror::Error;
 use tracing::{error, info};
 
+use crate::http::RequestBuilderExt;
 use crate::{http, plugin::prelude::*};
 
 /// Custom error types for the GitHub plugin.
 #[derive(Debug, Error, Diagnostic)]
 pub enum Error {
-    #[error("Failed to initialize GitHub HTTP client")]
-    #[diagnostic(code(github::init))]
-    InitFailed(#[source] reqwest::Error),
-
     #[error("Failed to perform GitHub search request")]
     #[diagnostic(code(github::search::request))]
     SearchRequestFailed(#[source] reqwest::Error),
@@ -35,6 +30,8 @@ pub enum Error {
 /// Holds the HTTP client to reuse connection pools.
 pub struct GitHubPlugin {
     http: reqwest::Client,
+    /// Headers sent with every GitHub API request.
+    headers: HeaderMap,
     command: Prefix,
 }
 
@@ -58,9 +55,7 @@ struct RepoItem {
 #[async_trait]
 impl Plugin<Context> for GitHubPlugin {
     fn new(_ctx: &Context) -> Result<Self, ZetaError> {
-        let plugin = GitHubPlugin::new()
-            .map_err(|e| ZetaError::Plugin(Box::new(std::io::Error::other(e))))?;
-        Ok(plugin)
+        Ok(GitHubPlugin::new())
     }
 
     fn metadata() -> Metadata {
@@ -82,7 +77,7 @@ impl Plugin<Context> for GitHubPlugin {
             if let Ok(Some(response)) = self.handle_command(channel, Some(args)).await {
                 client.send_privmsg(channel, response)?;
             } else {
-                client.send_privmsg(channel, "no results")?;
+                client.send_privmsg(channel, Reply::tagged("GitHub").text("No results"))?;
             }
         }
         Ok(())
@@ -91,8 +86,9 @@ impl Plugin<Context> for GitHubPlugin {
 
 impl GitHubPlugin {
     /// Create a new instance of the GitHub plugin.
-    /// Initializes a generic HTTP client with standard timeouts.
-    pub fn new() -> Result<Self> {
+    /// Uses the shared HTTP client, with the GitHub API headers added to each request.
+    #[must_use]
+    pub fn new() -> Self {
         let cmd = Prefix::new(".gh");
         let mut headers = HeaderMap::new();
         headers.insert(
@@ -104,15 +100,11 @@ impl GitHubPlugin {
             HeaderValue::from_static("2022-11-28"),
         );
 
-        let client = http::client::builder()
-            .default_headers(headers)
-            .build()
-            .map_err(Error::InitFailed)?;
-
-        Ok(Self {
-            http: client,
+        Self {
+            http: http::build_client(),
+            headers,
             command: cmd,
-        })
+        }
     }
 
     /// The main entry point for processing the `.gh` command.
@@ -122,16 +114,12 @@ impl GitHubPlugin {
     /// * `args` - The command arguments (the query).
     ///
     /// # Returns
-    /// * `Result<Option<String>>` - Some(message) to reply, or None if no reply needed.
-    pub async fn handle_command(
-        &self,
-        channel: &str,
-        args: Option<&str>,
-    ) -> Result<Option<String>> {
+    /// * `Result<Option<Reply>>` - Some(reply) to send, or None if no reply needed.
+    pub async fn handle_command(&self, channel: &str, args: Option<&str>) -> Result<Option<Reply>> {
         // 1. Check arguments
         let query = match args {
             Some(q) if !q.trim().is_empty() => q.trim(),
-            _ => return Ok(Some(Self::usage_information())),
+            _ => return Ok(Some(Reply::tagged("GitHub").text(".gh <query>"))),
         };
 
         info!("Searching GitHub for '{}' in channel {}", query, channel);
@@ -141,14 +129,16 @@ impl GitHubPlugin {
             Ok(response) => {
                 // 3. Process Result
                 response.items.first().map_or_else(
-                    || Ok(Some(Self::format_message("No results"))),
-                    |first_result| Ok(Some(Self::format_repo_details(first_result))),
+                    || Ok(Some(Reply::tagged("GitHub").text("No results"))),
+                    |first_result| Ok(Some(Self::repo_details(first_result))),
                 )
             }
             Err(e) => {
                 error!("GitHub API error: {:?}", e);
                 // In a real bot, you might want to sanitize this error message
-                Ok(Some(Self::format_message(&format!("http error: {e}"))))
+                Ok(Some(
+                    Reply::tagged("GitHub").text(format!("http error: {e}")),
+                ))
             }
         }
     }
@@ -160,8 +150,9 @@ impl GitHubPlugin {
         let response = self
             .http
             .get("https://api.github.com/search/repositories")
+            .headers(self.headers.clone())
             .query(&params)
-            .send()
+            .send_shared()
             .await
             .map_err(Error::SearchRequestFailed)?;
 
@@ -176,43 +167,27 @@ impl GitHubPlugin {
         Ok(search_data)
     }
 
-    /// Formats a specific repository item into an IRC-friendly string.
-    fn format_repo_details(item: &RepoItem) -> String {
-        let mut line = String::new();
+    /// Returns a reply that presents a repository item.
+    fn repo_details(item: &RepoItem) -> Reply {
+        let mut reply = Reply::tagged("GitHub");
 
-        // Fork icon: "\x0f\u2442\x0310 "
         if item.fork {
-            let _ = write!(line, "\x0F\u{2442}\x0310 ");
+            reply = reply.value("\u{2442} ");
         }
 
-        line.push_str(&item.full_name);
+        reply = reply.text(&item.full_name);
 
         if let Some(desc) = &item.description {
-            let _ = write!(line, " - {desc}");
+            reply = reply.text(format!(" - {desc}"));
         }
 
-        // " -\x0f <url>"
-        let _ = write!(line, " -\x0F {}", item.html_url);
-
-        // "\x0310 - Language:\x0f <lang>\x0310 Stars:\x0f <stars>"
         let lang = item.language.as_deref().unwrap_or("?");
-        let _ = write!(
-            line,
-            "\x0310 - Language:\x0F {}\x0310 Stars:\x0F {}",
-            lang, item.stargazers_count
-        );
-
-        Self::format_message(&line)
-    }
-
-    /// Usage information helper.
-    fn usage_information() -> String {
-        Self::format_message(".gh <query>")
-    }
 
-    /// Formats the final message with the standard Zeta/Blur prefix.
-    /// Ruby: %(\x0310>\x0F\x02 GitHub:\x02\x0310 #{message})
-    fn format_message(message: &str) -> String {
-        format!("\x0310>\x0F\x02 GitHub:\x02\x0310 {message}")
+        reply
+            .text(" - ")
+            .link(&item.html_url)
+            .text(" -")
+            .field("Language", lang)
+            .field("Stars", item.stargazers_count)
     }
 }
@@ -6,6 +6,7 @@
 use serde::Deserialize;
 use tracing::{debug, warn};
 
+use crate::http::RequestBuilderExt;
 use crate::{http, plugin::prelude::*};
 
 /// Google Image Search plugin structure.
@@ -96,7 +97,12 @@ impl Plugin<Context> for GoogleImages {
             && let Some(query) = self.command.parse(user_message)
         {
             if query.trim().is_empty() {
-                client.send_privmsg(channel, "\x0310> Usage: .gis\x0f <query>")?;
+                client.send_privmsg(
+                    channel,
+                    Reply::tagged("Google")
+                        .text("Usage: .gis")
+                        .value(" <query>"),
+                )?;
                 return Ok(());
             }
 
@@ -106,15 +112,20 @@ impl Plugin<Context> for GoogleImages {
                     let url = result.original_image.url;
                     client.send_privmsg(
                         channel,
-                        format!("\x0310>\x0f\x02 Google:\x02\x0310 {snippet} - {url}"),
+                        Reply::tagged("Google")
+                            .text(format!("{snippet} - "))
+                            .link(url),
                     )?;
                 }
                 Err(Error::NoResults) => {
-                    client.send_privmsg(channel, "\x0310> No results")?;
+                    client.send_privmsg(channel, Reply::tagged("Google").text("No results"))?;
                 }
                 Err(err) => {
                     warn!(?err, "google image search failed");
-                    client.send_privmsg(channel, format!("\x0310> Error: {err}"))?;
+                    client.send_privmsg(
+                        channel,
+                        Reply::tagged("Google").text(format!("Error: {err}")),
+                    )?;
                 }
             }
         }
@@ -148,7 +159,7 @@ impl GoogleImages {
             .get("https://www.google.com/search")
             .query(&params)
             .header("Accept", "*/*")
-            .send()
+            .send_shared()
             .await?;
 
         let body = response.text().await?;
@@ -1,7 +1,5 @@
-use std::fmt::Display;
-
-use tokio::runtime::Handle;
-
+use crate::i18n::{self, Locale, args};
+use crate::metrics::Snapshot;
 use crate::plugin::prelude::*;
 
 pub struct Health {
@@ -9,20 +7,6 @@ pub struct Health {
     command: Prefix,
 }
 
-/// Process telemetry snapshot.
-pub struct Snapshot {
-    /// The RSS memory usage, as bytes.
-    pub phys_mem: f64,
-    /// The VMS memory usage, as bytes.
-    pub virt_mem: f64,
-    /// The number of tasks currently scheduled in the runtime's global queue.
-    pub global_queue_depth: usize,
-    /// The current number of alive tasks in the runtime.
-    pub num_alive_tasks: usize,
-    /// The number of worker threads used by the runtime.
-    pub num_workers: usize,
-}
-
 #[async_trait]
 impl Plugin<Context> for Health {
     fn new(_ctx: &Context) -> Result<Health, ZetaError> {
@@ -40,7 +24,7 @@ impl Plugin<Context> for Health {
 
     async fn handle_message(
         &self,
-        _ctx: &Context,
+        ctx: &Context,
         client: &Client,
         message: &Message,
     ) -> Result<(), ZetaError> {
@@ -48,72 +32,55 @@ impl Plugin<Context> for Health {
             && let Some(_) = self.command.parse(user_message)
             && let Some(snapshot) = Snapshot::capture()
         {
-            client.send_privmsg(
-                channel,
-                format!("\x0310>\x0f\x02 Health\x02\x0310: {snapshot}"),
-            )?;
-        }
-
-        Ok(())
-    }
-}
+            let locale = i18n::locale(ctx, message).await;
 
-impl Snapshot {
-    #[allow(clippy::cast_precision_loss)]
-    pub fn capture() -> Option<Snapshot> {
-        if let Some(memory) = memory_stats::memory_stats() {
-            // Capture memory information
-            let phys_mem = memory.physical_mem as f64 / 1024.0 / 1024.0;
-            let virt_mem = memory.virtual_mem as f64 / 1024.0 / 1024.0;
-
-            // Capture tokio runtime information
-            let metrics = Handle::current().metrics();
-            let num_workers = metrics.num_workers();
-            let num_alive_tasks = metrics.num_alive_tasks();
-            let global_queue_depth = metrics.global_queue_depth();
-
-            return Some(Snapshot {
-                phys_mem,
-                virt_mem,
-                global_queue_depth,
-                num_alive_tasks,
-                num_workers,
-            });
+            client.send_privmsg(channel, reply(&snapshot, locale))?;
         }
 
-        None
+        Ok(())
     }
 }
 
-impl Display for Snapshot {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let phys_mem = self.phys_mem;
-        let virt_mem = self.virt_mem;
-
-        write!(fmt, "Memory usage:\x0f {phys_mem:.2} MiB\x0310 ")?;
-        write!(fmt, "(\x0f{virt_mem:.2} MiB\x0310 virtual) ")?;
-
-        write!(fmt, "Workers:\x0f {}\x0310 ", self.num_workers)?;
-        write!(fmt, "Tasks:\x0f {}\x0310 ", self.num_alive_tasks)?;
-        write!(fmt, "(\x0f{}\x0310 scheduled)", self.global_queue_depth)?;
-
-        Ok(())
-    }
+/// Builds a reply describing `snapshot`.
+fn reply(snapshot: &Snapshot, locale: Locale) -> Reply {
+    let phys_mem = snapshot.phys_mem / 1024.0 / 1024.0;
+    let virt_mem = snapshot.virt_mem / 1024.0 / 1024.0;
+
+    let reply = locale.reply(
+        Reply::tagged("Health"),
+        "health-memory",
+        args!(
+            physical = format!("{phys_mem:.2}"),
+            virtual = format!("{virt_mem:.2}")
+        ),
+    );
+
+    locale.reply(
+        reply.text(" "),
+        "health-runtime",
+        args!(
+            workers = snapshot.num_workers,
+            tasks = snapshot.num_alive_tasks,
+            scheduled = snapshot.global_queue_depth
+        ),
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use irc::proto::FormattedStringExt;
     use wildmatch::WildMatch;
+    use zeta_plugin::reply::{Output, Theme};
 
     #[tokio::test]
     async fn it_should_format_message() {
         let snapshot = Snapshot::capture().expect("could not capture");
-        let snapshot_message = snapshot.to_string().strip_formatting();
-        let wildmatcher =
-            WildMatch::new("Memory usage: * MiB (* MiB virtual) Workers: * Tasks: * (* scheduled)");
+        let snapshot_message =
+            reply(&snapshot, Locale::English).render(Output::Plain, &Theme::default());
+        let wildmatcher = WildMatch::new(
+            "> Health: Memory usage: * MiB (* MiB virtual) Workers: * Tasks: * (* scheduled)",
+        );
         assert!(wildmatcher.matches(&snapshot_message));
     }
 }
@@ -0,0 +1,75 @@
+//! Usage of the typed commands of other plugins.
+
+use argh::FromArgs;
+
+use crate::command;
+use crate::plugin::prelude::*;
+
+/// List commands, or show the usage of a command
+#[derive(FromArgs, Debug)]
+pub struct Opts {
+    /// the command to show the usage of, e.g. dig
+    #[argh(positional)]
+    command: Option<String>,
+}
+
+pub struct Help {
+    /// The `.help` command.
+    command: Typed<Opts>,
+}
+
+#[async_trait]
+impl Plugin<Context> for Help {
+    fn new(_ctx: &Context) -> Result<Help, ZetaError> {
+        let command = Typed::new(".help", "Help");
+
+        Ok(Help { command })
+    }
+
+    fn metadata() -> Metadata {
+        Metadata {
+            name: "help".into(),
+            authors: vec!["Mikkel Kroman <mk@maero.dk>".into()],
+        }
+    }
+
+    fn accepts_private_messages(&self) -> bool {
+        true
+    }
+
+    async fn handle_message(
+        &self,
+        _ctx: &Context,
+        client: &Client,
+        message: &Message,
+    ) -> Result<(), ZetaError> {
+        if let Command::PRIVMSG(_, ref user_message) = message.command
+            && let Some(target) = message.reply_target()
+            && let Some(opts) = self.command.handle(client, target, user_message)?
+        {
+            match opts.command {
+                Some(name) => match command::help(&name) {
+                    Some(help) => {
+                        for reply in help.replies("Help") {
+                            client.send_privmsg(target, reply)?;
+                        }
+                    }
+                    None => {
+                        let reply = Reply::tagged("Help").text("Unknown command ").value(name);
+
+                        client.send_privmsg(target, reply)?;
+                    }
+                },
+                None => {
+                    let reply = Reply::tagged("Help")
+                        .text("Commands: ")
+                        .value(command::commands().join(", "));
+
+                    client.send_privmsg(target, reply)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -11,6 +11,7 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 use tracing::{debug, warn};
 
+use crate::http::RequestBuilderExt;
 use crate::{http, plugin::prelude::*};
 
 const BASE_URL: &str = "https://howlongtobeat.com";
@@ -231,22 +232,30 @@ impl Plugin<Context> for HowLongToBeat {
             && let Some(query) = self.command.parse(user_message)
         {
             if query.trim().is_empty() {
-                client.send_privmsg(channel, "\x0310> Usage: .hltb\x0f <game>")?;
+                client.send_privmsg(
+                    channel,
+                    Reply::tagged("HLTB").text("Usage: .hltb").value(" <game>"),
+                )?;
                 return Ok(());
             }
 
             match self.search(query).await {
                 Ok(games) => {
                     if let Some(game) = games.first() {
-                        let msg = format_game(game);
-                        client.send_privmsg(channel, msg)?;
+                        client.send_privmsg(channel, game_reply(game))?;
                     } else {
-                        client.send_privmsg(channel, "\x0310> No results found")?;
+                        client.send_privmsg(
+                            channel,
+                            Reply::tagged("HLTB").text("No results found"),
+                        )?;
                     }
                 }
                 Err(err) => {
                     warn!(?err, "hltb search failed");
-                    client.send_privmsg(channel, format!("\x0310> Failed to fetch data: {err}"))?;
+                    client.send_privmsg(
+                        channel,
+                        Reply::tagged("HLTB").text(format!("Failed to fetch data: {err}")),
+                    )?;
                 }
             }
         }
@@ -281,7 +290,7 @@ impl HowLongToBeat {
             .client
             .get(&url)
             .header(REFERER, REFERER_URL)
-            .send()
+            .send_shared()
             .await?;
 
         let init: InitResponse = response
@@ -354,7 +363,7 @@ impl HowLongToBeat {
             .header("x-hp-val", &auth.hp_val)
             .header(CONTENT_TYPE, "application/json")
             .json(&body)
-            .send()
+            .send_shared()
             .await?;
 
         let response_data: SearchResponse = response
@@ -367,16 +376,14 @@ impl HowLongToBeat {
     }
 }
 
-/// Formats a parsed `Game` struct into an IRC-friendly text string.
-fn format_game(game: &Game) -> String {
-    let main = format_seconds(game.comp_main);
-    let main_extra = format_seconds(game.comp_plus);
-    let completionist = format_seconds(game.comp_100);
-
-    format!(
-        "\x0310>\x03\x02 HLTB\x02\x0310 (\x0f{}\x0310): Main Story: \x0f{}\x0310 | Main + Extra: \x0f{}\x0310 | Completionist: \x0f{}",
-        game.game_name, main, main_extra, completionist
-    )
+/// Returns a reply that presents the completion times of a parsed `Game`.
+fn game_reply(game: &Game) -> Reply {
+    Reply::tagged("HLTB")
+        .value(&game.game_name)
+        .text(" -")
+        .field("Main Story", format_seconds(game.comp_main))
+        .field("Main + Extra", format_seconds(game.comp_plus))
+        .field("Completionist", format_seconds(game.comp_100))
 }
 
 /// Converts a duration in seconds into a human-readable hours and minutes string.
@@ -1,6 +1,4 @@
-
-use std::sync::OnceLock;
-
+use jiff::tz::TimeZone;
 use regex::Regex;
 use serde::Deserialize;
 use time::{
@@ -8,21 +6,31 @@ use time::{
 };
 use tracing::{debug, warn};
 
+use crate::http::RequestBuilderExt;
+use crate::i18n::{self, Locale, args};
+use crate::preferences;
 use crate::{http, plugin::prelude::*};
 
 const API_BASE_URL: &str = "https://maps.googleapis.com";
 
-static RE_OPENING_TIME: OnceLock<Regex> = OnceLock::new();
-static RE_CLOSING_TIME: OnceLock<Regex> = OnceLock::new();
-static RE_IS_OPEN: OnceLock<Regex> = OnceLock::new();
-static RE_IS_CLOSED: OnceLock<Regex> = OnceLock::new();
-
 /// Plugin that allows users to query opening hours for places using the Google Maps API.
 ///
-/// It supports natural language queries in Danish, such as "hvornår åbner X?" or "er X åben?".
+/// It understands natural language queries in every locale, such as "hvornår åbner X?" or
+/// "is X open?", and replies in the locale of the user. Places are assumed to be in the saved
+/// timezone of the user when Google doesn't know their UTC offset.
 pub struct IsItOpen {
     client: reqwest::Client,
     api_key: String,
+    /// The queries that are understood, in every locale.
+    queries: Vec<Queries>,
+}
+
+/// The patterns of the queries in a single locale.
+struct Queries {
+    opening_time: Regex,
+    closing_time: Regex,
+    is_open: Regex,
+    is_closed: Regex,
 }
 
 /// Errors that can occur during plugin execution.
@@ -89,14 +97,19 @@ struct TimePoint {
 }
 
 impl PlaceDetails {
-    /// Returns the current time at the place, taking its UTC offset into account.
-    /// Defaults to UTC if no offset is provided.
-    fn local_now(&self) -> OffsetDateTime {
-        let now = OffsetDateTime::now_utc();
+    /// Returns `now` at the place, taking its UTC offset into account.
+    ///
+    /// Falls back to the offset of `timezone` if no offset is provided, and to UTC if neither is.
+    fn local_time(&self, now: OffsetDateTime, timezone: Option<&TimeZone>) -> OffsetDateTime {
+        if let Some(offset_minutes) = self.utc_offset {
+            return now + Duration::minutes(i64::from(offset_minutes));
+        }
 
-        self.utc_offset.map_or(now, |offset_minutes| {
-            now + Duration::minutes(i64::from(offset_minutes))
-        })
+        timezone
+            .zip(jiff::Timestamp::from_second(now.unix_timestamp()).ok())
+            .map_or(now, |(timezone, timestamp)| {
+                now + Duration::seconds(i64::from(timezone.to_offset(timestamp).seconds()))
+            })
     }
 
     /// Checks if the place is currently open based on the `open_now` field.
@@ -175,9 +188,53 @@ fn parse_hhmm(s: &str) -> Option<Time> {
 
 /// Formats a "HHMM" string into "HH:MM".
 fn format_time_string(s: &str) -> Option<String> {
-    let time = parse_hhmm(s)?;
+    parse_hhmm(s).map(format_time)
+}
+
+/// Formats a time as "HH:MM".
+fn format_time(time: Time) -> String {
+    format!("{:02}:{:02}", time.hour(), time.minute())
+}
+
+impl Queries {
+    /// Compiles the query patterns for `locale`.
+    fn new(locale: Locale) -> Queries {
+        let patterns = match locale {
+            Locale::Danish => [
+                r"(?i)^(?:hvornår|hvad tid) åbner (?P<place>.*?)\?$",
+                r"(?i)^(?:hvornår|hvad tid) lukker (?P<place>.*?)\?$",
+                r"(?i)^(?:har|er) (?P<place>.*?) (?:åbent|åben)\?$",
+                r"(?i)^(?:har|er) (?P<place>.*?) lukket\?$",
+            ],
+            Locale::English => [
+                r"(?i)^(?:when|what time) does (?P<place>.*?) open\?$",
+                r"(?i)^(?:when|what time) does (?P<place>.*?) close\?$",
+                r"(?i)^is (?P<place>.*?) open\?$",
+                r"(?i)^is (?P<place>.*?) closed\?$",
+            ],
+        };
+        let [opening_time, closing_time, is_open, is_closed] =
+            patterns.map(|pattern| Regex::new(pattern).unwrap());
+
+        Queries {
+            opening_time,
+            closing_time,
+            is_open,
+            is_closed,
+        }
+    }
 
-    Some(format!("{:02}:{:02}", time.hour(), time.minute()))
+    /// Returns the action and place name of `query`, if it's a known query.
+    fn parse(&self, query: &str) -> Option<(QueryAction, String)> {
+        [
+            (QueryAction::OpeningTime, &self.opening_time),
+            (QueryAction::ClosingTime, &self.closing_time),
+            (QueryAction::IsOpen, &self.is_open),
+            (QueryAction::IsClosed, &self.is_closed),
+        ]
+        .into_iter()
+        .find_map(|(action, regex)| Some((action, regex.captures(query)?["place"].to_string())))
+    }
 }
 
 #[async_trait]
@@ -185,21 +242,13 @@ impl Plugin<Context> for IsItOpen {
     fn new(_ctx: &Context) -> Result<Self, ZetaError> {
         let api_key = require_env("GOOGLE_MAPS_API_KEY")?;
         let client = http::build_client();
+        let queries = Locale::ALL.into_iter().map(Queries::new).collect();
 
-        // Initialize regexes (case insensitive)
-        let _ = RE_OPENING_TIME.get_or_init(|| {
-            Regex::new(r"(?i)^(?:hvornår|hvad tid) åbner (?P<place>.*?)\?$").unwrap()
-        });
-        let _ = RE_CLOSING_TIME.get_or_init(|| {
-            Regex::new(r"(?i)^(?:hvornår|hvad tid) lukker (?P<place>.*?)\?$").unwrap()
-        });
-        let _ = RE_IS_OPEN.get_or_init(|| {
-            Regex::new(r"(?i)^(?:har|er) (?P<place>.*?) (?:åbent|åben)\?$").unwrap()
-        });
-        let _ = RE_IS_CLOSED
-            .get_or_init(|| Regex::new(r"(?i)^(?:har|er) (?P<place>.*?) lukket\?$").unwrap());
-
-        Ok(IsItOpen { client, api_key })
+        Ok(IsItOpen {
+            client,
+            api_key,
+            queries,
+        })
     }
 
     fn metadata() -> Metadata {
@@ -211,18 +260,29 @@ impl Plugin<Context> for IsItOpen {
 
     async fn handle_message(
         &self,
-        _ctx: &Context,
+        ctx: &Context,
         client: &Client,
         message: &Message,
     ) -> Result<(), ZetaError> {
         if let Command::PRIVMSG(ref channel, ref inner_message) = message.command {
-            let current_nickname = client.current_nickname();
+            let current_nickname = ctx.nickname();
 
             // Check if the message is addressed to the bot
-            if let Some(msg) = strip_nick_prefix(inner_message, current_nickname)
+            if let Some(msg) = strip_nick_prefix(inner_message, &current_nickname)
                 && let Some(nick) = message.source_nickname()
             {
-                self.process_query(channel, nick, msg, client).await?;
+                let preferences = preferences::load(ctx, message).await;
+                let locale = i18n::preferred_locale(ctx, message, &preferences);
+
+                self.process_query(
+                    channel,
+                    nick,
+                    msg,
+                    locale,
+                    preferences.timezone.as_ref(),
+                    client,
+                )
+                .await?;
             }
         }
         Ok(())
@@ -231,49 +291,47 @@ impl Plugin<Context> for IsItOpen {
 
 impl IsItOpen {
     /// Determines the type of query and processes it.
+    ///
+    /// `timezone` is the saved timezone of the user, if any.
     async fn process_query(
         &self,
         channel: &str,
         nick: &str,
         query: &str,
+        locale: Locale,
+        timezone: Option<&TimeZone>,
         client: &Client,
     ) -> Result<(), ZetaError> {
-        let mut place_name = None;
-        let mut action = QueryAction::None;
-
-        // Determine intent based on regex matches
-        if let Some(caps) = RE_OPENING_TIME.get().unwrap().captures(query) {
-            place_name = Some(caps["place"].to_string());
-            action = QueryAction::OpeningTime;
-        } else if let Some(caps) = RE_CLOSING_TIME.get().unwrap().captures(query) {
-            place_name = Some(caps["place"].to_string());
-            action = QueryAction::ClosingTime;
-        } else if let Some(caps) = RE_IS_OPEN.get().unwrap().captures(query) {
-            place_name = Some(caps["place"].to_string());
-            action = QueryAction::IsOpen;
-        } else if let Some(caps) = RE_IS_CLOSED.get().unwrap().captures(query) {
-            place_name = Some(caps["place"].to_string());
-            action = QueryAction::IsClosed;
-        }
+        let parsed = self.queries.iter().find_map(|queries| queries.parse(query));
 
-        if let Some(place_name) = place_name {
+        if let Some((action, place_name)) = parsed {
             match self.find_place(&place_name).await {
                 Ok(place) => {
-                    let message = match action {
-                        QueryAction::OpeningTime => Self::format_opening_time(&place, nick),
-                        QueryAction::ClosingTime => Self::format_closing_time(&place, nick),
-                        QueryAction::IsOpen => Self::format_is_open(&place, nick),
-                        QueryAction::IsClosed => Self::format_is_closed(&place, nick),
-                        QueryAction::None => return Ok(()),
+                    let now = place.local_time(OffsetDateTime::now_utc(), timezone);
+                    let reply = match action {
+                        QueryAction::OpeningTime => {
+                            Self::opening_time_reply(&place, now, nick, locale)
+                        }
+                        QueryAction::ClosingTime => {
+                            Self::closing_time_reply(&place, now, nick, locale)
+                        }
+                        QueryAction::IsOpen => Self::is_open_reply(&place, now, nick, locale),
+                        QueryAction::IsClosed => Self::is_closed_reply(&place, now, nick, locale),
                     };
-                    client.send_privmsg(channel, &message)?;
+                    client.send_privmsg(channel, reply)?;
                 }
                 Err(Error::NotFound) => {
-                    client.send_privmsg(channel, formatted("Error: place not found"))?;
+                    client.send_privmsg(
+                        channel,
+                        locale.reply(Reply::new(), "isitopen-not-found", &[]),
+                    )?;
                 }
                 Err(e) => {
                     warn!(?e, "isitopen error");
-                    client.send_privmsg(channel, formatted(&format!("Error: {e}")))?;
+                    client.send_privmsg(
+                        channel,
+                        locale.reply(Reply::new(), "isitopen-error", args!(error = e)),
+                    )?;
                 }
             }
         }
@@ -288,7 +346,12 @@ impl IsItOpen {
         let search_url = format!("{API_BASE_URL}/maps/api/place/textsearch/json");
         let params = [("query", query), ("key", &self.api_key)];
 
-        let response = self.client.get(&search_url).query(&params).send().await?;
+        let response = self
+            .client
+            .get(&search_url)
+            .query(&params)
+            .send_shared()
+            .await?;
         let search_res: PlaceSearchResponse = response.json().await?;
 
         if search_res.status != "OK" && search_res.status != "ZERO_RESULTS" {
@@ -311,7 +374,7 @@ impl IsItOpen {
             .client
             .get(&details_url)
             .query(&details_params)
-            .send()
+            .send_shared()
             .await?;
         let details_res: PlaceDetailsResponse = response.json().await?;
 
@@ -322,140 +385,230 @@ impl IsItOpen {
         Ok(details_res.result)
     }
 
-    fn format_opening_time(place: &PlaceDetails, nick: &str) -> String {
+    /// Returns the reply to the query, where `now` is the local time at the place.
+    fn opening_time_reply(
+        place: &PlaceDetails,
+        now: OffsetDateTime,
+        nick: &str,
+        locale: Locale,
+    ) -> Reply {
         let name = &place.name;
-        let now = place.local_now();
 
         if place.is_always_open() {
-            format!("{nick}: \x02{name}\x02 har døgnåbent")
+            locale.reply(
+                Reply::new(),
+                "isitopen-always-open",
+                args!(nick = nick, name = name),
+            )
         } else if place.is_open_now() {
-            place.opening_time(now).map_or_else(|| format!("{nick}: \x02{name}\x02 har allerede åbent"), |opening_time| format!(
-                    "{nick}: \x02{name}\x02 har allerede åbent - de åbnede kl. \x02{opening_time}\x02"
-                ))
-        } else if let Some(opening_time) = place.opening_time(now) {
-            format!("{nick}: \x02{name}\x02 åbner kl. \x02{opening_time}\x02")
+            place.opening_time(now).map_or_else(
+                || {
+                    locale.reply(
+                        Reply::new(),
+                        "isitopen-already-open",
+                        args!(nick = nick, name = name),
+                    )
+                },
+                |opening| {
+                    locale.reply(
+                        Reply::new(),
+                        "isitopen-already-open-since",
+                        args!(nick = nick, name = name, opening = opening),
+                    )
+                },
+            )
+        } else if let Some(opening) = place.opening_time(now) {
+            locale.reply(
+                Reply::new(),
+                "isitopen-opens-at",
+                args!(nick = nick, name = name, opening = opening),
+            )
         } else {
-            format!("{nick}: pas - \x02{name}\x02 har ikke nogen åbningstid")
+            locale.reply(
+                Reply::new(),
+                "isitopen-no-opening-time",
+                args!(nick = nick, name = name),
+            )
         }
     }
 
-    fn format_closing_time(place: &PlaceDetails, nick: &str) -> String {
+    /// Returns the reply to the query, where `now` is the local time at the place.
+    fn closing_time_reply(
+        place: &PlaceDetails,
+        now: OffsetDateTime,
+        nick: &str,
+        locale: Locale,
+    ) -> Reply {
         let name = &place.name;
-        let now = place.local_now();
 
         if place.is_always_open() {
-            format!("{nick}: \x02{name}\x02 har døgnåbent")
+            locale.reply(
+                Reply::new(),
+                "isitopen-always-open",
+                args!(nick = nick, name = name),
+            )
         } else if place.is_open_now() {
             let closing = place
                 .closing_time(now)
-                .unwrap_or_else(|| "ukendt tid".to_string());
+                .unwrap_or_else(|| locale.format("isitopen-unknown-time", &[]));
             let opening = place
                 .opening_time(now)
-                .unwrap_or_else(|| "ukendt tid".to_string());
-            format!(
-                "{nick}: \x02{name}\x02 lukker kl. \x02{closing}\x02 - de åbnede kl. \x02{opening}\x02"
+                .unwrap_or_else(|| locale.format("isitopen-unknown-time", &[]));
+
+            locale.reply(
+                Reply::new(),
+                "isitopen-closes-at",
+                args!(
+                    nick = nick,
+                    name = name,
+                    closing = closing,
+                    opening = opening
+                ),
             )
         } else {
             let (open_time, close_time) = place.open_and_close_time(now);
 
             if let (Some(open), Some(close)) = (open_time, close_time) {
-                let now_time = now.time();
-                let closing_str = format!("{:02}:{:02}", close.hour(), close.minute());
-                let opening_str = format!("{:02}:{:02}", open.hour(), open.minute());
-
-                if open >= now_time {
-                    format!(
-                        "{nick}: \x02{name}\x02 lukker kl. \x02{closing_str}\x02, men de har ikke åbent endnu - de åbner først kl. \x02{opening_str}\x02"
+                if open >= now.time() {
+                    locale.reply(
+                        Reply::new(),
+                        "isitopen-closes-at-not-open-yet",
+                        args!(
+                            nick = nick,
+                            name = name,
+                            closing = format_time(close),
+                            opening = format_time(open)
+                        ),
                     )
                 } else {
-                    format!("{nick}: \x02{name}\x02 har lukket for resten af dagen")
+                    locale.reply(
+                        Reply::new(),
+                        "isitopen-closed-for-the-rest-of-the-day",
+                        args!(nick = nick, name = name),
+                    )
                 }
             } else {
-                format!("{nick}: pas - \x02{name}\x02 har ikke nogen lukketid")
+                locale.reply(
+                    Reply::new(),
+                    "isitopen-no-closing-time",
+                    args!(nick = nick, name = name),
+                )
             }
         }
     }
 
-    fn format_is_open(place: &PlaceDetails, nick: &str) -> String {
+    /// Returns the reply to the query, where `now` is the local time at the place.
+    fn is_open_reply(
+        place: &PlaceDetails,
+        now: OffsetDateTime,
+        nick: &str,
+        locale: Locale,
+    ) -> Reply {
         let name = &place.name;
-        let now = place.local_now();
 
         if place.is_always_open() {
-            format!("{nick}: ja, \x02{name}\x02 har døgnåbent")
+            locale.reply(
+                Reply::new(),
+                "isitopen-yes-always-open",
+                args!(nick = nick, name = name),
+            )
         } else if place.is_open_now() {
             let opening = place
                 .opening_time(now)
-                .unwrap_or_else(|| "ukendt tid".to_string());
-            format!("{nick}: ja, \x02{name}\x02 åbnede kl. \x02{opening}\x02 i dag")
+                .unwrap_or_else(|| locale.format("isitopen-unknown-time", &[]));
+
+            locale.reply(
+                Reply::new(),
+                "isitopen-yes-opened-at",
+                args!(nick = nick, name = name, opening = opening),
+            )
         } else {
             let (open_time, close_time) = place.open_and_close_time(now);
 
             if let (Some(open), Some(_close)) = (open_time, close_time) {
-                let now_time = now.time();
-                let opening_str = format!("{:02}:{:02}", open.hour(), open.minute());
-
-                if open >= now_time {
-                    format!(
-                        "{nick}: nej, \x02{name}\x02 har lukket, men de åbner kl. \x02{opening_str}\x02"
+                if open >= now.time() {
+                    locale.reply(
+                        Reply::new(),
+                        "isitopen-no-closed-opens-at",
+                        args!(nick = nick, name = name, opening = format_time(open)),
                     )
                 } else {
-                    format!("{nick}: nej, \x02{name}\x02 har lukket for i dag")
+                    locale.reply(
+                        Reply::new(),
+                        "isitopen-no-closed-today",
+                        args!(nick = nick, name = name),
+                    )
                 }
             } else {
-                format!("{nick}: pas - \x02{name}\x02 har ikke nogen åben- og lukketid")
+                locale.reply(
+                    Reply::new(),
+                    "isitopen-no-opening-hours",
+                    args!(nick = nick, name = name),
+                )
             }
         }
     }
 
-    fn format_is_closed(place: &PlaceDetails, nick: &str) -> String {
+    /// Returns the reply to the query, where `now` is the local time at the place.
+    fn is_closed_reply(
+        place: &PlaceDetails,
+        now: OffsetDateTime,
+        nick: &str,
+        locale: Locale,
+    ) -> Reply {
         let name = &place.name;
-        let now = place.local_now();
 
         if place.is_always_open() {
-            format!("{nick}: nej, \x02{name}\x02 har døgnåbent")
+            locale.reply(
+                Reply::new(),
+                "isitopen-no-always-open",
+                args!(nick = nick, name = name),
+            )
         } else if place.is_open_now() {
             let opening = place
                 .opening_time(now)
-                .unwrap_or_else(|| "ukendt tid".to_string());
+                .unwrap_or_else(|| locale.format("isitopen-unknown-time", &[]));
             let closing = place
                 .closing_time(now)
-                .unwrap_or_else(|| "ukendt tid".to_string());
-            format!(
-                "{nick}: nej, \x02{name}\x02 åbnede kl. \x02{opening}\x02 og lukker kl. \x02{closing}\x02 i dag"
+                .unwrap_or_else(|| locale.format("isitopen-unknown-time", &[]));
+
+            locale.reply(
+                Reply::new(),
+                "isitopen-no-open-until",
+                args!(
+                    nick = nick,
+                    name = name,
+                    opening = opening,
+                    closing = closing
+                ),
             )
         } else {
             let (open_time, _close_time) = place.open_and_close_time(now);
 
-            open_time.map_or_else(|| format!("{nick}: ja, \x02{name}\x02 har lukket for i dag"), |open| {
-                let now_time = now.time();
-                let opening_str = format!("{:02}:{:02}", open.hour(), open.minute());
-
-                if open >= now_time {
-                    format!(
-                        "{nick}: ja, \x02{name}\x02 har lukket, men de åbner kl. \x02{opening_str}\x02"
-                    )
-                } else {
-                    format!("{nick}: ja, \x02{name}\x02 har lukket for i dag")
-                }
-            })
+            match open_time {
+                Some(open) if open >= now.time() => locale.reply(
+                    Reply::new(),
+                    "isitopen-yes-closed-opens-at",
+                    args!(nick = nick, name = name, opening = format_time(open)),
+                ),
+                _ => locale.reply(
+                    Reply::new(),
+                    "isitopen-yes-closed-today",
+                    args!(nick = nick, name = name),
+                ),
+            }
         }
     }
 }
 
 /// Helper enum to map regex matches to actions.
 enum QueryAction {
-    None,
     OpeningTime,
     ClosingTime,
     IsOpen,
     IsClosed,
 }
 
-/// Applies IRC teal color formatting.
-fn formatted(s: &str) -> String {
-    format!("\x0310{s}")
-}
-
 /// Helper to strip the bot's nickname from the message start.
 fn strip_nick_prefix<'a>(s: &'a str, current_nickname: &'a str) -> Option<&'a str> {
     s.strip_prefix(current_nickname).and_then(|s| {
@@ -491,6 +644,22 @@ mod tests {
         assert_eq!(format_time_string("invalid"), None);
     }
 
+    #[test]
+    fn test_parse_queries() {
+        let danish = Queries::new(Locale::Danish);
+        let english = Queries::new(Locale::English);
+
+        assert!(matches!(
+            danish.parse("hvornår åbner Netto?"),
+            Some((QueryAction::OpeningTime, place)) if place == "Netto"
+        ));
+        assert!(matches!(
+            english.parse("is the library closed?"),
+            Some((QueryAction::IsClosed, place)) if place == "the library"
+        ));
+        assert!(english.parse("hvornår åbner Netto?").is_none());
+    }
+
     #[test]
     fn test_place_details_is_always_open() {
         let json = r#"{
@@ -506,6 +675,29 @@ mod tests {
         assert!(place.is_always_open());
     }
 
+    #[test]
+    fn test_local_time_falls_back_to_user_timezone() {
+        let now = OffsetDateTime::from_unix_timestamp(1_750_000_000).unwrap();
+        let timezone = TimeZone::get("Europe/Copenhagen").unwrap();
+        let place: PlaceDetails = serde_json::from_str(r#"{ "name": "Netto" }"#).unwrap();
+
+        // Copenhagen is two hours ahead of UTC in the summer
+        assert_eq!(place.local_time(now, None), now);
+        assert_eq!(
+            place.local_time(now, Some(&timezone)),
+            now + Duration::hours(2)
+        );
+
+        // The offset of the place itself is preferred
+        let place: PlaceDetails =
+            serde_json::from_str(r#"{ "name": "Walmart", "utc_offset": -300 }"#).unwrap();
+
+        assert_eq!(
+            place.local_time(now, Some(&timezone)),
+            now - Duration::hours(5)
+        );
+    }
+
     #[test]
     fn test_place_details_is_open_now() {
         let json = r#"{
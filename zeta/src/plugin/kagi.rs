@@ -79,16 +79,20 @@ impl Plugin<Context> for KagiPlugin {
             match results {
                 Ok(results) => {
                     if let Some(result) = results.first() {
-                        let title = &result.title;
-                        let url = &result.url;
+                        let reply = Reply::tagged("Kagi")
+                            .text(format!("{} - ", result.title))
+                            .link(&result.url);
 
-                        client.send_privmsg(channel, format!("\x0310> {title} - {url}"))?;
+                        client.send_privmsg(channel, reply)?;
                     } else {
-                        client.send_privmsg(channel, "\x0310> No results")?;
+                        client.send_privmsg(channel, Reply::tagged("Kagi").text("No results"))?;
                     }
                 }
                 Err(err) => {
-                    client.send_privmsg(channel, format!("Error: {err}"))?;
+                    client.send_privmsg(
+                        channel,
+                        Reply::tagged("Kagi").text(format!("Error: {err}")),
+                    )?;
                 }
             }
         }
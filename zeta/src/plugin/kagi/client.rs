@@ -1,7 +1,7 @@
 use std::{sync::Arc, time::Instant};
 
 use regex::Regex;
-use reqwest::header::{ACCEPT, SET_COOKIE};
+use reqwest::header::{ACCEPT, CACHE_CONTROL, SET_COOKIE};
 use scraper::{ElementRef, Html, Node, Selector};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -9,6 +9,7 @@ use tokio::sync::RwLock;
 use tracing::{debug, error};
 
 use crate::http;
+use crate::http::RequestBuilderExt;
 
 use super::{Error, KAGI_SESSION_DURATION, SearchResult};
 
@@ -68,10 +69,11 @@ impl Client {
         let req = self
             .http
             .get("https://kagi.com/search")
+            .header(CACHE_CONTROL, "no-cache")
             .query(&[("token", &self.token)]);
         debug!(?req, "requesting session cookies");
 
-        let res = req.send().await.map_err(Error::RequestSession)?;
+        let res = req.send_shared().await.map_err(Error::RequestSession)?;
         if !res.headers().contains_key(SET_COOKIE) {
             error!("the response does not include set-cookie headers!");
             return Err(Error::SessionCookies);
@@ -79,8 +81,11 @@ impl Client {
 
         // Request the main page to receive a nonce for the first search.
         debug!("requesting nonce");
-        let req = self.http.get("https://kagi.com/");
-        let res = req.send().await.map_err(Error::RequestNonce)?;
+        let req = self
+            .http
+            .get("https://kagi.com/")
+            .header(CACHE_CONTROL, "no-cache");
+        let res = req.send_shared().await.map_err(Error::RequestNonce)?;
         let body = res.text().await.map_err(Error::ReadNonce)?;
 
         extract_nonce(&body).ok_or(Error::Nonce)
@@ -128,7 +133,7 @@ impl Client {
             .header(ACCEPT, "application/vnd.kagi.stream")
             .query(&[("q", query)]);
         debug!(?req, "searching for {query}");
-        let res = req.send().await.map_err(|_| Error::SearchRequest)?;
+        let res = req.send_shared().await.map_err(|_| Error::SearchRequest)?;
         let body = res.text().await.map_err(|_| Error::SearchRequestBody)?;
         let stream_msgs = parse_kagi_stream(&body);
         let search_results = parse_search_result_messages(&stream_msgs);
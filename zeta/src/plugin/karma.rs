@@ -0,0 +1,499 @@
+//! Karma tracking.
+//!
+//! Users give or take karma by writing `thing++` or `thing--` in a channel, optionally followed
+//! by a reason after a `#`, e.g. `zeta++ # for being helpful`. Multi-word subjects can be wrapped
+//! in parentheses, e.g. `(rust analyzer)++`.
+//!
+//! Subjects are compared using the server's `CASEMAPPING`, users can't change their own karma and
+//! each user has to wait for a cooldown between karma changes in a channel.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use irc::client::prelude::ChannelExt;
+use tracing::error;
+
+use crate::command;
+use crate::database::karma::{self, Entry};
+use crate::isupport::CaseMapping;
+use crate::plugin::prelude::*;
+
+/// The maximum number of karma changes that are applied from a single message.
+const MAX_CHANGES_PER_MESSAGE: usize = 5;
+
+/// The number of entries shown in leaderboards.
+const LEADERBOARD_SIZE: i64 = 5;
+
+/// The number of recent reasons shown when looking up karma.
+const NUM_RECENT_REASONS: i64 = 3;
+
+/// Karma tracking plugin.
+pub struct Karma {
+    /// The `.karma` command trigger.
+    command: Prefix,
+    /// The minimum duration between karma changes from the same user in a channel.
+    cooldown: Duration,
+    /// The last time each (channel, user) pair changed karma.
+    last_changes: Mutex<HashMap<(String, String), Instant>>,
+}
+
+/// Errors that can occur when handling karma.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("irc error: {0}")]
+    Irc(#[from] irc::error::Error),
+}
+
+/// A single karma change found in a message.
+#[derive(Debug, Eq, PartialEq)]
+struct Change<'a> {
+    /// The subject whose karma is changed, as written by the user.
+    subject: &'a str,
+    /// Either `1` or `-1`.
+    delta: i16,
+    /// The reason given for the change.
+    reason: Option<&'a str>,
+}
+
+#[async_trait]
+impl Plugin<Context> for Karma {
+    fn new(ctx: &Context) -> Result<Karma, ZetaError> {
+        let command = Prefix::new(".karma");
+        let cooldown = ctx.config.plugins.karma.cooldown;
+
+        Ok(Karma {
+            command,
+            cooldown,
+            last_changes: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn metadata() -> Metadata {
+        Metadata {
+            name: "karma".into(),
+            authors: vec!["Mikkel Kroman <mk@maero.dk>".into()],
+        }
+    }
+
+    async fn handle_message(
+        &self,
+        ctx: &Context,
+        client: &Client,
+        message: &Message,
+    ) -> Result<(), ZetaError> {
+        if let Command::PRIVMSG(ref channel, ref user_message) = message.command
+            && channel.is_channel_name()
+            && let Some(nick) = message.source_nickname()
+        {
+            let result = if let Some(args) = self.command.parse(user_message) {
+                self.handle_command(ctx, client, channel, args.trim()).await
+            } else {
+                let changes = parse_changes(user_message);
+
+                if changes.is_empty() {
+                    Ok(())
+                } else {
+                    command::invoked("karma");
+                    self.apply_changes(ctx, client, channel, nick, changes)
+                        .await
+                }
+            };
+
+            if let Err(err) = result {
+                error!(error = %err, "could not handle karma");
+                client.send_privmsg(
+                    channel,
+                    Reply::new().text("Something went wrong, try again later"),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Karma {
+    /// Handles the `.karma` command.
+    async fn handle_command(
+        &self,
+        ctx: &Context,
+        client: &Client,
+        channel: &str,
+        args: &str,
+    ) -> Result<(), Error> {
+        let casemapping = ctx.casemapping();
+        let channel_key = casemapping.fold(channel);
+
+        match args {
+            "" => {
+                client.send_privmsg(
+                    channel,
+                    Reply::new()
+                        .text("Usage: .karma")
+                        .value(" <thing> | top | bottom"),
+                )?;
+            }
+            "top" => {
+                let entries = karma::top(&ctx.db, &channel_key, LEADERBOARD_SIZE).await?;
+
+                client.send_privmsg(channel, leaderboard("Top karma", &entries))?;
+            }
+            "bottom" => {
+                let entries = karma::bottom(&ctx.db, &channel_key, LEADERBOARD_SIZE).await?;
+
+                client.send_privmsg(channel, leaderboard("Bottom karma", &entries))?;
+            }
+            subject => {
+                let subject_key = casemapping.fold(subject);
+
+                match karma::get(&ctx.db, &channel_key, &subject_key).await? {
+                    Some(entry) => {
+                        let reasons = karma::recent_reasons(
+                            &ctx.db,
+                            &channel_key,
+                            &subject_key,
+                            NUM_RECENT_REASONS,
+                        )
+                        .await?;
+                        let mut reply = Reply::new()
+                            .text("Karma for ")
+                            .value(&entry.display_name)
+                            .text(": ")
+                            .value(entry.score);
+
+                        if !reasons.is_empty() {
+                            let reasons: Vec<String> =
+                                reasons.iter().map(|r| format!("“{r}”")).collect();
+
+                            reply = reply
+                                .text(" (recently: ")
+                                .value(reasons.join(", "))
+                                .text(")");
+                        }
+
+                        client.send_privmsg(channel, reply)?;
+                    }
+                    None => {
+                        client.send_privmsg(
+                            channel,
+                            Reply::new().value(subject).text(" has no karma"),
+                        )?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Applies the karma changes given by `nick` in `channel`.
+    async fn apply_changes(
+        &self,
+        ctx: &Context,
+        client: &Client,
+        channel: &str,
+        nick: &str,
+        changes: Vec<Change<'_>>,
+    ) -> Result<(), Error> {
+        let casemapping = ctx.casemapping();
+        let channel_key = casemapping.fold(channel);
+        let giver = casemapping.fold(nick);
+
+        if let Some(remaining) = self.cooldown_remaining(&channel_key, &giver) {
+            client.send_notice(
+                nick,
+                format!(
+                    "You have to wait {} seconds before changing karma in {channel} again",
+                    remaining.as_secs().max(1)
+                ),
+            )?;
+
+            return Ok(());
+        }
+
+        let mut changed = false;
+
+        for change in merge_changes(changes, casemapping)
+            .into_iter()
+            .take(MAX_CHANGES_PER_MESSAGE)
+        {
+            let subject = casemapping.fold(change.subject);
+
+            if subject == giver {
+                client.send_notice(nick, "You can't change your own karma")?;
+                continue;
+            }
+
+            let score = karma::adjust(
+                &ctx.db,
+                &channel_key,
+                &subject,
+                change.subject,
+                nick,
+                change.delta,
+                change.reason,
+            )
+            .await?;
+            changed = true;
+
+            client.send_privmsg(
+                channel,
+                Reply::new()
+                    .text("Karma for ")
+                    .value(change.subject)
+                    .text(" is now ")
+                    .value(score),
+            )?;
+        }
+
+        if changed {
+            self.record_change(channel_key, giver);
+        }
+
+        Ok(())
+    }
+
+    /// Returns how long `giver` has to wait before changing karma in `channel` again, if at all.
+    fn cooldown_remaining(&self, channel: &str, giver: &str) -> Option<Duration> {
+        let last_changes = self.last_changes.lock().unwrap();
+        let last_change = last_changes.get(&(channel.to_string(), giver.to_string()))?;
+
+        self.cooldown.checked_sub(last_change.elapsed())
+    }
+
+    /// Records that `giver` just changed karma in `channel`.
+    fn record_change(&self, channel: String, giver: String) {
+        let mut last_changes = self.last_changes.lock().unwrap();
+
+        last_changes.retain(|_, instant| instant.elapsed() < self.cooldown);
+        last_changes.insert((channel, giver), Instant::now());
+    }
+}
+
+/// Finds all karma changes in a message.
+fn parse_changes(s: &str) -> Vec<Change<'_>> {
+    let mut changes = Vec::new();
+    let mut rest = s;
+
+    while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+        rest = &rest[start..];
+
+        let (change, tail) = parse_parenthesized(rest).unwrap_or_else(|| {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let (word, tail) = rest.split_at(end);
+
+            (parse_word(word), tail)
+        });
+
+        if let Some(mut change) = change {
+            let reason = tail
+                .trim_start()
+                .strip_prefix('#')
+                .map(str::trim)
+                .filter(|reason| !reason.is_empty());
+
+            if reason.is_some() {
+                change.reason = reason;
+                changes.push(change);
+
+                break;
+            }
+
+            changes.push(change);
+        }
+
+        rest = tail;
+    }
+
+    changes
+}
+
+/// Merges the changes to the same subject into a single change, so a subject is changed at most
+/// once per message.
+///
+/// The deltas are summed and clamped to a single point, and changes that cancel each other out
+/// are dropped. The subject is kept as it was first written, and the reason is kept if any of the
+/// changes has one.
+fn merge_changes(changes: Vec<Change<'_>>, casemapping: CaseMapping) -> Vec<Change<'_>> {
+    let mut merged: Vec<(String, Change<'_>)> = Vec::with_capacity(changes.len());
+
+    for change in changes {
+        let key = casemapping.fold(change.subject);
+
+        match merged.iter_mut().find(|(subject, _)| *subject == key) {
+            Some((_, existing)) => {
+                existing.delta += change.delta;
+                existing.reason = existing.reason.or(change.reason);
+            }
+            None => merged.push((key, change)),
+        }
+    }
+
+    merged
+        .into_iter()
+        .filter(|(_, change)| change.delta != 0)
+        .map(|(_, change)| Change {
+            delta: change.delta.signum(),
+            ..change
+        })
+        .collect()
+}
+
+/// Parses a karma change for a parenthesized subject, e.g. `(rust analyzer)++`.
+///
+/// Returns the change and the remaining input if `s` starts with a parenthesized subject.
+fn parse_parenthesized(s: &str) -> Option<(Option<Change<'_>>, &str)> {
+    let inner = s.strip_prefix('(')?;
+    let close = inner.find(')')?;
+    let subject = inner[..close].trim();
+    let after = &inner[close + 1..];
+    let delta = parse_delta(after)?;
+    let tail = &after[2..];
+
+    if !tail.is_empty() && !tail.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let change = is_valid_subject(subject).then_some(Change {
+        subject,
+        delta,
+        reason: None,
+    });
+
+    Some((change, tail))
+}
+
+/// Parses a single word as a karma change, e.g. `zeta++`.
+fn parse_word(word: &str) -> Option<Change<'_>> {
+    let (subject, delta) = match word.strip_suffix("++") {
+        Some(subject) => (subject, 1),
+        None => (word.strip_suffix("--")?, -1),
+    };
+
+    is_valid_subject(subject).then_some(Change {
+        subject,
+        delta,
+        reason: None,
+    })
+}
+
+/// Returns the karma delta if `s` starts with `++` or `--`.
+fn parse_delta(s: &str) -> Option<i16> {
+    if s.starts_with("++") {
+        Some(1)
+    } else if s.starts_with("--") {
+        Some(-1)
+    } else {
+        None
+    }
+}
+
+/// Returns whether `subject` can be given karma.
+fn is_valid_subject(subject: &str) -> bool {
+    subject.chars().any(char::is_alphanumeric) && !subject.ends_with(['+', '-'])
+}
+
+/// Returns a reply that lists `entries` with their scores.
+fn leaderboard(title: &str, entries: &[Entry]) -> Reply {
+    if entries.is_empty() {
+        return Reply::new().text("Nobody has any karma yet");
+    }
+
+    let mut reply = Reply::new().text(format!("{title}: "));
+
+    for (idx, entry) in entries.iter().enumerate() {
+        if idx > 0 {
+            reply = reply.text(", ");
+        }
+
+        reply = reply
+            .value(&entry.display_name)
+            .text(" (")
+            .value(entry.score)
+            .text(")");
+    }
+
+    reply
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change<'a>(subject: &'a str, delta: i16, reason: Option<&'a str>) -> Change<'a> {
+        Change {
+            subject,
+            delta,
+            reason,
+        }
+    }
+
+    #[test]
+    fn it_should_parse_simple_changes() {
+        assert_eq!(parse_changes("zeta++"), vec![change("zeta", 1, None)]);
+        assert_eq!(parse_changes("zeta--"), vec![change("zeta", -1, None)]);
+        assert_eq!(
+            parse_changes("mk++ and zeta--"),
+            vec![change("mk", 1, None), change("zeta", -1, None)]
+        );
+    }
+
+    #[test]
+    fn it_should_parse_reasons() {
+        assert_eq!(
+            parse_changes("zeta++ # for being helpful"),
+            vec![change("zeta", 1, Some("for being helpful"))]
+        );
+        assert_eq!(
+            parse_changes("mk++ zeta-- #too slow"),
+            vec![change("mk", 1, None), change("zeta", -1, Some("too slow"))]
+        );
+        assert_eq!(parse_changes("zeta++ #"), vec![change("zeta", 1, None)]);
+    }
+
+    #[test]
+    fn it_should_parse_parenthesized_subjects() {
+        assert_eq!(
+            parse_changes("(rust analyzer)++ # it works"),
+            vec![change("rust analyzer", 1, Some("it works"))]
+        );
+        assert_eq!(parse_changes("(foo)++bar"), vec![]);
+    }
+
+    #[test]
+    fn it_should_ignore_non_changes() {
+        assert!(parse_changes("hello world").is_empty());
+        assert!(parse_changes("this -- that").is_empty());
+        assert!(parse_changes("++ --").is_empty());
+        assert!(parse_changes("a+++").is_empty());
+        assert!(parse_changes("i++i").is_empty());
+        assert!(parse_changes("").is_empty());
+    }
+
+    #[test]
+    fn it_should_merge_changes_to_the_same_subject() {
+        let casemapping = CaseMapping::Rfc1459;
+
+        assert_eq!(
+            merge_changes(parse_changes("zeta++ Zeta++ ZETA++"), casemapping),
+            vec![change("zeta", 1, None)]
+        );
+        assert_eq!(
+            merge_changes(parse_changes("zeta++ mk-- zeta++ # twice"), casemapping),
+            vec![change("zeta", 1, Some("twice")), change("mk", -1, None)]
+        );
+        assert_eq!(
+            merge_changes(parse_changes("zeta++ zeta-- mk++"), casemapping),
+            vec![change("mk", 1, None)]
+        );
+    }
+
+    #[test]
+    fn it_should_handle_unicode() {
+        assert_eq!(parse_changes("blåbær++"), vec![change("blåbær", 1, None)]);
+        assert!(parse_changes("æ").is_empty());
+    }
+}
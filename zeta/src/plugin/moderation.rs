@@ -0,0 +1,815 @@
+//! Channel moderation.
+//!
+//! Channel operators and admins can kick, ban and mute users with `.kick`, `.ban`, `.unban` and
+//! `.mute`. Bans and mutes given a duration are stored in the database and lifted once they
+//! expire, also if the bot was restarted in the meantime.
+//!
+//! Channels can have a policy in the configuration that ops or voices users when they join, and
+//! kicks or bans users who flood, repeat themselves or shout. Admins and users with a membership
+//! mode such as voice are exempt from the limits. Policies are only enforced in channels where
+//! the bot is an operator.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use argh::FromArgs;
+use irc::client::prelude::ChannelExt;
+use time::OffsetDateTime;
+use tracing::{debug, error, warn};
+
+use crate::command::{self, Parsed};
+use crate::config::{CapsLimit, ModerationAction, ModerationConfig, ModerationPolicy};
+use crate::database::bans;
+use crate::permissions;
+use crate::plugin::prelude::*;
+
+/// The tag of replies.
+const TAG: &str = "Moderation";
+
+/// How long the recent messages of a user are remembered after they last said something.
+const ACTIVITY_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// The list mode of bans.
+const BAN_MODE: char = 'b';
+
+/// Kick a user from the channel
+#[derive(FromArgs, Debug)]
+pub struct KickOpts {
+    /// the nickname of the user to kick
+    #[argh(positional)]
+    nick: String,
+    /// why the user is kicked
+    #[argh(positional)]
+    reason: Vec<String>,
+}
+
+/// Ban a user or a mask from the channel, and kick the users it matches
+#[derive(FromArgs, Debug)]
+pub struct BanOpts {
+    /// how long the ban lasts, e.g. 10m, 2h or 1d; the ban is permanent if not given
+    #[argh(option, short = 'd', from_str_fn(parse_duration))]
+    duration: Option<Duration>,
+    /// the nickname of the user, or a mask such as *!*@example.com
+    #[argh(positional)]
+    target: String,
+    /// why the user is banned
+    #[argh(positional)]
+    reason: Vec<String>,
+}
+
+/// Lift a ban or mute of a user or a mask
+#[derive(FromArgs, Debug)]
+pub struct UnbanOpts {
+    /// lift a mute instead of a ban
+    #[argh(switch, short = 'm')]
+    mute: bool,
+    /// the nickname of the user, or the banned mask
+    #[argh(positional)]
+    target: String,
+}
+
+/// Stop a user or a mask from speaking in the channel
+#[derive(FromArgs, Debug)]
+pub struct MuteOpts {
+    /// how long the mute lasts, e.g. 10m, 2h or 1d; the mute is permanent if not given
+    #[argh(option, short = 'd', from_str_fn(parse_duration))]
+    duration: Option<Duration>,
+    /// the nickname of the user, or a mask such as *!*@example.com
+    #[argh(positional)]
+    target: String,
+    /// why the user is muted
+    #[argh(positional)]
+    reason: Vec<String>,
+}
+
+/// Channel moderation plugin.
+pub struct Moderation {
+    /// The `.kick` command.
+    kick: Typed<KickOpts>,
+    /// The `.ban` command.
+    ban: Typed<BanOpts>,
+    /// The `.unban` command.
+    unban: Typed<UnbanOpts>,
+    /// The `.mute` command.
+    mute: Typed<MuteOpts>,
+    /// The moderation configuration.
+    config: ModerationConfig,
+    /// The recent messages of each (folded channel, folded nickname) pair.
+    activity: Mutex<HashMap<(String, String), Activity>>,
+}
+
+/// Errors that can occur when moderating a channel.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("irc error: {0}")]
+    Irc(#[from] irc::error::Error),
+}
+
+/// The recent messages of a user in a channel.
+#[derive(Debug)]
+struct Activity {
+    /// When the recent messages were sent, oldest first.
+    sent: VecDeque<Instant>,
+    /// The last message, in lowercase.
+    last_message: String,
+    /// The number of times in a row the last message has been repeated.
+    repeats: usize,
+}
+
+/// A moderation command that was invoked, with its parsed arguments.
+enum Invocation {
+    /// `.kick`
+    Kick(Parsed<KickOpts>),
+    /// `.ban`
+    Ban(Parsed<BanOpts>),
+    /// `.unban`
+    Unban(Parsed<UnbanOpts>),
+    /// `.mute`
+    Mute(Parsed<MuteOpts>),
+}
+
+/// A moderation limit that was broken.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Violation {
+    /// Too many messages in too short a time.
+    Flood,
+    /// The same message too many times in a row.
+    Repeat,
+    /// Too many uppercase letters.
+    Caps,
+}
+
+#[async_trait]
+impl Plugin<Context> for Moderation {
+    fn new(ctx: &Context) -> Result<Moderation, ZetaError> {
+        Ok(Moderation {
+            kick: Typed::new(".kick", TAG),
+            ban: Typed::new(".ban", TAG),
+            unban: Typed::new(".unban", TAG),
+            mute: Typed::new(".mute", TAG),
+            config: ctx.config.plugins.moderation.clone(),
+            activity: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn metadata() -> Metadata {
+        Metadata {
+            name: "moderation".into(),
+            authors: vec!["Mikkel Kroman <mk@maero.dk>".into()],
+        }
+    }
+
+    async fn handle_message(
+        &self,
+        ctx: &Context,
+        client: &Client,
+        message: &Message,
+    ) -> Result<(), ZetaError> {
+        match message.command {
+            Command::JOIN(ref channels, ..) => {
+                for channel in channels.split(',') {
+                    self.auto_modes(ctx, client, message, channel)?;
+                }
+            }
+            Command::PRIVMSG(ref channel, ref text) if channel.is_channel_name() => {
+                if let Err(err) = self
+                    .handle_command(ctx, client, message, channel, text)
+                    .await
+                {
+                    error!(error = %err, "could not handle moderation command");
+                    client.send_privmsg(
+                        channel,
+                        Reply::tagged(TAG).text("Something went wrong, try again later"),
+                    )?;
+                }
+
+                if let Err(err) = self.enforce(ctx, client, message, channel, text).await {
+                    warn!(error = %err, "could not enforce moderation policy");
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    async fn handle_tick(&self, ctx: &Context, client: &Client) -> Result<(), ZetaError> {
+        if let Err(err) = self.sweep(ctx, client).await {
+            warn!(error = %err, "could not lift expired bans");
+        }
+
+        Ok(())
+    }
+}
+
+impl Moderation {
+    /// Handles the moderation commands.
+    async fn handle_command(
+        &self,
+        ctx: &Context,
+        client: &Client,
+        message: &Message,
+        channel: &str,
+        text: &str,
+    ) -> Result<(), Error> {
+        let invocation = if let Some(parsed) = self.kick.parse(text) {
+            Invocation::Kick(parsed)
+        } else if let Some(parsed) = self.ban.parse(text) {
+            Invocation::Ban(parsed)
+        } else if let Some(parsed) = self.unban.parse(text) {
+            Invocation::Unban(parsed)
+        } else if let Some(parsed) = self.mute.parse(text) {
+            Invocation::Mute(parsed)
+        } else {
+            return Ok(());
+        };
+
+        if !is_privileged(ctx, message, channel) {
+            let reply = Reply::tagged(TAG).text("You are not allowed to moderate this channel");
+
+            client.send_privmsg(channel, reply)?;
+
+            return Ok(());
+        }
+
+        let set_by = message.source_nickname().unwrap_or_default();
+
+        match invocation {
+            Invocation::Kick(parsed) => {
+                if let Some(opts) = self.kick.respond(client, channel, parsed)? {
+                    let reason = reason(&opts.reason);
+
+                    client.send(Command::KICK(channel.to_string(), opts.nick, reason))?;
+                }
+            }
+            Invocation::Ban(parsed) => {
+                if let Some(opts) = self.ban.respond(client, channel, parsed)? {
+                    let mask = ban_mask(ctx, &opts.target);
+                    let reason = reason(&opts.reason);
+
+                    self.set(
+                        ctx,
+                        client,
+                        channel,
+                        BAN_MODE,
+                        &mask,
+                        reason.as_deref(),
+                        set_by,
+                        opts.duration,
+                    )
+                    .await?;
+                    kick_matching(ctx, client, channel, &mask, reason)?;
+                }
+            }
+            Invocation::Unban(parsed) => {
+                if let Some(opts) = self.unban.respond(client, channel, parsed)? {
+                    let (mode, mask) = if opts.mute {
+                        (
+                            self.config.mute_mode,
+                            self.mute_mask(&ban_mask(ctx, &opts.target)),
+                        )
+                    } else {
+                        (BAN_MODE, ban_mask(ctx, &opts.target))
+                    };
+
+                    bans::remove(&ctx.db, &ctx.casemapping().fold(channel), mode, &mask).await?;
+                    send_mode(client, channel, '-', mode, &mask)?;
+                }
+            }
+            Invocation::Mute(parsed) => {
+                if let Some(opts) = self.mute.respond(client, channel, parsed)? {
+                    let mask = self.mute_mask(&ban_mask(ctx, &opts.target));
+                    let reason = reason(&opts.reason);
+
+                    self.set(
+                        ctx,
+                        client,
+                        channel,
+                        self.config.mute_mode,
+                        &mask,
+                        reason.as_deref(),
+                        set_by,
+                        opts.duration,
+                    )
+                    .await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sets the list mode `mode` on `mask` in `channel`, and records it so it's lifted after
+    /// `duration`.
+    #[allow(clippy::too_many_arguments)]
+    async fn set(
+        &self,
+        ctx: &Context,
+        client: &Client,
+        channel: &str,
+        mode: char,
+        mask: &str,
+        reason: Option<&str>,
+        set_by: &str,
+        duration: Option<Duration>,
+    ) -> Result<(), Error> {
+        let expires_at = duration.and_then(|duration| {
+            let duration = time::Duration::try_from(duration).ok()?;
+
+            OffsetDateTime::now_utc().checked_add(duration)
+        });
+        let folded = ctx.casemapping().fold(channel);
+
+        bans::add(&ctx.db, &folded, mode, mask, reason, set_by, expires_at).await?;
+        send_mode(client, channel, '+', mode, mask)?;
+
+        Ok(())
+    }
+
+    /// Returns the mask that mutes users matching `mask`.
+    fn mute_mask(&self, mask: &str) -> String {
+        format!("{}{mask}", self.config.mute_prefix)
+    }
+
+    /// Returns the policy of `channel`, if it has one.
+    fn policy(&self, ctx: &Context, channel: &str) -> Option<&ModerationPolicy> {
+        let casemapping = ctx.casemapping();
+        let channel = casemapping.fold(channel);
+
+        self.config
+            .channels
+            .iter()
+            .find(|policy| casemapping.fold(&policy.name) == channel)
+    }
+
+    /// Ops or voices the sender of a `JOIN` to `channel` if they match its policy.
+    fn auto_modes(
+        &self,
+        ctx: &Context,
+        client: &Client,
+        message: &Message,
+        channel: &str,
+    ) -> Result<(), irc::error::Error> {
+        let Some(policy) = self.policy(ctx, channel) else {
+            return Ok(());
+        };
+        let Some(nick) = message.source_nickname() else {
+            return Ok(());
+        };
+
+        if !is_operator(ctx, channel, &ctx.nickname()) {
+            return Ok(());
+        }
+
+        let hostmask = permissions::hostmask(message).unwrap_or_default();
+        let account = ctx.user(nick).and_then(|user| user.account);
+        let account = account.as_deref();
+
+        if matches(&policy.auto_op, &hostmask, account) {
+            command::invoked("moderation");
+            debug!(%channel, %nick, "auto-opping user");
+            send_mode(client, channel, '+', 'o', nick)?;
+        } else if matches(&policy.auto_voice, &hostmask, account) {
+            command::invoked("moderation");
+            debug!(%channel, %nick, "auto-voicing user");
+            send_mode(client, channel, '+', 'v', nick)?;
+        }
+
+        Ok(())
+    }
+
+    /// Kicks or bans the sender of `text` if it breaks the policy of `channel`.
+    async fn enforce(
+        &self,
+        ctx: &Context,
+        client: &Client,
+        message: &Message,
+        channel: &str,
+        text: &str,
+    ) -> Result<(), Error> {
+        let Some(policy) = self.policy(ctx, channel) else {
+            return Ok(());
+        };
+        let Some(nick) = message.source_nickname() else {
+            return Ok(());
+        };
+
+        if is_exempt(ctx, message, channel, nick) || !is_operator(ctx, channel, &ctx.nickname()) {
+            return Ok(());
+        }
+
+        let casemapping = ctx.casemapping();
+        let key = (casemapping.fold(channel), casemapping.fold(nick));
+        let violation = {
+            let mut activity = self.activity.lock().unwrap();
+            let violation = activity
+                .entry(key.clone())
+                .or_insert_with(Activity::new)
+                .record(policy, text, Instant::now());
+
+            if violation.is_some() {
+                activity.remove(&key);
+            }
+
+            violation
+        };
+        let Some(violation) = violation else {
+            return Ok(());
+        };
+        let reason = violation.reason().to_string();
+
+        command::invoked("moderation");
+        debug!(%channel, %nick, ?violation, "user broke the channel policy");
+
+        if policy.action == ModerationAction::Ban {
+            let mask = ban_mask(ctx, nick);
+
+            self.set(
+                ctx,
+                client,
+                channel,
+                BAN_MODE,
+                &mask,
+                Some(reason.as_str()),
+                &ctx.nickname(),
+                Some(policy.ban_duration),
+            )
+            .await?;
+        }
+
+        client.send(Command::KICK(
+            channel.to_string(),
+            nick.to_string(),
+            Some(reason),
+        ))?;
+
+        Ok(())
+    }
+
+    /// Lifts the expired bans in the channels where we're an operator, and forgets the activity of
+    /// users who have been quiet for a while.
+    async fn sweep(&self, ctx: &Context, client: &Client) -> Result<(), Error> {
+        self.activity
+            .lock()
+            .unwrap()
+            .retain(|_, activity| activity.is_recent(ACTIVITY_TTL));
+
+        let casemapping = ctx.casemapping();
+        let nickname = ctx.nickname();
+        let channels: Vec<String> = ctx
+            .channels()
+            .into_iter()
+            .filter(|channel| is_operator(ctx, &channel.name, &nickname))
+            .map(|channel| casemapping.fold(&channel.name))
+            .collect();
+
+        if channels.is_empty() {
+            return Ok(());
+        }
+
+        for ban in bans::take_expired(&ctx.db, &channels).await? {
+            let Some(mode) = ban.mode.chars().next() else {
+                continue;
+            };
+
+            debug!(channel = %ban.channel, mask = %ban.mask, %mode, "lifting expired ban");
+            send_mode(client, &ban.channel, '-', mode, &ban.mask)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Activity {
+    /// Creates the activity of a user who hasn't said anything yet.
+    const fn new() -> Activity {
+        Activity {
+            sent: VecDeque::new(),
+            last_message: String::new(),
+            repeats: 0,
+        }
+    }
+
+    /// Records a message sent at `now`, and returns the limit of `policy` it breaks, if any.
+    fn record(&mut self, policy: &ModerationPolicy, text: &str, now: Instant) -> Option<Violation> {
+        let normalized = text.trim().to_lowercase();
+
+        if normalized == self.last_message {
+            self.repeats += 1;
+        } else {
+            self.last_message = normalized;
+            self.repeats = 0;
+        }
+
+        self.sent.push_back(now);
+
+        if let Some(flood) = &policy.flood {
+            while self
+                .sent
+                .front()
+                .is_some_and(|sent| now.duration_since(*sent) > flood.per)
+            {
+                self.sent.pop_front();
+            }
+
+            if self.sent.len() > flood.lines {
+                return Some(Violation::Flood);
+            }
+        } else {
+            // Only the time of the last message is needed to expire the activity
+            self.sent.drain(..self.sent.len() - 1);
+        }
+
+        if policy.max_repeats.is_some_and(|max| self.repeats > max) {
+            return Some(Violation::Repeat);
+        }
+
+        if policy
+            .caps
+            .as_ref()
+            .is_some_and(|caps| is_shouting(text, caps))
+        {
+            return Some(Violation::Caps);
+        }
+
+        None
+    }
+
+    /// Returns whether the last message was sent within `ttl`.
+    fn is_recent(&self, ttl: Duration) -> bool {
+        self.sent.back().is_some_and(|sent| sent.elapsed() < ttl)
+    }
+}
+
+impl Violation {
+    /// Returns the reason given when kicking a user for the violation.
+    const fn reason(self) -> &'static str {
+        match self {
+            Violation::Flood => "Flooding",
+            Violation::Repeat => "Repeating the same message",
+            Violation::Caps => "Excessive caps",
+        }
+    }
+}
+
+/// Returns whether the sender of `message` may moderate `channel`, i.e. is an admin or an operator
+/// in it.
+fn is_privileged(ctx: &Context, message: &Message, channel: &str) -> bool {
+    permissions::is_admin(&ctx.config.permissions, message)
+        || message
+            .source_nickname()
+            .is_some_and(|nick| is_operator(ctx, channel, nick))
+}
+
+/// Returns whether `nick` is exempt from the limits of the policy of `channel`.
+fn is_exempt(ctx: &Context, message: &Message, channel: &str, nick: &str) -> bool {
+    if permissions::is_admin(&ctx.config.permissions, message) {
+        return true;
+    }
+
+    let nick = ctx.casemapping().fold(nick);
+
+    ctx.channel(channel)
+        .and_then(|channel| channel.member(&nick).map(|member| !member.modes.is_empty()))
+        .unwrap_or_default()
+}
+
+/// Returns whether `nick` is an operator in `channel`, or has a higher ranked membership mode.
+fn is_operator(ctx: &Context, channel: &str, nick: &str) -> bool {
+    let Some(channel) = ctx.channel(channel) else {
+        return false;
+    };
+    let isupport = ctx.isupport.read().unwrap();
+    let Some(op_rank) = isupport.mode_rank('o') else {
+        return false;
+    };
+
+    channel
+        .member(&isupport.casemapping.fold(nick))
+        .is_some_and(|member| {
+            member
+                .modes
+                .chars()
+                .any(|mode| isupport.mode_rank(mode).is_some_and(|rank| rank <= op_rank))
+        })
+}
+
+/// Returns whether a user with `hostmask` and `account` matches any of `patterns`.
+///
+/// Patterns starting with `$a:` match the services account of the user, and others their hostmask.
+fn matches(patterns: &[String], hostmask: &str, account: Option<&str>) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| match pattern.strip_prefix("$a:") {
+            Some(name) => account.is_some_and(|account| account.eq_ignore_ascii_case(name)),
+            None => permissions::matches_any(std::slice::from_ref(pattern), hostmask),
+        })
+}
+
+/// Returns whether `text` has at least as many uppercase letters as `limit` allows.
+fn is_shouting(text: &str, limit: &CapsLimit) -> bool {
+    let (letters, uppercase) = text
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .fold((0u32, 0u32), |(letters, uppercase), c| {
+            (letters + 1, uppercase + u32::from(c.is_uppercase()))
+        });
+
+    letters as usize >= limit.min_length && f64::from(uppercase) / f64::from(letters) >= limit.ratio
+}
+
+/// Returns the mask to ban `target` with.
+///
+/// Masks are returned as they are. Nicknames of users whose host is known are turned into a mask
+/// of their host, and other nicknames into a mask of the nickname.
+fn ban_mask(ctx: &Context, target: &str) -> String {
+    if target.contains(['!', '@', '$', ':']) {
+        return target.to_string();
+    }
+
+    match ctx.user(target).and_then(|user| user.host) {
+        Some(host) => format!("*!*@{host}"),
+        None => format!("{target}!*@*"),
+    }
+}
+
+/// Kicks the members of `channel` matching `mask`, except ourselves.
+fn kick_matching(
+    ctx: &Context,
+    client: &Client,
+    channel: &str,
+    mask: &str,
+    reason: Option<String>,
+) -> Result<(), irc::error::Error> {
+    let Some(state) = ctx.channel(channel) else {
+        return Ok(());
+    };
+    let patterns = [mask.to_string()];
+    let casemapping = ctx.casemapping();
+    let own_nick = casemapping.fold(&ctx.nickname());
+
+    for member in state.members.values() {
+        let is_match = ctx
+            .user(&member.nick)
+            .and_then(|user| user.hostmask())
+            .is_some_and(|hostmask| permissions::matches_any(&patterns, &hostmask));
+
+        if is_match && casemapping.fold(&member.nick) != own_nick {
+            client.send(Command::KICK(
+                channel.to_string(),
+                member.nick.clone(),
+                reason.clone(),
+            ))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends a `MODE` that adds or removes (`sign`) `mode` with `arg` in `channel`.
+fn send_mode(
+    client: &Client,
+    channel: &str,
+    sign: char,
+    mode: char,
+    arg: &str,
+) -> Result<(), irc::error::Error> {
+    client.send(Command::Raw(
+        "MODE".to_string(),
+        vec![
+            channel.to_string(),
+            format!("{sign}{mode}"),
+            arg.to_string(),
+        ],
+    ))
+}
+
+/// Joins the words of a reason, if one was given.
+fn reason(words: &[String]) -> Option<String> {
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+/// Parses a duration such as `30s`, `10m`, `2h`, `1d` or `1w`.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid duration {value:?}, expected e.g. 10m, 2h or 1d");
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+
+    Ok(Duration::from_secs(amount.saturating_mul(seconds)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::FloodLimit;
+
+    use super::*;
+
+    fn policy() -> ModerationPolicy {
+        ModerationPolicy {
+            name: "#zeta".to_string(),
+            auto_op: vec![],
+            auto_voice: vec![],
+            flood: Some(FloodLimit {
+                lines: 3,
+                per: Duration::from_secs(5),
+            }),
+            max_repeats: Some(1),
+            caps: Some(CapsLimit {
+                ratio: 0.8,
+                min_length: 10,
+            }),
+            action: ModerationAction::Kick,
+            ban_duration: Duration::from_secs(600),
+        }
+    }
+
+    #[test]
+    fn it_should_parse_durations() {
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("10m"), Ok(Duration::from_secs(600)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("1d"), Ok(Duration::from_secs(86400)));
+        assert!(parse_duration("10").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("10y").is_err());
+    }
+
+    #[test]
+    fn it_should_detect_flooding() {
+        let policy = policy();
+        let mut activity = Activity::new();
+        let start = Instant::now();
+
+        assert_eq!(activity.record(&policy, "a", start), None);
+        assert_eq!(activity.record(&policy, "b", start), None);
+        assert_eq!(activity.record(&policy, "c", start), None);
+        assert_eq!(
+            activity.record(&policy, "d", start + Duration::from_secs(6)),
+            None
+        );
+        assert_eq!(
+            activity.record(&policy, "e", start + Duration::from_secs(7)),
+            None
+        );
+        assert_eq!(
+            activity.record(&policy, "f", start + Duration::from_secs(7)),
+            None
+        );
+        assert_eq!(
+            activity.record(&policy, "g", start + Duration::from_secs(8)),
+            Some(Violation::Flood)
+        );
+    }
+
+    #[test]
+    fn it_should_detect_repeats() {
+        let policy = ModerationPolicy {
+            flood: None,
+            ..policy()
+        };
+        let mut activity = Activity::new();
+        let now = Instant::now();
+
+        assert_eq!(activity.record(&policy, "hello", now), None);
+        assert_eq!(activity.record(&policy, "Hello ", now), None);
+        assert_eq!(
+            activity.record(&policy, "hello", now),
+            Some(Violation::Repeat)
+        );
+        assert_eq!(activity.sent.len(), 1);
+    }
+
+    #[test]
+    fn it_should_detect_shouting() {
+        let limit = policy().caps.unwrap();
+
+        assert!(is_shouting("WHY IS NOTHING WORKING", &limit));
+        assert!(is_shouting("WHY IS NOTHING WORKING?! :)", &limit));
+        assert!(!is_shouting("LOL OK", &limit));
+        assert!(!is_shouting("Why is nothing working", &limit));
+    }
+
+    #[test]
+    fn it_should_match_hostmasks_and_accounts() {
+        let patterns = vec!["*!*@staff/*".to_string(), "$a:mk".to_string()];
+
+        assert!(matches(&patterns, "someone!user@staff/someone", None));
+        assert!(matches(&patterns, "mk!mk@maero.dk", Some("MK")));
+        assert!(!matches(&patterns, "mk!mk@maero.dk", Some("other")));
+        assert!(!matches(&patterns, "mk!mk@maero.dk", None));
+    }
+}
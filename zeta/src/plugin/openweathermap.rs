@@ -3,17 +3,25 @@
 //! OpenWeatherMap integration plugin.
 //!
 //! This plugin allows users to query current weather information via the OpenWeatherMap API
-//! using the `.w` command.
+//! using the `.w` command. Without a location, the user's saved `location` preference is used,
+//! and their `units` and `language` preferences decide how the weather is presented.
 
 use serde::Deserialize;
 use tracing::{debug, warn};
 
-use crate::{http, plugin::prelude::*};
+use crate::http::RequestBuilderExt;
+use crate::{
+    http,
+    plugin::prelude::*,
+    preferences::{self, Units},
+};
 
 /// Base URL for the OpenWeatherMap API.
 const API_BASE_URL: &str = "https://api.openweathermap.org";
 /// Constant for converting Kelvin to Celsius.
 const KELVIN: f64 = 273.15;
+/// Constant for converting meters per second to miles per hour.
+const MPS_TO_MPH: f64 = 2.236_936;
 
 /// Plugin for querying weather data.
 pub struct OpenWeatherMap {
@@ -131,29 +139,47 @@ impl Plugin<Context> for OpenWeatherMap {
 
     async fn handle_message(
         &self,
-        _ctx: &Context,
+        ctx: &Context,
         client: &Client,
         message: &Message,
     ) -> Result<(), ZetaError> {
         if let Command::PRIVMSG(ref channel, ref user_message) = message.command
             && let Some(args) = self.command.parse(user_message)
         {
-            let location = args.trim();
+            let preferences = preferences::load(ctx, message).await;
+            let location = match args.trim() {
+                "" => preferences.location.as_deref().unwrap_or_default(),
+                location => location,
+            };
+
             if location.is_empty() {
-                client.send_privmsg(channel, "\x0310> Usage: .w\x0f <location>")?;
+                client.send_privmsg(
+                    channel,
+                    Reply::new()
+                        .text("Usage: .w")
+                        .value(" <location>")
+                        .text(" (or save a default location with ")
+                        .value(".set location <location>")
+                        .text(")"),
+                )?;
                 return Ok(());
             }
 
-            match self.fetch_weather(location).await {
+            let units = preferences.units.unwrap_or_default();
+
+            match self
+                .fetch_weather(location, preferences.language.as_deref())
+                .await
+            {
                 Ok(weather) => {
-                    client.send_privmsg(channel, format_weather(&weather))?;
+                    client.send_privmsg(channel, weather_reply(&weather, units))?;
                 }
                 Err(Error::LocationNotFound) => {
-                    client.send_privmsg(channel, "\x0310> Location not found")?;
+                    client.send_privmsg(channel, Reply::new().text("Location not found"))?;
                 }
                 Err(e) => {
                     warn!(error = ?e, "openweathermap error");
-                    client.send_privmsg(channel, format!("\x0310> Error: {e}"))?;
+                    client.send_privmsg(channel, Reply::new().text(format!("Error: {e}")))?;
                 }
             }
         }
@@ -166,10 +192,15 @@ impl OpenWeatherMap {
     ///
     /// This involves two steps:
     /// 1. Geocoding the location string to coordinates (lat, lon).
-    /// 2. Fetching the weather data for those coordinates.
-    async fn fetch_weather(&self, location: &str) -> Result<WeatherResponse, Error> {
+    /// 2. Fetching the weather data for those coordinates, with descriptions in `language` if
+    ///    given.
+    async fn fetch_weather(
+        &self,
+        location: &str,
+        language: Option<&str>,
+    ) -> Result<WeatherResponse, Error> {
         let geo = self.geocode(location).await?;
-        self.current_weather(geo.lat, geo.lon).await
+        self.current_weather(geo.lat, geo.lon, language).await
     }
 
     /// Geocodes a location query to coordinates.
@@ -178,7 +209,7 @@ impl OpenWeatherMap {
         let url = format!("{API_BASE_URL}/geo/1.0/direct");
         let params = [("q", query), ("limit", "1"), ("appid", &self.app_id)];
 
-        let response = self.client.get(&url).query(&params).send().await?;
+        let response = self.client.get(&url).query(&params).send_shared().await?;
 
         if !response.status().is_success() {
             return Err(Error::Api(format!(
@@ -192,18 +223,28 @@ impl OpenWeatherMap {
     }
 
     /// Fetches current weather data for specific coordinates.
-    async fn current_weather(&self, lat: f64, lon: f64) -> Result<WeatherResponse, Error> {
-        debug!(lat, lon, "fetching current weather");
+    async fn current_weather(
+        &self,
+        lat: f64,
+        lon: f64,
+        language: Option<&str>,
+    ) -> Result<WeatherResponse, Error> {
+        debug!(lat, lon, ?language, "fetching current weather");
         let url = format!("{API_BASE_URL}/data/2.5/weather");
         let lat_s = lat.to_string();
         let lon_s = lon.to_string();
-        let params = [
+        let language = language.map(owm_language);
+        let mut params = vec![
             ("lat", lat_s.as_str()),
             ("lon", lon_s.as_str()),
             ("appid", self.app_id.as_str()),
         ];
 
-        let response = self.client.get(&url).query(&params).send().await?;
+        if let Some(ref language) = language {
+            params.push(("lang", language.as_str()));
+        }
+
+        let response = self.client.get(&url).query(&params).send_shared().await?;
 
         if !response.status().is_success() {
             return Err(Error::Api(format!(
@@ -216,10 +257,44 @@ impl OpenWeatherMap {
     }
 }
 
-/// Formats the weather response into a natural language string.
-fn format_weather(w: &WeatherResponse) -> String {
-    let temp = w.main.temp - KELVIN;
-    let feels_like = w.main.feels_like - KELVIN;
+/// Converts a language tag from the user's preferences, such as `pt-br`, to the language code
+/// OpenWeatherMap expects, such as `pt_br`.
+///
+/// OpenWeatherMap only has regional variants of a few languages, so the region is dropped from
+/// all other tags.
+fn owm_language(language: &str) -> String {
+    let language = language.to_ascii_lowercase().replace('-', "_");
+
+    match language.as_str() {
+        "pt_br" | "zh_cn" | "zh_tw" => language,
+        _ => {
+            let primary = language.split('_').next().unwrap_or_default();
+
+            // OpenWeatherMap uses its own codes for some languages.
+            match primary {
+                "cs" => "cz",
+                "ko" => "kr",
+                "lv" => "la",
+                "sq" => "al",
+                primary => primary,
+            }
+            .to_string()
+        }
+    }
+}
+
+/// Returns a reply that describes the weather response in natural language using the given units.
+fn weather_reply(w: &WeatherResponse, units: Units) -> Reply {
+    let temperature = |kelvin: f64| match units {
+        Units::Metric => format!("{:.1} °C", kelvin - KELVIN),
+        Units::Imperial => format!("{:.1} °F", (kelvin - KELVIN).mul_add(9.0 / 5.0, 32.0)),
+    };
+    let speed = |mps: f64| match units {
+        Units::Metric => format!("{mps:.1} m/s"),
+        Units::Imperial => format!("{:.1} mph", mps * MPS_TO_MPH),
+    };
+    let temp = temperature(w.main.temp);
+    let feels_like = temperature(w.main.feels_like);
 
     let location = w
         .sys
@@ -238,25 +313,41 @@ fn format_weather(w: &WeatherResponse) -> String {
     };
 
     let wind_info = match w.wind.gust {
-        Some(g) if g > 0.0 => format!("{:.1} m/s (gusts: {:.1} m/s)", w.wind.speed, g),
-        _ => format!("{:.1} m/s", w.wind.speed),
+        Some(g) if g > 0.0 => format!("{} (gusts: {})", speed(w.wind.speed), speed(g)),
+        _ => speed(w.wind.speed),
     };
 
-    let mut extra_info = Vec::new();
-    extra_info.push(format!("Wind: \x0f{wind_info}\x0310"));
-    extra_info.push(format!("Humidity: \x0f{}%\x0310", w.main.humidity));
-    extra_info.push(format!("Pressure: \x0f{} hPa\x0310", w.main.pressure));
-
-    if let Some(clouds) = &w.clouds {
-        extra_info.push(format!("Cloud coverage: \x0f{}%\x0310", clouds.all));
+    let reply = Reply::new()
+        .text("Right now in ")
+        .value(location)
+        .text(" it's ")
+        .value(temp)
+        .text(" (feels like ")
+        .value(feels_like)
+        .text(") with ")
+        .value(conditions)
+        .text(".")
+        .field("Wind", wind_info)
+        .field("Humidity", format!("{}%", w.main.humidity))
+        .field("Pressure", format!("{} hPa", w.main.pressure));
+
+    match &w.clouds {
+        Some(clouds) => reply.field("Cloud coverage", format!("{}%", clouds.all)),
+        None => reply,
     }
+}
 
-    format!(
-        "\x0310> Right now in \x0f{}\x0310 it's \x0f{:.1} °C\x0310 (feels like \x0f{:.1} °C\x0310) with \x0f{}\x0310. {}",
-        location,
-        temp,
-        feels_like,
-        conditions,
-        extra_info.join(". ")
-    )
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_convert_language_tags() {
+        assert_eq!(owm_language("da"), "da");
+        assert_eq!(owm_language("da-dk"), "da");
+        assert_eq!(owm_language("pt-br"), "pt_br");
+        assert_eq!(owm_language("pt-pt"), "pt");
+        assert_eq!(owm_language("zh-CN"), "zh_cn");
+        assert_eq!(owm_language("cs"), "cz");
+    }
 }
@@ -10,10 +10,8 @@ use serde::{Deserialize, de::DeserializeOwned};
 use tracing::{debug, error};
 use url::Url;
 
-use crate::{
-    http,
-    plugin::{self, prelude::*},
-};
+use crate::http::RequestBuilderExt;
+use crate::{http, plugin::prelude::*};
 
 /// The hostname for PornHub URLs.
 const PORNHUB_HOST: &str = "www.pornhub.com";
@@ -147,40 +145,29 @@ impl Plugin<Context> for PornHub {
         }
     }
 
-    // Handles incoming messages and processes any PornHub URLs found.
-    async fn handle_message(
+    fn claims_url(&self, url: &Url) -> bool {
+        is_pornhub_video_url(url)
+    }
+
+    // Processes a claimed PornHub URL.
+    async fn handle_url(
         &self,
         _ctx: &Context,
         client: &Client,
         message: &Message,
+        url: &Url,
     ) -> Result<(), ZetaError> {
-        if let Command::PRIVMSG(ref channel, ref user_message) = message.command
-            && let Some(urls) = plugin::extract_urls(user_message)
-        {
-            let _ = self.process_urls(urls, channel, client).await;
-        }
-
-        Ok(())
-    }
-}
-
-impl PornHub {
-    /// Processes multiple URLs, handling each one that matches the PornHub pattern.
-    async fn process_urls(
-        &self,
-        urls: Vec<Url>,
-        channel: &str,
-        client: &Client,
-    ) -> Result<(), Error> {
-        for url in &urls {
+        if let Command::PRIVMSG(ref channel, _) = message.command {
             debug!(%url, "processing url");
-            self.process_url(url, channel, client).await?;
+            let _ = self.process_url(url, channel, client).await;
             debug!(%url, "finished processing url");
         }
 
         Ok(())
     }
+}
 
+impl PornHub {
     // Processes a single URL if it's a valid PornHub video URL.
     async fn process_url(&self, url: &Url, channel: &str, client: &Client) -> Result<(), Error> {
         if is_pornhub_video_url(url)
@@ -190,7 +177,7 @@ impl PornHub {
             let video = self.video_by_id(&video_id).await?;
             debug!(?video, "fetched video");
 
-            let _ = client.send_privmsg(channel, Self::format_video_mesage(&video));
+            let _ = client.send_privmsg(channel, Self::video_reply(&video));
         }
 
         Ok(())
@@ -205,7 +192,12 @@ impl PornHub {
         )
         .unwrap();
 
-        let response = self.client.get(url).send().await.map_err(Error::Request)?;
+        let response = self
+            .client
+            .get(url)
+            .send_shared()
+            .await
+            .map_err(Error::Request)?;
         debug!("request went ok, parsing response");
         let json: ApiResponse = deserialize_response(response).await?;
 
@@ -221,12 +213,13 @@ impl PornHub {
         }
     }
 
-    /// Formats a message about the video.
-    fn format_video_mesage(video: &Video) -> String {
-        let title = &video.title;
-        let views = video.views.to_formatted_string(&Locale::en);
-
-        format!("\x0310> “\x0f{title}\x0310” is a PornHub video with\x0f {views}\x0310 views")
+    /// Returns a reply about the video.
+    fn video_reply(video: &Video) -> Reply {
+        Reply::new()
+            .quoted(&video.title)
+            .text(" is a PornHub video with ")
+            .value(video.views.to_formatted_string(&Locale::en))
+            .text(" views")
     }
 }
 
@@ -0,0 +1,170 @@
+//! User preference commands.
+//!
+//! `.set` lists the preferences of the user, `.set <key> <value>` saves a preference and
+//! `.unset <key>` removes it. See [`crate::preferences`] for the available keys.
+
+use tracing::error;
+
+use crate::database::preferences;
+use crate::plugin::prelude::*;
+use crate::preferences::{Key, Owner};
+
+/// User preferences plugin.
+pub struct Preferences {
+    /// The `.set` command trigger.
+    set_command: Prefix,
+    /// The `.unset` command trigger.
+    unset_command: Prefix,
+}
+
+/// Errors that can occur when handling preference commands.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("irc error: {0}")]
+    Irc(#[from] irc::error::Error),
+    #[error(transparent)]
+    Preference(#[from] crate::preferences::Error),
+}
+
+#[async_trait]
+impl Plugin<Context> for Preferences {
+    fn new(_ctx: &Context) -> Result<Preferences, ZetaError> {
+        let set_command = Prefix::new(".set");
+        let unset_command = Prefix::new(".unset");
+
+        Ok(Preferences {
+            set_command,
+            unset_command,
+        })
+    }
+
+    fn metadata() -> Metadata {
+        Metadata {
+            name: "preferences".into(),
+            authors: vec!["Mikkel Kroman <mk@maero.dk>".into()],
+        }
+    }
+
+    fn accepts_private_messages(&self) -> bool {
+        true
+    }
+
+    async fn handle_message(
+        &self,
+        ctx: &Context,
+        client: &Client,
+        message: &Message,
+    ) -> Result<(), ZetaError> {
+        if let Command::PRIVMSG(_, ref user_message) = message.command
+            && let Some(target) = message.reply_target()
+            && let Some(owner) = Owner::from_message(message, ctx.casemapping())
+        {
+            let result = if let Some(args) = self.set_command.parse(user_message) {
+                self.set(ctx, client, target, &owner, args.trim()).await
+            } else if let Some(args) = self.unset_command.parse(user_message) {
+                self.unset(ctx, client, target, &owner, args.trim()).await
+            } else {
+                Ok(())
+            };
+
+            match result {
+                Ok(()) => {}
+                // Invalid preferences are the user's mistake, so they're told what's wrong
+                Err(Error::Preference(err)) => {
+                    client.send_privmsg(target, Reply::new().text(format!("Error: {err}")))?;
+                }
+                Err(err) => {
+                    error!(error = %err, "could not handle preference command");
+                    client.send_privmsg(
+                        target,
+                        Reply::new().text("Something went wrong, try again later"),
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Preferences {
+    /// Handles the `.set` command.
+    async fn set(
+        &self,
+        ctx: &Context,
+        client: &Client,
+        target: &str,
+        owner: &Owner,
+        args: &str,
+    ) -> Result<(), Error> {
+        if args.is_empty() {
+            let pairs = preferences::all(&ctx.db, owner.kind(), owner.name()).await?;
+
+            if pairs.is_empty() {
+                client.send_privmsg(
+                    target,
+                    Reply::new()
+                        .text("You have no preferences. Usage: .set")
+                        .value(" <key> <value>"),
+                )?;
+            } else {
+                let reply = pairs.iter().fold(
+                    Reply::new().text("Your preferences:"),
+                    |reply, (key, value)| reply.field(key, value),
+                );
+
+                client.send_privmsg(target, reply)?;
+            }
+
+            return Ok(());
+        }
+
+        let Some((key, value)) = args.split_once(char::is_whitespace) else {
+            client.send_privmsg(
+                target,
+                Reply::new().text("Usage: .set").value(" <key> <value>"),
+            )?;
+
+            return Ok(());
+        };
+
+        let key: Key = key.parse()?;
+        let value = key.normalize(value)?;
+
+        preferences::set(&ctx.db, owner.kind(), owner.name(), key.as_str(), &value).await?;
+        client.send_privmsg(
+            target,
+            Reply::new().text(format!("Set {key} to ")).value(value),
+        )?;
+
+        Ok(())
+    }
+
+    /// Handles the `.unset` command.
+    async fn unset(
+        &self,
+        ctx: &Context,
+        client: &Client,
+        target: &str,
+        owner: &Owner,
+        args: &str,
+    ) -> Result<(), Error> {
+        if args.is_empty() {
+            client.send_privmsg(target, Reply::new().text("Usage: .unset").value(" <key>"))?;
+
+            return Ok(());
+        }
+
+        let key: Key = args.parse()?;
+
+        if preferences::unset(&ctx.db, owner.kind(), owner.name(), key.as_str()).await? {
+            client.send_privmsg(target, Reply::new().text(format!("Unset {key}")))?;
+        } else {
+            client.send_privmsg(target, Reply::new().text(format!("{key} was not set")))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,250 @@
+//! Per-channel quote database.
+//!
+//! Quotes are added with `.quote add <text>` and can be recalled by id, by full-text search or at
+//! random. Deleting quotes is restricted to admins.
+
+use time::{format_description::FormatItem, macros::format_description};
+use tracing::error;
+
+use crate::database::quotes::{self, Quote};
+use crate::permissions;
+use crate::plugin::prelude::*;
+
+/// The maximum number of search results to fetch.
+const MAX_SEARCH_RESULTS: i64 = 10;
+
+/// The format used when displaying the date a quote was added.
+const DATE_FORMAT: &[FormatItem<'_>] = format_description!("[year]-[month]-[day]");
+
+/// Quote database plugin.
+pub struct Quotes {
+    /// The `.quote` command trigger.
+    command: Prefix,
+}
+
+/// Errors that can occur when handling quote commands.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("irc error: {0}")]
+    Irc(#[from] irc::error::Error),
+}
+
+/// A parsed `.quote` invocation.
+#[derive(Debug, Eq, PartialEq)]
+enum Action<'a> {
+    /// Add a new quote.
+    Add(&'a str),
+    /// Delete the quote with the given id.
+    Delete(i64),
+    /// Show the quote with the given id.
+    Get(i64),
+    /// Show a random quote.
+    Random,
+    /// Search for quotes matching the query.
+    Search(&'a str),
+    /// Show usage information.
+    Usage,
+}
+
+#[async_trait]
+impl Plugin<Context> for Quotes {
+    fn new(_ctx: &Context) -> Result<Quotes, ZetaError> {
+        let command = Prefix::new(".quote");
+
+        Ok(Quotes { command })
+    }
+
+    fn metadata() -> Metadata {
+        Metadata {
+            name: "quotes".into(),
+            authors: vec!["Mikkel Kroman <mk@maero.dk>".into()],
+        }
+    }
+
+    async fn handle_message(
+        &self,
+        ctx: &Context,
+        client: &Client,
+        message: &Message,
+    ) -> Result<(), ZetaError> {
+        if let Command::PRIVMSG(ref channel, ref user_message) = message.command
+            && let Some(args) = self.command.parse(user_message)
+            && let Err(err) = self
+                .handle_action(ctx, client, message, channel, parse_action(args))
+                .await
+        {
+            error!(error = %err, "could not handle quote command");
+            client.send_privmsg(
+                channel,
+                Reply::new().text("Something went wrong, try again later"),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Quotes {
+    async fn handle_action(
+        &self,
+        ctx: &Context,
+        client: &Client,
+        message: &Message,
+        channel: &str,
+        action: Action<'_>,
+    ) -> Result<(), Error> {
+        let key = ctx.casemapping().fold(channel);
+
+        match action {
+            Action::Add(text) => {
+                let author = message.source_nickname().unwrap_or("unknown");
+                let quote = quotes::insert(&ctx.db, &key, author, text).await?;
+
+                client.send_privmsg(
+                    channel,
+                    Reply::new().text(format!("Added quote #{}", quote.id)),
+                )?;
+            }
+            Action::Delete(_) if !permissions::is_admin(&ctx.config.permissions, message) => {
+                client.send_privmsg(
+                    channel,
+                    Reply::new().text("You are not allowed to delete quotes"),
+                )?;
+            }
+            Action::Delete(id) => {
+                let reply = if quotes::delete(&ctx.db, &key, id).await? {
+                    format!("Deleted quote #{id}")
+                } else {
+                    format!("No such quote #{id}")
+                };
+
+                client.send_privmsg(channel, Reply::new().text(reply))?;
+            }
+            Action::Get(id) => {
+                let reply = quotes::get(&ctx.db, &key, id).await?.map_or_else(
+                    || Reply::new().text(format!("No such quote #{id}")),
+                    |quote| quote_reply(&quote),
+                );
+
+                client.send_privmsg(channel, reply)?;
+            }
+            Action::Random => {
+                let reply = quotes::random(&ctx.db, &key).await?.map_or_else(
+                    || Reply::new().text("There are no quotes yet"),
+                    |quote| quote_reply(&quote),
+                );
+
+                client.send_privmsg(channel, reply)?;
+            }
+            Action::Search(query) => {
+                let results = quotes::search(&ctx.db, &key, query, MAX_SEARCH_RESULTS).await?;
+
+                match results.split_first() {
+                    Some((quote, [])) => client.send_privmsg(channel, quote_reply(quote))?,
+                    Some((quote, rest)) => {
+                        let others: Vec<String> =
+                            rest.iter().map(|q| format!("#{}", q.id)).collect();
+
+                        client.send_privmsg(channel, quote_reply(quote))?;
+                        client.send_privmsg(
+                            channel,
+                            Reply::new()
+                                .text("Also matching: ")
+                                .value(others.join(", ")),
+                        )?;
+                    }
+                    None => {
+                        client.send_privmsg(channel, Reply::new().text("No matching quotes"))?;
+                    }
+                }
+            }
+            Action::Usage => {
+                client.send_privmsg(
+                    channel,
+                    Reply::new()
+                        .text("Usage: .quote")
+                        .value(" [add <text> | del <id> | random | <id> | <search>]"),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the arguments given to the `.quote` command.
+fn parse_action(args: &str) -> Action<'_> {
+    let args = args.trim();
+    let (subcommand, rest) = args
+        .split_once(char::is_whitespace)
+        .map_or((args, ""), |(subcommand, rest)| (subcommand, rest.trim()));
+
+    match subcommand {
+        "" | "random" if rest.is_empty() => Action::Random,
+        "add" if !rest.is_empty() => Action::Add(rest),
+        "add" => Action::Usage,
+        "del" | "delete" => parse_id(rest).map_or(Action::Usage, Action::Delete),
+        _ => parse_id(args).map_or(Action::Search(args), Action::Get),
+    }
+}
+
+/// Parses a quote id, with or without a leading `#`.
+fn parse_id(s: &str) -> Option<i64> {
+    s.strip_prefix('#').unwrap_or(s).parse().ok()
+}
+
+/// Returns a reply that presents the quote.
+fn quote_reply(quote: &Quote) -> Reply {
+    let date = quote
+        .created_at
+        .format(DATE_FORMAT)
+        .unwrap_or_else(|_| "?".to_string());
+
+    Reply::tagged(format!("Quote #{}", quote.id))
+        .value(&quote.content)
+        .text(" (added by ")
+        .value(&quote.author)
+        .text(" on ")
+        .value(date)
+        .text(")")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_parse_random() {
+        assert_eq!(parse_action(""), Action::Random);
+        assert_eq!(parse_action("random"), Action::Random);
+    }
+
+    #[test]
+    fn it_should_parse_add() {
+        assert_eq!(
+            parse_action("add <mk> hello  world"),
+            Action::Add("<mk> hello  world")
+        );
+        assert_eq!(parse_action("add"), Action::Usage);
+    }
+
+    #[test]
+    fn it_should_parse_delete() {
+        assert_eq!(parse_action("del 12"), Action::Delete(12));
+        assert_eq!(parse_action("delete #12"), Action::Delete(12));
+        assert_eq!(parse_action("del twelve"), Action::Usage);
+    }
+
+    #[test]
+    fn it_should_parse_ids_and_searches() {
+        assert_eq!(parse_action("42"), Action::Get(42));
+        assert_eq!(parse_action("#42"), Action::Get(42));
+        assert_eq!(parse_action("hello world"), Action::Search("hello world"));
+        assert_eq!(
+            parse_action("random things"),
+            Action::Search("random things")
+        );
+    }
+}
@@ -1,10 +1,12 @@
 use reddit::Link;
+use reqwest::Method;
 use secrecy::SecretString;
-use tracing::error;
+use tracing::{debug, error};
 use url::Url;
 
 use crate::{
-    plugin::{self, prelude::*},
+    http::{self, FetchError, GuardedClient},
+    plugin::prelude::*,
     utils::Truncatable,
 };
 
@@ -18,23 +20,32 @@ pub enum Error {
     Request(#[from] reqwest::Error),
     #[error("irc error: {0}")]
     Irc(#[from] irc::error::Error),
+    #[error(transparent)]
+    Fetch(#[from] FetchError),
+    #[error("shortened link does not redirect to a submission or comment")]
+    InvalidShortenedLink,
 }
 
 /// Reddit integration plugin.
 pub struct Reddit {
     /// Reddit API client
     client: reddit::Client,
+    /// HTTP client for resolving shortened links
+    guarded: GuardedClient,
 }
 
 #[async_trait]
 impl Plugin<Context> for Reddit {
-    fn new(_ctx: &Context) -> Result<Self, ZetaError> {
+    fn new(ctx: &Context) -> Result<Self, ZetaError> {
         let client_id = require_env("REDDIT_CLIENT_ID")?;
         let client_secret: SecretString = require_env("REDDIT_CLIENT_SECRET")?.into();
         let user_agent = Some(USER_AGENT.to_string());
-        let client = reddit::Client::new(client_id, client_secret, user_agent);
+        let client =
+            reddit::Client::new(client_id, client_secret, user_agent).with_send(http::send_boxed);
 
-        Ok(Reddit { client })
+        let guarded = GuardedClient::new(ctx);
+
+        Ok(Reddit { client, guarded })
     }
 
     fn metadata() -> Metadata {
@@ -44,19 +55,26 @@ impl Plugin<Context> for Reddit {
         }
     }
 
-    async fn handle_message(
+    fn claims_url(&self, url: &Url) -> bool {
+        // Image links are left for other plugins
+        reddit::classify_reddit_url(url)
+            .is_some_and(|link| !matches!(link, Link::Image(_) | Link::Preview(_)))
+    }
+
+    async fn handle_url(
         &self,
         _ctx: &Context,
         client: &Client,
         message: &Message,
+        url: &Url,
     ) -> Result<(), ZetaError> {
-        if let Command::PRIVMSG(ref channel, ref user_message) = message.command
-            && let Some(urls) = plugin::extract_urls(user_message)
+        if let Command::PRIVMSG(ref channel, _) = message.command
+            && let Some(link) = reddit::classify_reddit_url(url)
         {
             let _ = self
-                .process_urls(&urls, channel, client)
+                .process_url(link, channel, client)
                 .await
-                .inspect_err(|e| error!("error when processing urls: {e}"));
+                .inspect_err(|e| error!("error when processing url: {e}"));
         }
 
         Ok(())
@@ -64,21 +82,6 @@ impl Plugin<Context> for Reddit {
 }
 
 impl Reddit {
-    pub async fn process_urls(
-        &self,
-        urls: &Vec<Url>,
-        channel: &str,
-        client: &Client,
-    ) -> Result<(), Error> {
-        for url in urls {
-            if let Some(link) = reddit::classify_reddit_url(url) {
-                self.process_url(link, channel, client).await?;
-            }
-        }
-
-        Ok(())
-    }
-
     async fn process_url(&self, link: Link, channel: &str, client: &Client) -> Result<(), Error> {
         match link {
             Link::Gallery(id) | Link::Comments { id } | Link::Submission { id, .. } => {
@@ -87,12 +90,15 @@ impl Reddit {
                         let title = submission.title;
                         let subreddit = submission.subreddit;
 
-                        client.send_privmsg(channel, format!("\x0310> {title} : {subreddit}"))?;
+                        client.send_privmsg(
+                            channel,
+                            Reply::new().text(format!("{title} : {subreddit}")),
+                        )?;
                     }
                     Err(err) => {
                         client.send_privmsg(
                             channel,
-                            format!("\x0310> could not fetch submission details: {err}"),
+                            Reply::new().text(format!("could not fetch submission details: {err}")),
                         )?;
                     }
                 }
@@ -102,11 +108,14 @@ impl Reddit {
                     let title = submission.title;
                     let subreddit = submission.subreddit;
 
-                    client.send_privmsg(channel, format!("\x0310> {title} : {subreddit}"))?;
+                    client.send_privmsg(
+                        channel,
+                        Reply::new().text(format!("{title} : {subreddit}")),
+                    )?;
                 }
                 Err(err) => client.send_privmsg(
                     channel,
-                    format!("\x0310> could not fetch submission details: {err}"),
+                    Reply::new().text(format!("could not fetch submission details: {err}")),
                 )?,
             },
             Link::Video(id) => match self.client.video(&id).await {
@@ -114,17 +123,20 @@ impl Reddit {
                     let title = submission.title;
                     let subreddit = submission.subreddit;
 
-                    client.send_privmsg(channel, format!("\x0310> {title} : {subreddit}"))?;
+                    client.send_privmsg(
+                        channel,
+                        Reply::new().text(format!("{title} : {subreddit}")),
+                    )?;
                 }
                 Err(err) => {
                     client.send_privmsg(
                         channel,
-                        format!("\x0310> could not resolve video link: {err}"),
+                        Reply::new().text(format!("could not resolve video link: {err}")),
                     )?;
                 }
             },
             Link::Shortened { id, subreddit } => {
-                match self.client.resolve_shortened_link(&subreddit, &id).await {
+                match self.resolve_shortened_link(&subreddit, &id).await {
                     Ok(link) => {
                         if let Err(e) = Box::pin(self.process_url(link, channel, client)).await {
                             error!("failed to process resolved link: {e}");
@@ -133,7 +145,7 @@ impl Reddit {
                     Err(err) => {
                         client.send_privmsg(
                             channel,
-                            format!("\x0310> could not resolve shortened link: {err}"),
+                            Reply::new().text(format!("could not resolve shortened link: {err}")),
                         )?;
                     }
                 }
@@ -145,15 +157,12 @@ impl Reddit {
                         let description =
                             subreddit.public_description.truncate_with_suffix(250, "…");
 
-                        client.send_privmsg(
-                            channel,
-                            format!("\x0310>\x03\x02 {title}:\x02\x0310 {description}"),
-                        )?;
+                        client.send_privmsg(channel, Reply::tagged(title).text(description))?;
                     }
                     Err(err) => {
                         client.send_privmsg(
                             channel,
-                            format!("\x0310> could not fetch subreddit details: {err}"),
+                            Reply::new().text(format!("could not fetch subreddit details: {err}")),
                         )?;
                     }
                 }
@@ -163,4 +172,17 @@ impl Reddit {
 
         Ok(())
     }
+
+    /// Resolves a shortened link to the submission or comment it redirects to.
+    async fn resolve_shortened_link(&self, subreddit: &str, id: &str) -> Result<Link, Error> {
+        debug!(%subreddit, %id, "resolving shortened link");
+        let url = Url::parse(&format!("{}/r/{subreddit}/s/{id}", reddit::BASE_URL))
+            .map_err(|_| Error::InvalidShortenedLink)?;
+        let location = self.guarded.redirect_location(Method::HEAD, &url).await?;
+
+        match reddit::classify_reddit_url(&location) {
+            Some(link @ (Link::Comment { .. } | Link::Submission { .. })) => Ok(link),
+            _ => Err(Error::InvalidShortenedLink),
+        }
+    }
 }
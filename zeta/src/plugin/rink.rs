@@ -1,10 +1,36 @@
 //! Helpful calculator features based on rink.
+//!
+//! Quantities in the units of the other system than the one a user has saved with `.set units`
+//! are converted to their own, unless the query says what to convert them to.
 
 use std::sync::Mutex;
 
 use rink_core::Context as RinkContext;
 
 use crate::plugin::prelude::*;
+use crate::preferences::{self, Units};
+
+/// The names of a metric unit and the imperial unit it's converted to and from, with the name
+/// rink is asked to convert to first.
+const CONVERSIONS: &[(&[&str], &[&str])] = &[
+    (&["km", "kilometer", "kilometers"], &["mile", "mi", "miles"]),
+    (
+        &["m", "meter", "meters", "metre", "metres"],
+        &["foot", "ft", "feet"],
+    ),
+    (&["cm", "centimeter", "centimeters"], &["inch", "inches"]),
+    (
+        &["kg", "kilogram", "kilograms"],
+        &["pound", "lb", "lbs", "pounds"],
+    ),
+    (&["g", "gram", "grams"], &["ounce", "oz", "ounces"]),
+    (
+        &["liter", "l", "liters", "litre", "litres"],
+        &["gallon", "gal", "gallons"],
+    ),
+    (&["km/hour", "kph", "kmh"], &["mph"]),
+    (&["degC", "°C", "celsius"], &["degF", "°F", "fahrenheit"]),
+];
 
 /// Calculator plugin using rink-rs.
 pub struct Rink {
@@ -17,7 +43,8 @@ pub struct Rink {
 #[async_trait]
 impl Plugin<Context> for Rink {
     fn new(_ctx: &Context) -> Result<Rink, ZetaError> {
-        let ctx = rink_core::simple_context().map_err(|e| ZetaError::Plugin(Box::new(std::io::Error::other(e))))?;
+        let ctx = rink_core::simple_context()
+            .map_err(|e| ZetaError::Plugin(Box::new(std::io::Error::other(e))))?;
         let command = Prefix::new(".r");
 
         Ok(Rink {
@@ -33,21 +60,30 @@ impl Plugin<Context> for Rink {
         }
     }
 
+    fn accepts_private_messages(&self) -> bool {
+        true
+    }
+
     async fn handle_message(
         &self,
-        _ctx: &Context,
+        ctx: &Context,
         client: &Client,
         message: &Message,
     ) -> Result<(), ZetaError> {
-        if let Command::PRIVMSG(ref channel, ref user_message) = message.command
+        if let Command::PRIVMSG(_, ref user_message) = message.command
+            && let Some(target) = message.reply_target()
             && let Some(query) = self.command.parse(user_message)
         {
-            let message = match self.eval(query) {
-                Ok(result) => format!("\x0310> {result}"),
-                Err(err) => format!("\x0310> Error: {err}"),
+            let units = preferences::load(ctx, message).await.units;
+            let converted = units
+                .and_then(|units| conversion_target(query, units))
+                .and_then(|target| self.eval(&format!("{query} -> {target}")).ok());
+            let reply = match converted.map_or_else(|| self.eval(query), Ok) {
+                Ok(result) => Reply::new().text(result),
+                Err(err) => Reply::new().text(format!("Error: {err}")),
             };
 
-            client.send_privmsg(channel, message)?;
+            client.send_privmsg(target, reply)?;
         }
 
         Ok(())
@@ -61,3 +97,57 @@ impl Rink {
         rink_core::one_line(&mut ctx, line)
     }
 }
+
+/// Returns the unit that `query` should be converted to for a user of `units`, if it mentions a
+/// unit of the other system and doesn't say what to convert to.
+fn conversion_target(query: &str, units: Units) -> Option<&'static str> {
+    let lowercase = query.to_lowercase();
+
+    if lowercase.contains("->") || lowercase.contains(" to ") || lowercase.contains(" in ") {
+        return None;
+    }
+
+    query
+        .split(|c: char| !c.is_alphabetic() && c != '°')
+        .find_map(|word| {
+            CONVERSIONS.iter().find_map(|&(metric, imperial)| {
+                let (from, to) = match units {
+                    Units::Metric => (imperial, metric),
+                    Units::Imperial => (metric, imperial),
+                };
+
+                from.iter()
+                    .any(|name| name.eq_ignore_ascii_case(word))
+                    .then_some(to[0])
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_convert_to_the_preferred_units() {
+        assert_eq!(conversion_target("5 miles", Units::Metric), Some("km"));
+        assert_eq!(conversion_target("72°F", Units::Metric), Some("degC"));
+        assert_eq!(
+            conversion_target("3 Kg + 2 kg", Units::Imperial),
+            Some("pound")
+        );
+        assert_eq!(conversion_target("100 kph", Units::Imperial), Some("mph"));
+    }
+
+    #[test]
+    fn it_should_not_convert_units_of_the_same_system() {
+        assert_eq!(conversion_target("5 km", Units::Metric), None);
+        assert_eq!(conversion_target("5 miles", Units::Imperial), None);
+        assert_eq!(conversion_target("2 + 2", Units::Metric), None);
+    }
+
+    #[test]
+    fn it_should_not_override_explicit_conversions() {
+        assert_eq!(conversion_target("5 miles -> m", Units::Metric), None);
+        assert_eq!(conversion_target("5 miles to ft", Units::Metric), None);
+    }
+}
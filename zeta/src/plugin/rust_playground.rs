@@ -6,6 +6,7 @@ use regex::Regex;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, warn};
 
+use crate::http::RequestBuilderExt;
 use crate::{http, plugin::prelude::*, utils::Truncatable};
 
 const BASE_URL: &str = "https://play.rust-lang.org/execute";
@@ -68,28 +69,41 @@ impl Plugin<Context> for RustPlayground {
         }
     }
 
+    fn accepts_private_messages(&self) -> bool {
+        true
+    }
+
     async fn handle_message(
         &self,
         _ctx: &Context,
         client: &Client,
         message: &Message,
     ) -> Result<(), ZetaError> {
-        if let Command::PRIVMSG(ref channel, ref user_message) = message.command
+        if let Command::PRIVMSG(_, ref user_message) = message.command
+            && let Some(target) = message.reply_target()
             && let Some(expr) = self.command.parse(user_message)
         {
             // Early return if input is empty
             if expr.trim().is_empty() {
-                client.send_privmsg(channel, formatted("Usage: .rs\x0f <expr>"))?;
+                client.send_privmsg(
+                    target,
+                    Reply::tagged("Rust Playground")
+                        .text("Usage: .rs")
+                        .value(" <expr>"),
+                )?;
                 return Ok(());
             }
 
             match self.evaluate(expr).await {
                 Ok(output) => {
-                    client.send_privmsg(channel, formatted(&output))?;
+                    client.send_privmsg(target, Reply::tagged("Rust Playground").text(output))?;
                 }
                 Err(e) => {
                     warn!("rust playground error: {}", e);
-                    client.send_privmsg(channel, formatted(&format!("http error: {e}")))?;
+                    client.send_privmsg(
+                        target,
+                        Reply::tagged("Rust Playground").text(format!("http error: {e}")),
+                    )?;
                 }
             }
         }
@@ -116,7 +130,12 @@ impl RustPlayground {
 
         debug!("sending code to rust playground");
 
-        let response = self.client.post(BASE_URL).json(&request).send().await?;
+        let response = self
+            .client
+            .post(BASE_URL)
+            .json(&request)
+            .send_shared()
+            .await?;
 
         let result: ExecuteResponse = response.error_for_status()?.json().await?;
 
@@ -144,11 +163,6 @@ impl RustPlayground {
     }
 }
 
-/// Applies IRC formatting to the message.
-fn formatted(msg: &str) -> String {
-    format!("\x0310>\x0F\x02 Rust Playground:\x02\x0310 {msg}")
-}
-
 /// Sanitizes output by removing control characters (0x00-0x19, 0x7F).
 /// This includes newlines, which is desirable for IRC.
 fn sanitize_output(s: &str) -> String {
@@ -1,5 +1,3 @@
-
-use std::fmt::Write;
 use std::time::{Duration, Instant};
 
 use base64::prelude::*;
@@ -11,10 +9,8 @@ use tokio::sync::RwLock;
 use tracing::{debug, warn};
 use url::Url;
 
-use crate::{
-    http,
-    plugin::{self, prelude::*},
-};
+use crate::http::RequestBuilderExt;
+use crate::{http, plugin::prelude::*};
 
 const AUTH_URL: &str = "https://accounts.spotify.com/api/token";
 const API_BASE_URL: &str = "https://api.spotify.com/v1";
@@ -144,7 +140,7 @@ impl Plugin<Context> for Spotify {
         message: &Message,
     ) -> Result<(), ZetaError> {
         if let Command::PRIVMSG(ref channel, ref user_message) = message.command {
-            // 1. Handle Spotify URIs (spotify:type:id)
+            // Handle Spotify URIs (spotify:type:id)
             for cap in self.uri_regex.captures_iter(user_message) {
                 let type_str = &cap["type"];
                 let id_str = &cap["id"];
@@ -152,20 +148,29 @@ impl Plugin<Context> for Spotify {
                 self.handle_spotify_resource(channel, type_str, id_str, true, client)
                     .await?;
             }
+        }
 
-            // 2. Handle Spotify URLs (open.spotify.com/type/id)
-            if let Some(urls) = plugin::extract_urls(user_message) {
-                for url in urls {
-                    if let Some(host) = url.host_str()
-                        && (host == "open.spotify.com" || host == "play.spotify.com")
-                        && let Some((type_str, id_str)) = parse_spotify_url(&url)
-                    {
-                        // Do not include external URL for link matches (avoid redundancy)
-                        self.handle_spotify_resource(channel, type_str, id_str, false, client)
-                            .await?;
-                    }
-                }
-            }
+        Ok(())
+    }
+
+    fn claims_url(&self, url: &Url) -> bool {
+        parse_spotify_url(url).is_some()
+    }
+
+    /// Handles Spotify URLs (open.spotify.com/type/id)
+    async fn handle_url(
+        &self,
+        _ctx: &Context,
+        client: &Client,
+        message: &Message,
+        url: &Url,
+    ) -> Result<(), ZetaError> {
+        if let Command::PRIVMSG(ref channel, _) = message.command
+            && let Some((type_str, id_str)) = parse_spotify_url(url)
+        {
+            // Do not include external URL for link matches (avoid redundancy)
+            self.handle_spotify_resource(channel, type_str, id_str, false, client)
+                .await?;
         }
 
         Ok(())
@@ -191,7 +196,7 @@ impl Spotify {
             .header(AUTHORIZATION, format!("Basic {encoded}"))
             .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
             .form(&[("grant_type", "client_credentials")])
-            .send()
+            .send_shared()
             .await?;
 
         let auth: AuthResponse = response.json().await?;
@@ -245,7 +250,7 @@ impl Spotify {
             .client
             .get(&url)
             .header(AUTHORIZATION, format!("Bearer {token}"))
-            .send()
+            .send_shared()
             .await?;
 
         if !response.status().is_success() {
@@ -264,18 +269,18 @@ impl Spotify {
     ) -> Result<(), ZetaError> {
         match self.fetch::<Track>(&format!("tracks/{id}")).await {
             Ok(track) => {
-                let name = track.name;
-                let artists = join_artists(&track.artists);
-                let album = track.album.name;
-
-                let mut msg = format!("\x0f{name}\x0310 is a track by {artists}\x0310");
-                let _ = write!(msg, " from the album \x0f{album}\x0310");
+                let reply = Reply::tagged("Spotify")
+                    .value(track.name)
+                    .text(" is a track by ");
+                let mut reply = with_artists(reply, &track.artists)
+                    .text(" from the album ")
+                    .value(track.album.name);
 
                 if include_url {
-                    let _ = write!(msg, " - {}", track.external_urls.spotify);
+                    reply = reply.text(" - ").link(track.external_urls.spotify);
                 }
 
-                client.send_privmsg(channel, formatted(&msg))?;
+                client.send_privmsg(channel, reply)?;
             }
             Err(e) => handle_error(channel, client, &e)?,
         }
@@ -291,16 +296,16 @@ impl Spotify {
     ) -> Result<(), ZetaError> {
         match self.fetch::<Album>(&format!("albums/{id}")).await {
             Ok(album) => {
-                let name = album.name;
-                let artists = join_artists(&album.artists);
-
-                let mut msg = format!("\x0f{name}\x0310 is an album by {artists}");
+                let reply = Reply::tagged("Spotify")
+                    .value(album.name)
+                    .text(" is an album by ");
+                let mut reply = with_artists(reply, &album.artists);
 
                 if include_url {
-                    let _ = write!(msg, " - {}", album.external_urls.spotify);
+                    reply = reply.text(" - ").link(album.external_urls.spotify);
                 }
 
-                client.send_privmsg(channel, formatted(&msg))?;
+                client.send_privmsg(channel, reply)?;
             }
             Err(e) => handle_error(channel, client, &e)?,
         }
@@ -316,23 +321,25 @@ impl Spotify {
     ) -> Result<(), ZetaError> {
         match self.fetch::<Artist>(&format!("artists/{id}")).await {
             Ok(artist) => {
-                let name = artist.name;
                 let genres = to_sentence(&artist.genres);
                 let followers = artist.followers.total.to_formatted_string(&Locale::en);
 
-                let mut msg = format!("\x0f{name}\x0310 is");
+                let mut reply = Reply::tagged("Spotify").value(artist.name);
                 if artist.genres.is_empty() {
-                    let _ = write!(msg, " an");
+                    reply = reply.text(" is an");
                 } else {
-                    let _ = write!(msg, " a {genres}");
+                    reply = reply.text(format!(" is a {genres}"));
                 }
-                let _ = write!(msg, " artist with \x0f{followers}\x0310 followers");
+                reply = reply
+                    .text(" artist with ")
+                    .value(followers)
+                    .text(" followers");
 
                 if include_url {
-                    let _ = write!(msg, " - {}", artist.external_urls.spotify);
+                    reply = reply.text(" - ").link(artist.external_urls.spotify);
                 }
 
-                client.send_privmsg(channel, formatted(&msg))?;
+                client.send_privmsg(channel, reply)?;
             }
             Err(e) => handle_error(channel, client, &e)?,
         }
@@ -348,25 +355,28 @@ impl Spotify {
     ) -> Result<(), ZetaError> {
         match self.fetch::<Playlist>(&format!("playlists/{id}")).await {
             Ok(playlist) => {
-                let name = playlist.name;
-                let total_tracks = playlist.tracks.total;
                 let owner = playlist.owner.display_name.unwrap_or(playlist.owner.id);
                 let followers = playlist.followers.total;
 
-                let mut msg = format!(
-                    "\x0f{name}\x0310 is a playlist with\x0f {total_tracks}\x0310 tracks curated by\x0f {owner}\x0310"
-                );
+                let mut reply = Reply::tagged("Spotify")
+                    .value(playlist.name)
+                    .text(" is a playlist with ")
+                    .value(playlist.tracks.total)
+                    .text(" tracks curated by ")
+                    .value(owner);
 
                 if followers > 0 {
-                    let followers_fmt = followers.to_formatted_string(&Locale::en);
-                    let _ = write!(msg, " with \x0f{followers_fmt}\x0310 followers");
+                    reply = reply
+                        .text(" with ")
+                        .value(followers.to_formatted_string(&Locale::en))
+                        .text(" followers");
                 }
 
                 if include_url {
-                    let _ = write!(msg, " - {}", playlist.external_urls.spotify);
+                    reply = reply.text(" - ").link(playlist.external_urls.spotify);
                 }
 
-                client.send_privmsg(channel, formatted(&msg))?;
+                client.send_privmsg(channel, reply)?;
             }
             Err(e) => handle_error(channel, client, &e)?,
         }
@@ -374,10 +384,6 @@ impl Spotify {
     }
 }
 
-fn formatted(message: &str) -> String {
-    format!("\x0310>\x0f\x02 Spotify:\x02\x0310 {message}")
-}
-
 fn handle_error(channel: &str, client: &Client, error: &Error) -> Result<(), ZetaError> {
     warn!("Spotify error: {}", error);
     // Mimic Ruby behavior: simplistic error messages for common HTTP codes could be added here
@@ -387,18 +393,27 @@ fn handle_error(channel: &str, client: &Client, error: &Error) -> Result<(), Zet
     if let Error::Api(s) = error
         && s.contains("404")
     {
-        client.send_privmsg(channel, formatted("Resource not found"))?;
+        client.send_privmsg(channel, Reply::tagged("Spotify").text("Resource not found"))?;
     }
 
     Ok(())
 }
 
-fn join_artists(artists: &[ArtistSimple]) -> String {
-    let names: Vec<String> = artists
-        .iter()
-        .map(|a| format!("\x0f{}\x0310", a.name))
-        .collect();
-    to_sentence(&names)
+/// Appends the names of `artists` to `reply` as a sentence, e.g. `A, B and C`.
+fn with_artists(mut reply: Reply, artists: &[ArtistSimple]) -> Reply {
+    for (idx, artist) in artists.iter().enumerate() {
+        if idx > 0 {
+            reply = reply.text(if idx == artists.len() - 1 {
+                " and "
+            } else {
+                ", "
+            });
+        }
+
+        reply = reply.value(&artist.name);
+    }
+
+    reply
 }
 
 fn to_sentence(words: &[String]) -> String {
@@ -415,6 +430,13 @@ fn to_sentence(words: &[String]) -> String {
 }
 
 fn parse_spotify_url(url: &Url) -> Option<(&str, &str)> {
+    if !matches!(
+        url.host_str(),
+        Some("open.spotify.com" | "play.spotify.com")
+    ) {
+        return None;
+    }
+
     // path segments: ["track", "4uLU6hMCjMI75M1A2tKUQC"]
     let segments: Vec<&str> = url.path_segments()?.collect();
     if segments.len() >= 2 {
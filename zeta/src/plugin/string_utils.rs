@@ -38,32 +38,41 @@ impl Plugin<Context> for StringUtils {
         if let Command::PRIVMSG(ref channel, ref user_message) = message.command {
             if let Some(args) = self.bytes_command.parse(user_message) {
                 if args.is_empty() {
-                    client.send_privmsg(channel, formatted("Usage: .b\x0f <byte..>"))?;
+                    client
+                        .send_privmsg(channel, Reply::new().text("Usage: .b").value(" <byte..>"))?;
                 } else {
-                    client.send_privmsg(channel, formatted(&str_to_hex_string(args)))?;
+                    client.send_privmsg(channel, Reply::new().text(str_to_hex_string(args)))?;
                 }
             } else if let Some(args) = self.length_command.parse(user_message) {
                 if args.is_empty() {
-                    client.send_privmsg(channel, formatted("Usage: .len\x0f <string>"))?;
+                    client.send_privmsg(
+                        channel,
+                        Reply::new().text("Usage: .len").value(" <string>"),
+                    )?;
                 } else {
-                    client
-                        .send_privmsg(channel, formatted(&format!("{}", args.chars().count())))?;
+                    client.send_privmsg(channel, Reply::new().text(args.chars().count()))?;
                 }
             } else if let Some(args) = self.ord_command.parse(user_message) {
                 if args.is_empty() {
-                    client.send_privmsg(channel, formatted("Usage: .ord\x0f <chars..>"))?;
+                    client.send_privmsg(
+                        channel,
+                        Reply::new().text("Usage: .ord").value(" <chars..>"),
+                    )?;
                 } else {
                     let orded: Vec<String> = args.chars().map(|x| (x as u32).to_string()).collect();
 
-                    client.send_privmsg(channel, formatted(&orded.join(", ")))?;
+                    client.send_privmsg(channel, Reply::new().text(orded.join(", ")))?;
                 }
             } else if let Some(args) = self.reverse_command.parse(user_message) {
                 if args.is_empty() {
-                    client.send_privmsg(channel, formatted("Usage: .rev\x0f <string>"))?;
+                    client.send_privmsg(
+                        channel,
+                        Reply::new().text("Usage: .rev").value(" <string>"),
+                    )?;
                 } else {
                     let reversed: String = args.chars().rev().collect();
 
-                    client.send_privmsg(channel, formatted(&reversed))?;
+                    client.send_privmsg(channel, Reply::new().text(reversed))?;
                 }
             } else if let Some(_args) = self.unicode_command.parse(user_message) {
             }
@@ -73,10 +82,6 @@ impl Plugin<Context> for StringUtils {
     }
 }
 
-fn formatted(s: &str) -> String {
-    format!("\x0310> {s}")
-}
-
 fn str_to_hex_string(s: &str) -> String {
     let mut buf = String::with_capacity(s.len() * 4);
 
@@ -3,9 +3,6 @@
 //! This plugin detects Thingiverse URLs in messages and fetches information
 //! about the linked "thing" using the Thingiverse API.
 
-
-use std::fmt::{self, Display};
-
 use num_format::{Locale, ToFormattedString};
 use regex::Regex;
 use reqwest::header::AUTHORIZATION;
@@ -13,10 +10,8 @@ use serde::Deserialize;
 use tracing::{debug, warn};
 use url::Url;
 
-use crate::{
-    http,
-    plugin::{self, prelude::*},
-};
+use crate::http::RequestBuilderExt;
+use crate::{http, plugin::prelude::*};
 
 const API_BASE_URL: &str = "https://api.thingiverse.com";
 
@@ -89,22 +84,22 @@ impl Plugin<Context> for Thingiverse {
         }
     }
 
-    async fn handle_message(
+    fn claims_url(&self, url: &Url) -> bool {
+        matches!(
+            url.host_str(),
+            Some("thingiverse.com" | "www.thingiverse.com")
+        ) && self.path_regex.is_match(url.path())
+    }
+
+    async fn handle_url(
         &self,
         _ctx: &Context,
         client: &Client,
         message: &Message,
+        url: &Url,
     ) -> Result<(), ZetaError> {
-        if let Command::PRIVMSG(ref channel, ref user_message) = message.command
-            && let Some(urls) = plugin::extract_urls(user_message)
-        {
-            for url in urls {
-                if let Some(host) = url.host_str()
-                    && (host == "thingiverse.com" || host == "www.thingiverse.com")
-                {
-                    self.process_url(&url, channel, client).await?;
-                }
-            }
+        if let Command::PRIVMSG(ref channel, _) = message.command {
+            self.process_url(url, channel, client).await?;
         }
 
         Ok(())
@@ -131,14 +126,20 @@ impl Thingiverse {
 
             match self.fetch_thing(thing_id).await {
                 Ok(thing) => {
-                    client.send_privmsg(channel, format_irc_output(&thing.to_string()))?;
+                    client.send_privmsg(channel, thing.reply())?;
                 }
                 Err(Error::NotFound) => {
-                    client.send_privmsg(channel, format_irc_output("Thing not found"))?;
+                    client.send_privmsg(
+                        channel,
+                        Reply::tagged("Thingiverse").text("Thing not found"),
+                    )?;
                 }
                 Err(e) => {
                     warn!(error = ?e, "thingiverse api error");
-                    client.send_privmsg(channel, format_irc_output(&format!("http error: {e}")))?;
+                    client.send_privmsg(
+                        channel,
+                        Reply::tagged("Thingiverse").text(format!("http error: {e}")),
+                    )?;
                 }
             }
         }
@@ -154,7 +155,7 @@ impl Thingiverse {
             .client
             .get(&url)
             .header(AUTHORIZATION, format!("Bearer {}", self.app_token))
-            .send()
+            .send_shared()
             .await?;
 
         if response.status() == reqwest::StatusCode::NOT_FOUND {
@@ -169,53 +170,54 @@ impl Thingiverse {
     }
 }
 
-impl Display for Thing {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let name = &self.name;
-        let creator = &self.creator.name;
+impl Thing {
+    /// Returns a reply that presents the thing.
+    fn reply(&self) -> Reply {
+        let mut reply = Reply::tagged("Thingiverse")
+            .quoted(&self.name)
+            .text(" is a ");
 
-        let type_desc = if self.is_wip == 1 {
-            "\x0f work in progress\x0310"
+        reply = if self.is_wip == 1 {
+            reply.value("work in progress")
         } else if self.is_featured.unwrap_or(false) {
-            " \x0ffeatured\x0310"
+            reply.value("featured").text(" thing")
         } else {
-            " thing"
+            reply.text("thing")
         };
 
-        let likes = self.like_count.to_formatted_string(&Locale::en);
         let like_noun = if self.like_count == 1 {
             "like"
         } else {
             "likes"
         };
-
-        let downloads = self.download_count.to_formatted_string(&Locale::en);
         let dl_noun = if self.download_count == 1 {
             "download"
         } else {
             "downloads"
         };
 
-        write!(
-            f,
-            "“\x0f{name}\x0310” is a{type_desc} created by\x0f {creator}\x0310 with\x0f {likes}\x0310 {like_noun}, \x0f{downloads}\x0310 {dl_noun}"
-        )?;
+        reply = reply
+            .text(" created by ")
+            .value(&self.creator.name)
+            .text(" with ")
+            .value(self.like_count.to_formatted_string(&Locale::en))
+            .text(format!(" {like_noun}, "))
+            .value(self.download_count.to_formatted_string(&Locale::en))
+            .text(format!(" {dl_noun}"));
 
         if self.collect_count > 0 {
-            let collects = self.collect_count.to_formatted_string(&Locale::en);
             let coll_noun = if self.collect_count == 1 {
                 "collection"
             } else {
                 "collections"
             };
-            write!(f, " and is part of\x0f {collects}\x0310 {coll_noun}")?;
+
+            reply = reply
+                .text(" and is part of ")
+                .value(self.collect_count.to_formatted_string(&Locale::en))
+                .text(format!(" {coll_noun}"));
         }
 
-        Ok(())
+        reply
     }
 }
-
-/// Wraps a message in the standard Zeta plugin prefix.
-fn format_irc_output(message: &str) -> String {
-    format!("\x0310>\x0F \x02Thingiverse:\x02\x0310 {message}")
-}
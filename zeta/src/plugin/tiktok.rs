@@ -1,17 +1,12 @@
 #![allow(clippy::doc_markdown)]
 
-use std::fmt::Write;
-
-use reqwest::header::LOCATION;
+use reqwest::Method;
 use serde::Deserialize;
 use tracing::{debug, error};
 use url::Url;
 
-use crate::{
-    http,
-    plugin::{self, prelude::*},
-    utils::Truncatable,
-};
+use crate::http::{FetchError, GuardedClient, RequestBuilderExt};
+use crate::{http, plugin::prelude::*, utils::Truncatable};
 
 /// The URL to the oEmbed endpoint.
 const TIKTOK_OEMBED_API: &str = "https://www.tiktok.com/oembed";
@@ -27,22 +22,24 @@ const TIKTOK_TITLE_LENGTH: usize = 150;
 
 pub struct Tiktok {
     client: reqwest::Client,
+    /// HTTP client for resolving shortened links
+    guarded: GuardedClient,
 }
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("request error: {0}")]
     Request(#[from] reqwest::Error),
-    #[error("location header is missing from response")]
-    MissingLocationHeader,
-    #[error("location header contains invalid utf-8")]
-    InvalidHeaderValue,
-    #[error("location header contains invalid URL")]
+    #[error(transparent)]
+    Fetch(#[from] FetchError),
+    #[error("shortened link is not a valid URL")]
     InvalidUrl,
     #[error("tiktok returned invalid oembed response")]
     InvalidOEmbed,
     #[error("shortened link redirects to invalid url")]
     InvalidRedirectUrl,
+    #[error("irc error: {0}")]
+    Irc(#[from] irc::error::Error),
 }
 
 #[derive(Eq, PartialEq, Debug)]
@@ -81,8 +78,8 @@ pub struct OEmbed {
 
 #[async_trait]
 impl Plugin<Context> for Tiktok {
-    fn new(_ctx: &Context) -> Result<Tiktok, ZetaError> {
-        Ok(Tiktok::new())
+    fn new(ctx: &Context) -> Result<Tiktok, ZetaError> {
+        Ok(Tiktok::new(ctx))
     }
 
     fn metadata() -> Metadata {
@@ -92,17 +89,24 @@ impl Plugin<Context> for Tiktok {
         }
     }
 
-    async fn handle_message(
+    fn claims_url(&self, url: &Url) -> bool {
+        matches!(
+            classify_tiktok_url(url),
+            Some(UrlKind::Video(..) | UrlKind::Shortened(_))
+        )
+    }
+
+    async fn handle_url(
         &self,
         _ctx: &Context,
         client: &Client,
         message: &Message,
+        url: &Url,
     ) -> Result<(), ZetaError> {
-        if let Command::PRIVMSG(ref channel, ref user_message) = message.command
-            && let Some(urls) = plugin::extract_urls(user_message)
-            && let Err(err) = self.process_urls(urls, channel, client).await
+        if let Command::PRIVMSG(ref channel, _) = message.command
+            && let Err(err) = self.process_url(url, channel, client).await
         {
-            error!("could not process urls: {err}");
+            error!("could not process url: {err}");
         }
 
         Ok(())
@@ -110,23 +114,11 @@ impl Plugin<Context> for Tiktok {
 }
 
 impl Tiktok {
-    pub fn new() -> Self {
+    pub fn new(ctx: &Context) -> Self {
         let client = http::build_client();
+        let guarded = GuardedClient::new(ctx);
 
-        Self { client }
-    }
-
-    async fn process_urls(
-        &self,
-        urls: Vec<Url>,
-        channel: &str,
-        client: &Client,
-    ) -> Result<(), Error> {
-        for url in urls {
-            self.process_url(&url, channel, client).await?;
-        }
-
-        Ok(())
+        Self { client, guarded }
     }
 
     async fn process_url(&self, url: &Url, channel: &str, client: &Client) -> Result<(), Error> {
@@ -187,20 +179,20 @@ impl Tiktok {
             return Ok(());
         }
 
-        let mut buf = String::new();
+        let mut reply = Reply::new();
 
-        if let Some(title) = embed.title {
+        if let Some(title) = &embed.title {
             let truncated = title.truncate_with_suffix(TIKTOK_TITLE_LENGTH, "…");
 
-            let _ = write!(buf, "“\x0f{truncated}\x0310” is a ");
+            reply = reply.quoted(truncated).text(" is a ");
         }
 
-        if let Some(author_name) = embed.author_name {
-            let _ = write!(buf, "TikTok video by\x0f {author_name}");
+        if let Some(author_name) = &embed.author_name {
+            reply = reply.text("TikTok video by ").value(author_name);
         }
 
-        if !buf.is_empty() {
-            client.send_privmsg(channel, formatted(&buf)).unwrap();
+        if embed.title.is_some() || embed.author_name.is_some() {
+            client.send_privmsg(channel, reply)?;
         }
 
         Ok(())
@@ -209,7 +201,7 @@ impl Tiktok {
     async fn fetch_oembed_data(&self, url: &str) -> Result<OEmbed, Error> {
         debug!(%url, "fetching oembed data");
         let request = self.client.get(TIKTOK_OEMBED_API).query(&[("url", url)]);
-        let response = request.send().await.map_err(Error::Request)?;
+        let response = request.send_shared().await.map_err(Error::Request)?;
         let oembed = response.json().await.map_err(|_| Error::InvalidOEmbed)?;
 
         Ok(oembed)
@@ -218,14 +210,9 @@ impl Tiktok {
     /// Requests the redirect with the given id and returns the location it redirects to.
     async fn resolve_redirect_url(&self, id: &str) -> Result<Url, Error> {
         debug!(%id, "fetching redirect url");
-        let request = self.client.get(format!("https://vm.tiktok.com/{id}/"));
-        let response = request.send().await.map_err(Error::Request)?;
-        let location = response
-            .headers()
-            .get(LOCATION)
-            .ok_or_else(|| Error::MissingLocationHeader)?;
-        let location_str = location.to_str().map_err(|_| Error::InvalidHeaderValue)?;
-        let url = Url::parse(location_str).map_err(|_| Error::InvalidUrl)?;
+        let url = Url::parse(&format!("https://{TIKTOK_SHORT_HOST}/{id}/"))
+            .map_err(|_| Error::InvalidUrl)?;
+        let url = self.guarded.redirect_location(Method::GET, &url).await?;
         debug!(%url, "fetched redirect url");
         debug_assert_eq!(url.host_str(), Some("www.tiktok.com"));
 
@@ -233,10 +220,6 @@ impl Tiktok {
     }
 }
 
-fn formatted(s: &str) -> String {
-    format!("\x0310> {s}")
-}
-
 /// Parses the given `url` and returns a [`UrlKind`] depending on the type of Tiktok URL.
 fn classify_tiktok_url(url: &Url) -> Option<UrlKind> {
     match url.host_str()? {
@@ -6,6 +6,7 @@ use num_format::{Locale, ToFormattedString};
 use serde::Deserialize;
 use tracing::{debug, warn};
 
+use crate::http::RequestBuilderExt;
 use crate::{http, plugin::prelude::*};
 
 /// The base URL for the Trustpilot API.
@@ -99,21 +100,32 @@ impl Plugin<Context> for Trustpilot {
             && let Some(query) = self.command.parse(user_message)
         {
             if query.trim().is_empty() {
-                client.send_privmsg(channel, "\x0310> Usage: .tp\x0f <domain name>")?;
+                client.send_privmsg(
+                    channel,
+                    Reply::tagged("Trustpilot")
+                        .text("Usage: .tp")
+                        .value(" <domain name>"),
+                )?;
                 return Ok(());
             }
 
             match self.search(query).await {
                 Ok(business) => {
-                    client.send_privmsg(channel, format_business(&business))?;
+                    client.send_privmsg(channel, business_reply(&business))?;
                 }
                 Err(Error::NotFound) => {
-                    client.send_privmsg(channel, "\x0310> No results found")?;
+                    client.send_privmsg(
+                        channel,
+                        Reply::tagged("Trustpilot").text("No results found"),
+                    )?;
                 }
                 Err(e) => {
                     warn!(error = ?e, "trustpilot error");
                     // The error is already safe for display
-                    client.send_privmsg(channel, format!("\x0310> Error: {e}"))?;
+                    client.send_privmsg(
+                        channel,
+                        Reply::tagged("Trustpilot").text(format!("Error: {e}")),
+                    )?;
                 }
             }
         }
@@ -142,7 +154,7 @@ impl Trustpilot {
             .get(&url)
             .header("apikey", &self.api_key)
             .query(&params)
-            .send()
+            .send_shared()
             .await?;
 
         if response.status() == reqwest::StatusCode::NOT_FOUND {
@@ -156,24 +168,28 @@ impl Trustpilot {
     }
 }
 
-/// Formats a business unit into an IRC-friendly string.
-fn format_business(b: &BusinessUnit) -> String {
-    let score = b.score.trust_score;
+/// Returns a reply that presents a business unit.
+fn business_reply(b: &BusinessUnit) -> Reply {
     let reviews = b.number_of_reviews.total.to_formatted_string(&Locale::en);
     let url = format!("https://dk.trustpilot.com/review/{}", b.name.identifying);
-    let name = &b.display_name;
 
-    format!(
-        "\x0310>\x0f\x02 Trustpilot\x02\x0310 (\x0f{name}\x0310): Score:\x0f {score}\x0310/\x0f5.0\x0310 Reviews:\x0f {reviews}\x0310 - {url}"
-    )
+    Reply::tagged("Trustpilot")
+        .value(&b.display_name)
+        .text(" -")
+        .field("Score", format!("{}/5.0", b.score.trust_score))
+        .field("Reviews", reviews)
+        .text(" - ")
+        .link(url)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use zeta_plugin::reply::{Output, Theme};
+
     #[test]
-    fn test_format_business() {
+    fn test_business_reply() {
         let business = BusinessUnit {
             display_name: "Cool Company".to_string(),
             name: BusinessName {
@@ -183,11 +199,11 @@ mod tests {
             number_of_reviews: NumberOfReviews { total: 12345 },
         };
 
-        let formatted = format_business(&business);
-        // Note: contains IRC color codes
+        let reply = business_reply(&business).render(Output::Plain, &Theme::default());
+
         assert_eq!(
-            formatted,
-            "\x0310>\x0f\x02 Trustpilot\x02\x0310 (\x0fCool Company\x0310): Score:\x0f 4.8\x0310/\x0f5.0\x0310 Reviews:\x0f 12,345\x0310 - https://dk.trustpilot.com/review/coolcompany.com"
+            reply,
+            "> Trustpilot: Cool Company - Score: 4.8/5.0 Reviews: 12,345 - https://dk.trustpilot.com/review/coolcompany.com"
         );
     }
 
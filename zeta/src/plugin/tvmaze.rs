@@ -9,6 +9,8 @@ use serde::{Deserialize, de::DeserializeOwned};
 use time::Duration;
 use tracing::{debug, error, instrument};
 
+use crate::http::RequestBuilderExt;
+use crate::i18n::{self, Locale, args};
 use crate::{http, plugin::prelude::*};
 
 /// Base URL for the TVmaze API.
@@ -143,14 +145,17 @@ impl Plugin<Context> for Tvmaze {
 
     async fn handle_message(
         &self,
-        _ctx: &Context,
+        ctx: &Context,
         client: &Client,
         message: &Message,
     ) -> Result<(), ZetaError> {
         if let Command::PRIVMSG(ref channel, ref user_message) = message.command
             && let Some(args) = self.command.parse(user_message)
         {
-            self.handle_show_search(args, channel, client).await?;
+            let locale = i18n::locale(ctx, message).await;
+
+            self.handle_show_search(args, channel, locale, client)
+                .await?;
         }
 
         Ok(())
@@ -187,7 +192,12 @@ impl Tvmaze {
         let url = self.build_search_url(name);
         debug!(url.full = %url, "requesting single search for show: {name}");
 
-        let response = self.client.get(url).send().await.map_err(Error::Request)?;
+        let response = self
+            .client
+            .get(url)
+            .send_shared()
+            .await
+            .map_err(Error::Request)?;
 
         Self::handle_search_response(response).await
     }
@@ -217,78 +227,71 @@ impl Tvmaze {
         &self,
         name: &str,
         channel: &str,
+        locale: Locale,
         client: &Client,
     ) -> Result<(), ZetaError> {
-        match self.single_search(name).await {
-            Ok(show) => {
-                let message = Self::format_show_message(&show);
-
-                client.send_privmsg(channel, message)?;
-            }
-            Err(err) => {
-                let error_message = Self::format_error_message(&err);
+        let reply = match self.single_search(name).await {
+            Ok(show) => Self::show_reply(&show, locale),
+            Err(err) => Self::error_reply(&err, locale),
+        };
 
-                client.send_privmsg(channel, &error_message)?;
-            }
-        }
+        client.send_privmsg(channel, reply)?;
 
         Ok(())
     }
 
-    /// Formats a show message based on whether there's a next episode.
-    fn format_show_message(show: &Show) -> String {
+    /// Builds a reply based on whether there's a next episode.
+    fn show_reply(show: &Show, locale: Locale) -> Reply {
         show.embedded
             .as_ref()
             .and_then(|e| e.next_episode.as_ref())
             .map_or_else(
-                || Self::format_show_status_message(show),
-                |episode| Self::format_next_episode_message(show, episode),
+                || Self::show_status_reply(show, locale),
+                |episode| Self::next_episode_reply(show, episode, locale),
             )
     }
 
-    /// Formats a message about the next episode.
-    fn format_next_episode_message(show: &Show, episode: &Episode) -> String {
-        let title = &episode.name;
-        let season = episode.season;
-        let number = episode.number;
+    /// Builds a reply about the next episode.
+    fn next_episode_reply(show: &Show, episode: &Episode, locale: Locale) -> Reply {
         let time_until_air = {
             let now = time::OffsetDateTime::now_utc();
             episode.airstamp.map_or_else(
                 || "???".to_string(),
-                |airstamp| {
-                    let dt = airstamp - now;
-                    duration_in_words(dt)
-                },
+                |airstamp| duration_in_words(airstamp - now, locale),
             )
         };
-        let content = format!(
-            "Next episode “\x0f{title}\x0310” (\x0f{season}x{number:02}\x0310) airs in\x0f {time_until_air}"
-        );
-
-        Self::build_formatted_message(Some(&show.name), &content)
+        let reply = Reply::tagged("TVmaze").value(&show.name).text(": ");
+
+        locale.reply(
+            reply,
+            "tvmaze-next-episode",
+            args!(
+                title = episode.name,
+                episode = format!("{}x{:02}", episode.season, episode.number),
+                duration = time_until_air
+            ),
+        )
     }
 
-    /// Formats a message about the show's current status.
-    fn format_show_status_message(show: &Show) -> String {
-        let name = &show.name;
-        let status = &show.status;
-        let content = format!(
-            "\x0f{name}\x0310 is currently marked as\x0f {status}\x0310 and there is no next episode"
-        );
-
-        Self::build_formatted_message(None, &content)
+    /// Builds a reply about the show's current status.
+    fn show_status_reply(show: &Show, locale: Locale) -> Reply {
+        locale.reply(
+            Reply::tagged("TVmaze"),
+            "tvmaze-no-next-episode",
+            args!(name = show.name, status = show.status),
+        )
     }
 
-    /// Formats an error message for display.
-    fn format_error_message(error: &Error) -> String {
-        let content = match error {
-            Error::NotFound => "Show not found".to_string(),
-            Error::Request(_) => "Failed to fetch show information".to_string(),
-            Error::Deserialize(_) => "Failed to parse show information".to_string(),
-            Error::UnexpectedResponse => "Received unexpected response from API".to_string(),
+    /// Builds a reply describing an error.
+    fn error_reply(error: &Error, locale: Locale) -> Reply {
+        let key = match error {
+            Error::NotFound => "tvmaze-not-found",
+            Error::Request(_) => "tvmaze-request-failed",
+            Error::Deserialize(_) => "tvmaze-invalid-response",
+            Error::UnexpectedResponse => "tvmaze-unexpected-response",
         };
 
-        Self::build_formatted_message(None, &format!("Error: {content}"))
+        Reply::tagged("TVmaze").text(locale.format(key, &[]))
     }
 
     /// Builds the search URL with query parameters.
@@ -300,15 +303,6 @@ impl Tvmaze {
             .append_pair("embed", "nextepisode");
         url
     }
-
-    /// Formats a message for display in IRC with optional prefix.
-    #[allow(clippy::option_if_let_else)]
-    fn build_formatted_message(prefix: Option<&str>, message: &str) -> String {
-        match prefix {
-            Some(name) => format!("\x0310>\x03\x02 TVmaze\x02\x0310 (\x0f{name}\x0310): {message}"),
-            None => format!("\x0310>\x03\x02 TVmaze\x02\x0310: {message}"),
-        }
-    }
 }
 
 /// Deserializes an HTTP response into the specified type.
@@ -326,13 +320,9 @@ async fn deserialize_response<T: DeserializeOwned>(response: Response) -> Result
         .map_err(Error::Deserialize)
 }
 
-fn duration_in_words(duration: Duration) -> String {
-    let total_seconds = duration.whole_seconds();
-
-    // Handle zero or negative durations
-    if total_seconds <= 0 {
-        return "0 minutes".to_string();
-    }
+/// Formats a duration in words, e.g. `1 week, 2 days and 3 hours`.
+fn duration_in_words(duration: Duration, locale: Locale) -> String {
+    let total_seconds = duration.whole_seconds().max(0);
 
     // Calculate time units
     let weeks = total_seconds / (7 * 24 * 60 * 60);
@@ -344,43 +334,43 @@ fn duration_in_words(duration: Duration) -> String {
     let minutes = remaining_after_hours / 60;
 
     // Build the parts vector with non-zero units
-    let mut parts = Vec::new();
-
-    if weeks > 0 {
-        parts.push(format!(
-            "{} week{}",
-            weeks,
-            if weeks == 1 { "" } else { "s" }
-        ));
-    }
-    if days > 0 {
-        parts.push(format!("{} day{}", days, if days == 1 { "" } else { "s" }));
+    let parts: Vec<String> = [
+        ("duration-week", weeks),
+        ("duration-day", days),
+        ("duration-hour", hours),
+        ("duration-minute", minutes),
+    ]
+    .into_iter()
+    .filter(|(_, count)| *count > 0)
+    .map(|(key, count)| locale.plural(key, count))
+    .collect();
+
+    if parts.is_empty() {
+        locale.plural("duration-minute", 0)
+    } else {
+        locale.list(&parts)
     }
+}
 
-    if hours > 0 {
-        parts.push(format!(
-            "{} hour{}",
-            hours,
-            if hours == 1 { "" } else { "s" }
-        ));
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    if minutes > 0 {
-        parts.push(format!(
-            "{} minute{}",
-            minutes,
-            if minutes == 1 { "" } else { "s" }
-        ));
-    }
+    #[test]
+    fn it_should_format_durations_in_words() {
+        let duration = Duration::weeks(1) + Duration::hours(3) + Duration::minutes(1);
 
-    // Format the output with proper grammar
-    match parts.len() {
-        0 => "0 minutes".to_string(),
-        1 => parts[0].clone(),
-        2 => format!("{} and {}", parts[0], parts[1]),
-        _ => {
-            let last = parts.pop().unwrap();
-            format!("{}, and {}", parts.join(", "), last)
-        }
+        assert_eq!(
+            duration_in_words(duration, Locale::English),
+            "1 week, 3 hours and 1 minute"
+        );
+        assert_eq!(
+            duration_in_words(duration, Locale::Danish),
+            "1 uge, 3 timer og 1 minut"
+        );
+        assert_eq!(
+            duration_in_words(Duration::seconds(-5), Locale::English),
+            "0 minutes"
+        );
     }
 }
@@ -1,6 +1,5 @@
 #![allow(clippy::doc_markdown)]
 
-
 use std::time::{Duration, Instant};
 
 use num_format::{Locale, ToFormattedString};
@@ -9,10 +8,8 @@ use tokio::sync::RwLock;
 use tracing::{debug, warn};
 use url::Url;
 
-use crate::{
-    http,
-    plugin::{self, prelude::*},
-};
+use crate::http::RequestBuilderExt;
+use crate::{http, plugin::prelude::*};
 
 /// Twitch OAuth2 token endpoint.
 const AUTH_URL: &str = "https://id.twitch.tv/oauth2/token";
@@ -131,27 +128,28 @@ impl Plugin<Context> for Twitch {
         }
     }
 
-    async fn handle_message(
+    fn claims_url(&self, url: &Url) -> bool {
+        Self::parse_url(url).is_some()
+    }
+
+    async fn handle_url(
         &self,
         _ctx: &Context,
         client: &Client,
         message: &Message,
+        url: &Url,
     ) -> Result<(), ZetaError> {
-        if let Command::PRIVMSG(ref channel, ref user_message) = message.command
-            && let Some(urls) = plugin::extract_urls(user_message)
+        if let Command::PRIVMSG(ref channel, _) = message.command
+            && let Some(kind) = Self::parse_url(url)
         {
-            for url in urls {
-                if let Some(kind) = Self::parse_url(&url) {
-                    let result = match kind {
-                        UrlKind::Stream(login) => self.handle_stream(channel, &login, client).await,
-                        UrlKind::Clip(id) => self.handle_clip(channel, &id, client).await,
-                        UrlKind::Video(id) => self.handle_video(channel, &id, client).await,
-                    };
-
-                    if let Err(e) = result {
-                        warn!("Twitch plugin error: {}", e);
-                    }
-                }
+            let result = match kind {
+                UrlKind::Stream(login) => self.handle_stream(channel, &login, client).await,
+                UrlKind::Clip(id) => self.handle_clip(channel, &id, client).await,
+                UrlKind::Video(id) => self.handle_video(channel, &id, client).await,
+            };
+
+            if let Err(e) = result {
+                warn!("Twitch plugin error: {}", e);
             }
         }
 
@@ -179,7 +177,12 @@ impl Twitch {
             ("grant_type", "client_credentials"),
         ];
 
-        let response = self.client.post(AUTH_URL).form(&params).send().await?;
+        let response = self
+            .client
+            .post(AUTH_URL)
+            .form(&params)
+            .send_shared()
+            .await?;
         let auth: AuthResponse = response.error_for_status()?.json().await?;
 
         let token = Token {
@@ -207,7 +210,7 @@ impl Twitch {
             .header("Client-ID", &self.client_id)
             .header("Authorization", format!("Bearer {token}"))
             .query(query)
-            .send()
+            .send_shared()
             .await?;
 
         if !response.status().is_success() {
@@ -255,17 +258,18 @@ impl Twitch {
         let response: Response<Stream> = self.get("streams", &[("user_login", user_login)]).await?;
 
         if let Some(stream) = response.data.first() {
-            let user_login = &stream.user_login;
-            let title = &stream.title;
-            let game_name = &stream.game_name;
             let viewers = stream.viewer_count.to_formatted_string(&Locale::en);
-
-            client.send_privmsg(channel, formatted(&format!(
-                "{user_login}:\x0f {title}\x0310 - Game:\x0f {game_name}\x0310 Viewers:\x0f {viewers}\x0310"
-            )))?;
+            let reply = Reply::tagged("Twitch")
+                .text(format!("{}: ", stream.user_login))
+                .value(&stream.title)
+                .text(" -")
+                .field("Game", &stream.game_name)
+                .field("Viewers", viewers);
+
+            client.send_privmsg(channel, reply)?;
         } else {
             // Fallback behavior: just print the channel name if not live.
-            client.send_privmsg(channel, format!("\x0310> {user_login} - Twitch"))?;
+            client.send_privmsg(channel, Reply::new().text(format!("{user_login} - Twitch")))?;
         }
 
         Ok(())
@@ -281,16 +285,19 @@ impl Twitch {
         let response: Response<Clip> = self.get("clips", &[("id", clip_id)]).await?;
 
         if let Some(clip) = response.data.first() {
-            let title = &clip.title;
-            let broadcaster = &clip.broadcaster_name;
-            let creator = &clip.creator_name;
-            let views = clip.view_count.to_formatted_string(&Locale::en);
-
-            client.send_privmsg(channel, formatted(&format!(
-                "“\x0f{title}\x0310” is a clip of\x0f {broadcaster}\x0310 clipped by\x0f {creator}\x0310 with\x0f {views}\x0310 views"
-            )))?;
+            let reply = Reply::tagged("Twitch")
+                .quoted(&clip.title)
+                .text(" is a clip of ")
+                .value(&clip.broadcaster_name)
+                .text(" clipped by ")
+                .value(&clip.creator_name)
+                .text(" with ")
+                .value(clip.view_count.to_formatted_string(&Locale::en))
+                .text(" views");
+
+            client.send_privmsg(channel, reply)?;
         } else {
-            client.send_privmsg(channel, formatted("No results"))?;
+            client.send_privmsg(channel, Reply::tagged("Twitch").text("No results"))?;
         }
 
         Ok(())
@@ -306,26 +313,23 @@ impl Twitch {
         let response: Response<Video> = self.get("videos", &[("id", video_id)]).await?;
 
         if let Some(video) = response.data.first() {
-            let title = &video.title;
-            let user = &video.user_name;
-            let views = video.view_count.to_formatted_string(&Locale::en);
-
-            client.send_privmsg(channel, formatted(&format!(
-                "“\x0f{title}\x0310” is a video by\x0f {user}\x0310 with\x0f {views}\x0310 views"
-            )))?;
+            let reply = Reply::tagged("Twitch")
+                .quoted(&video.title)
+                .text(" is a video by ")
+                .value(&video.user_name)
+                .text(" with ")
+                .value(video.view_count.to_formatted_string(&Locale::en))
+                .text(" views");
+
+            client.send_privmsg(channel, reply)?;
         } else {
-            client.send_privmsg(channel, formatted("No results"))?;
+            client.send_privmsg(channel, Reply::tagged("Twitch").text("No results"))?;
         }
 
         Ok(())
     }
 }
 
-/// Formats a message with the Twitch prefix and colors.
-fn formatted(message: &str) -> String {
-    format!("\x0310>\x0F\x02 Twitch:\x02\x0310 {message}")
-}
-
 /// Checks if a string looks like a valid Twitch username.
 ///
 /// Twitch usernames are 4-25 characters long and contain alphanumeric characters
@@ -0,0 +1,315 @@
+//! Fallback link unfurling.
+//!
+//! Links that no other plugin claims are fetched and summarized: HTML pages are summarized by
+//! their `og:title` or `<title>` and their `og:site_name`, and other resources by their content
+//! type and size. Links to denied domains are left alone.
+
+use scraper::{Html, Selector};
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::{
+    http::{FetchError, FetchOptions, Fetched, GuardedClient},
+    plugin::prelude::*,
+    utils::Truncatable,
+};
+
+/// The maximum number of characters of a title to send.
+const MAX_TITLE_LENGTH: usize = 250;
+
+/// Content types that are parsed as HTML.
+const HTML_CONTENT_TYPES: &[&str] = &["text/html", "application/xhtml+xml"];
+
+/// Link unfurling plugin.
+pub struct Unfurl {
+    /// HTTP client for fetching links.
+    client: GuardedClient,
+    /// Domains whose links are never unfurled.
+    denied_domains: Vec<String>,
+    /// The maximum number of bytes to read from a link.
+    max_bytes: usize,
+    /// Selectors for the page metadata.
+    selectors: Selectors,
+}
+
+/// Selectors for the metadata of an HTML page.
+struct Selectors {
+    /// `<meta property="og:title">`
+    og_title: Selector,
+    /// `<meta property="og:site_name">`
+    og_site_name: Selector,
+    /// `<title>`
+    title: Selector,
+}
+
+/// A summary of a fetched link.
+#[derive(Debug, PartialEq, Eq)]
+enum Summary {
+    /// An HTML page.
+    Page {
+        /// The title of the page.
+        title: String,
+        /// The name of the site the page belongs to.
+        site_name: Option<String>,
+    },
+    /// Any other resource.
+    Resource {
+        /// The content type of the resource.
+        content_type: String,
+        /// The size of the resource in bytes, if known.
+        size: Option<u64>,
+    },
+}
+
+/// Errors that can occur when unfurling a link.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Fetch(#[from] FetchError),
+    #[error("irc error: {0}")]
+    Irc(#[from] irc::error::Error),
+}
+
+#[async_trait]
+impl Plugin<Context> for Unfurl {
+    fn new(ctx: &Context) -> Result<Self, ZetaError> {
+        let config = &ctx.config.plugins.unfurl;
+
+        Ok(Unfurl {
+            client: GuardedClient::new(ctx),
+            denied_domains: config
+                .denied_domains
+                .iter()
+                .map(|domain| domain.trim_start_matches('.').to_ascii_lowercase())
+                .collect(),
+            max_bytes: config.max_bytes,
+            selectors: Selectors::new(),
+        })
+    }
+
+    fn metadata() -> Metadata {
+        Metadata {
+            name: "unfurl".into(),
+            authors: vec!["Mikkel Kroman <mk@maero.dk>".into()],
+        }
+    }
+
+    fn claims_url(&self, url: &Url) -> bool {
+        url.host_str()
+            .is_some_and(|host| !is_denied(&host.to_ascii_lowercase(), &self.denied_domains))
+    }
+
+    async fn handle_url(
+        &self,
+        _ctx: &Context,
+        client: &Client,
+        message: &Message,
+        url: &Url,
+    ) -> Result<(), ZetaError> {
+        if let Command::PRIVMSG(ref channel, _) = message.command
+            && let Err(err) = self.unfurl(url, channel, client).await
+        {
+            warn!(%url, error = %err, "could not unfurl link");
+        }
+
+        Ok(())
+    }
+}
+
+impl Unfurl {
+    /// Fetches `url` and sends a summary of it to `channel`.
+    async fn unfurl(&self, url: &Url, channel: &str, client: &Client) -> Result<(), Error> {
+        debug!(%url, "unfurling link");
+
+        let options = FetchOptions {
+            max_bytes: Some(self.max_bytes),
+            truncate: true,
+            ..FetchOptions::default()
+        };
+        let fetched = self.client.fetch(url, &options).await?;
+
+        if let Some(summary) = self.summarize(&fetched) {
+            client.send_privmsg(channel, summary.reply())?;
+        }
+
+        Ok(())
+    }
+
+    /// Summarizes a fetched link, or returns `None` if there's nothing worth sending.
+    fn summarize(&self, fetched: &Fetched) -> Option<Summary> {
+        let content_type = fetched.content_type()?;
+
+        if HTML_CONTENT_TYPES.contains(&content_type.as_str()) {
+            return self.selectors.summarize_page(&fetched.text());
+        }
+
+        let size = fetched
+            .headers
+            .get(reqwest::header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok()?.parse().ok())
+            .or_else(|| (!fetched.truncated).then_some(fetched.body.len() as u64));
+
+        Some(Summary::Resource { content_type, size })
+    }
+}
+
+impl Selectors {
+    /// Parses the selectors.
+    fn new() -> Selectors {
+        Selectors {
+            og_title: Selector::parse(r#"meta[property="og:title"]"#).unwrap(),
+            og_site_name: Selector::parse(r#"meta[property="og:site_name"]"#).unwrap(),
+            title: Selector::parse("title").unwrap(),
+        }
+    }
+
+    /// Summarizes an HTML page, or returns `None` if it has no title.
+    fn summarize_page(&self, html: &str) -> Option<Summary> {
+        let document = Html::parse_document(html);
+        let meta = |selector: &Selector| {
+            document
+                .select(selector)
+                .find_map(|element| element.attr("content"))
+                .map(collapse_whitespace)
+                .filter(|content| !content.is_empty())
+        };
+
+        let title = meta(&self.og_title).or_else(|| {
+            document
+                .select(&self.title)
+                .next()
+                .map(|element| collapse_whitespace(&element.text().collect::<String>()))
+                .filter(|title| !title.is_empty())
+        })?;
+        let site_name = meta(&self.og_site_name);
+
+        Some(Summary::Page { title, site_name })
+    }
+}
+
+impl Summary {
+    /// Returns a reply that presents the summary.
+    fn reply(&self) -> Reply {
+        match self {
+            Summary::Page { title, site_name } => {
+                let title = title.truncate_with_suffix(MAX_TITLE_LENGTH, "…");
+
+                match site_name {
+                    Some(site_name) => Reply::tagged(site_name).text(title),
+                    None => Reply::new().text(title),
+                }
+            }
+            Summary::Resource {
+                content_type,
+                size: Some(size),
+            } => Reply::new().text(format!("{content_type}, {}", format_size(*size))),
+            Summary::Resource {
+                content_type,
+                size: None,
+            } => Reply::new().text(content_type),
+        }
+    }
+}
+
+/// Returns whether `host` is one of the `denied` domains, or a subdomain of one.
+fn is_denied(host: &str, denied: &[String]) -> bool {
+    denied.iter().any(|domain| {
+        host == domain
+            || host
+                .strip_suffix(domain.as_str())
+                .is_some_and(|prefix| prefix.ends_with('.'))
+    })
+}
+
+/// Collapses runs of whitespace in `s` into single spaces.
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Formats a size in bytes with a binary unit, e.g. `1.5 MiB`.
+#[allow(clippy::cast_precision_loss)]
+fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use zeta_plugin::reply::{Output, Theme};
+
+    #[test]
+    fn it_should_prefer_opengraph_metadata() {
+        let html = r#"<html><head>
+            <title>Fallback title</title>
+            <meta property="og:title" content="Some  &amp; article">
+            <meta property="og:site_name" content="Example News">
+        </head></html>"#;
+
+        assert_eq!(
+            Selectors::new().summarize_page(html),
+            Some(Summary::Page {
+                title: "Some & article".to_string(),
+                site_name: Some("Example News".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn it_should_fall_back_to_the_title_element() {
+        let html = "<html><head><title>\n  A   blog post\n</title></head></html>";
+
+        assert_eq!(
+            Selectors::new().summarize_page(html),
+            Some(Summary::Page {
+                title: "A blog post".to_string(),
+                site_name: None,
+            })
+        );
+        assert_eq!(Selectors::new().summarize_page("<p>no title</p>"), None);
+    }
+
+    #[test]
+    fn it_should_match_denied_subdomains() {
+        let denied = vec!["example.com".to_string()];
+
+        assert!(is_denied("example.com", &denied));
+        assert!(is_denied("cdn.example.com", &denied));
+        assert!(!is_denied("notexample.com", &denied));
+        assert!(!is_denied("example.org", &denied));
+    }
+
+    #[test]
+    fn it_should_format_sizes() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+    }
+
+    #[test]
+    fn it_should_format_summaries() {
+        let resource = Summary::Resource {
+            content_type: "image/png".to_string(),
+            size: Some(2048),
+        };
+
+        assert_eq!(
+            resource.reply().render(Output::Plain, &Theme::default()),
+            "> image/png, 2.0 KiB"
+        );
+    }
+}
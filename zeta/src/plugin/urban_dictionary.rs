@@ -1,12 +1,10 @@
-use std::fmt::Display;
-
 use serde::Deserialize;
 use time::OffsetDateTime;
 use tracing::debug;
 
+use crate::http::RequestBuilderExt;
 use crate::{http, plugin::prelude::*};
 
-pub const USAGE: &str = "Usage: .ud\x0f <query>";
 pub const BASE_URL: &str = "https://api.urbandictionary.com";
 
 /// Urban Dictionary plugin.
@@ -79,19 +77,29 @@ impl Plugin<Context> for UrbanDictionary {
         if let Command::PRIVMSG(ref channel, ref user_message) = message.command {
             match self.command.parse(user_message) {
                 Some("") => {
-                    client.send_privmsg(channel, formatted(USAGE))?;
+                    client.send_privmsg(
+                        channel,
+                        Reply::tagged("Urban Dictionary")
+                            .text("Usage: .ud")
+                            .value(" <query>"),
+                    )?;
                 }
                 Some(query) => match self.definitions(query).await {
                     Ok(definitions) => {
                         if let Some(definition) = definitions.list.first() {
-                            let s = formatted(&format!("{definition}"));
-                            client.send_privmsg(channel, s)?;
+                            client.send_privmsg(channel, definition.reply())?;
                         } else {
-                            client.send_privmsg(channel, formatted("No results"))?;
+                            client.send_privmsg(
+                                channel,
+                                Reply::tagged("Urban Dictionary").text("No results"),
+                            )?;
                         }
                     }
                     Err(err) => {
-                        client.send_privmsg(channel, formatted(&format!("Error: {err}")))?;
+                        client.send_privmsg(
+                            channel,
+                            Reply::tagged("Urban Dictionary").text(format!("Error: {err}")),
+                        )?;
                     }
                 },
                 None => {}
@@ -102,15 +110,13 @@ impl Plugin<Context> for UrbanDictionary {
     }
 }
 
-impl Display for Definition {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let word = &self.word;
-        let definition = presentable(&self.definition);
-        let example = presentable(&self.example);
-
-        write!(fmt, "Term:\x0f {word}\x0310")?;
-        write!(fmt, " Definition:\x0f {definition}\x0310")?;
-        write!(fmt, " Example:\x0f {example}")
+impl Definition {
+    /// Returns a reply that presents the definition.
+    fn reply(&self) -> Reply {
+        Reply::tagged("Urban Dictionary")
+            .field("Term", &self.word)
+            .field("Definition", presentable(&self.definition))
+            .field("Example", presentable(&self.example))
     }
 }
 
@@ -120,10 +126,6 @@ fn presentable(s: &str) -> String {
     s.trim().replace('\r', "").replace('\n', " ")
 }
 
-fn formatted(s: &str) -> String {
-    format!("\x0310>\x03\x02 Urban Dictionary:\x02\x0310 {s}")
-}
-
 impl UrbanDictionary {
     pub fn new() -> Self {
         let client = http::build_client();
@@ -147,7 +149,7 @@ impl UrbanDictionary {
             .client
             .get(format!("{BASE_URL}/v0/define"))
             .query(&params);
-        let response = request.send().await.map_err(Error::Request)?;
+        let response = request.send_shared().await.map_err(Error::Request)?;
 
         match response.error_for_status() {
             Ok(response) => {
@@ -0,0 +1,296 @@
+//! URL history and repost detection.
+//!
+//! Every URL posted in a channel is recorded along with who posted it and when. URLs are
+//! normalized before they're compared, so that e.g. `youtu.be/<id>` and
+//! `youtube.com/watch?v=<id>&si=<tracking id>` are considered the same link. When a URL is
+//! posted again, the channel is told who posted it first.
+//!
+//! The `.urls [search]` command lists recently posted URLs.
+
+use irc::client::prelude::ChannelExt;
+use time::OffsetDateTime;
+use tracing::warn;
+use url::Url;
+
+use crate::{
+    command,
+    database::url_history,
+    plugin::{self, prelude::*},
+    utils::format_age,
+};
+
+/// The number of URLs listed by the `.urls` command.
+const NUM_SEARCH_RESULTS: i64 = 5;
+
+/// Query parameters that are used for tracking and don't change which resource a URL points to.
+const TRACKING_PARAMS: &[&str] = &[
+    "_hsenc", "_hsmi", "dclid", "fbclid", "gbraid", "gclid", "igsh", "igshid", "mc_cid", "mc_eid",
+    "mkt_tok", "msclkid", "ref_src", "si", "wbraid", "yclid",
+];
+
+/// URL history plugin.
+pub struct UrlHistory {
+    /// The `.urls` command trigger.
+    command: Prefix,
+}
+
+/// Errors that can occur when handling URL history.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("irc error: {0}")]
+    Irc(#[from] irc::error::Error),
+}
+
+#[async_trait]
+impl Plugin<Context> for UrlHistory {
+    fn new(_ctx: &Context) -> Result<UrlHistory, ZetaError> {
+        let command = Prefix::new(".urls");
+
+        Ok(UrlHistory { command })
+    }
+
+    fn metadata() -> Metadata {
+        Metadata {
+            name: "url_history".into(),
+            authors: vec!["Mikkel Kroman <mk@maero.dk>".into()],
+        }
+    }
+
+    async fn handle_message(
+        &self,
+        ctx: &Context,
+        client: &Client,
+        message: &Message,
+    ) -> Result<(), ZetaError> {
+        if let Command::PRIVMSG(ref channel, ref user_message) = message.command
+            && channel.is_channel_name()
+            && let Some(nick) = message.source_nickname()
+        {
+            let result = if let Some(query) = self.command.parse(user_message) {
+                self.search(ctx, client, channel, query.trim()).await
+            } else if let Some(urls) = plugin::extract_urls(user_message) {
+                command::invoked("url_history");
+                self.record(ctx, client, channel, nick, &urls).await
+            } else {
+                Ok(())
+            };
+
+            if let Err(err) = result {
+                warn!(error = %err, "could not handle url history");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl UrlHistory {
+    /// Records the `urls` posted by `nick` and announces any reposts.
+    async fn record(
+        &self,
+        ctx: &Context,
+        client: &Client,
+        channel: &str,
+        nick: &str,
+        urls: &[Url],
+    ) -> Result<(), Error> {
+        let casemapping = ctx.casemapping();
+        let channel_key = casemapping.fold(channel);
+
+        for url in urls {
+            let normalized = normalize_url(url);
+            let first_post =
+                url_history::first_post(&ctx.db, &channel_key, normalized.as_str()).await?;
+
+            url_history::record(
+                &ctx.db,
+                &channel_key,
+                normalized.as_str(),
+                url.as_str(),
+                nick,
+            )
+            .await?;
+
+            if let Some(post) = first_post
+                && casemapping.fold(&post.poster) != casemapping.fold(nick)
+            {
+                let age = format_age(OffsetDateTime::now_utc() - post.posted_at);
+
+                client.send_privmsg(
+                    channel,
+                    Reply::new()
+                        .text("Old! First posted by ")
+                        .value(&post.poster)
+                        .text(format!(" {age}")),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lists the most recent URLs in `channel` that match `query`.
+    async fn search(
+        &self,
+        ctx: &Context,
+        client: &Client,
+        channel: &str,
+        query: &str,
+    ) -> Result<(), Error> {
+        let channel_key = ctx.casemapping().fold(channel);
+        let query = (!query.is_empty()).then_some(query);
+        let posts = url_history::search(&ctx.db, &channel_key, query, NUM_SEARCH_RESULTS).await?;
+
+        if posts.is_empty() {
+            client.send_privmsg(channel, Reply::new().text("No matching URLs"))?;
+
+            return Ok(());
+        }
+
+        let now = OffsetDateTime::now_utc();
+
+        for post in posts {
+            let reply = Reply::new()
+                .link(&post.original_url)
+                .text(" (posted by ")
+                .value(&post.poster)
+                .text(format!(" {})", format_age(now - post.posted_at)));
+
+            client.send_privmsg(channel, reply)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns a normalized form of `url` used for detecting reposts.
+///
+/// YouTube video links are rewritten to their canonical `watch` form, fragments and known
+/// tracking parameters are removed, the scheme is upgraded to `https`, `www.` is stripped from
+/// the host and the remaining query parameters are sorted.
+pub fn normalize_url(url: &Url) -> Url {
+    if let Some(video_id) = youtube_video_id(url)
+        && let Ok(canonical) = Url::parse(&format!("https://www.youtube.com/watch?v={video_id}"))
+    {
+        return canonical;
+    }
+
+    let mut normalized = url.clone();
+
+    normalized.set_fragment(None);
+    let _ = normalized.set_scheme("https");
+
+    if let Some(host) = url.host_str()
+        && let Some(stripped) = host.strip_prefix("www.")
+    {
+        let _ = normalized.set_host(Some(stripped));
+    }
+
+    let mut params: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| !is_tracking_param(key))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+
+    if params.is_empty() {
+        normalized.set_query(None);
+    } else {
+        params.sort();
+        normalized.query_pairs_mut().clear().extend_pairs(params);
+    }
+
+    if normalized.path().len() > 1 && normalized.path().ends_with('/') {
+        let path = normalized.path().trim_end_matches('/').to_string();
+
+        normalized.set_path(&path);
+    }
+
+    normalized
+}
+
+/// Returns whether the query parameter `key` is only used for tracking.
+fn is_tracking_param(key: &str) -> bool {
+    key.starts_with("utm_") || TRACKING_PARAMS.contains(&key)
+}
+
+/// Returns the video id if `url` links to a YouTube video.
+fn youtube_video_id(url: &Url) -> Option<String> {
+    let host = url.host_str()?;
+    let mut segments = url.path_segments()?;
+
+    match host {
+        "youtu.be" => segments
+            .next()
+            .filter(|id| !id.is_empty())
+            .map(ToString::to_string),
+        "youtube.com" | "www.youtube.com" | "m.youtube.com" | "music.youtube.com" => {
+            match segments.next()? {
+                "watch" => url
+                    .query_pairs()
+                    .find(|(key, _)| key == "v")
+                    .map(|(_, value)| value.into_owned()),
+                "shorts" | "live" | "embed" => segments
+                    .next()
+                    .filter(|id| !id.is_empty())
+                    .map(ToString::to_string),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalized(s: &str) -> String {
+        normalize_url(&Url::parse(s).unwrap()).to_string()
+    }
+
+    #[test]
+    fn it_should_canonicalize_youtube_urls() {
+        let canonical = "https://www.youtube.com/watch?v=dQw4w9WgXcQ";
+
+        for url in [
+            "https://youtu.be/dQw4w9WgXcQ",
+            "https://youtu.be/dQw4w9WgXcQ?si=abcdef",
+            "http://youtube.com/watch?v=dQw4w9WgXcQ&feature=share",
+            "https://m.youtube.com/watch?v=dQw4w9WgXcQ&t=42",
+            "https://www.youtube.com/shorts/dQw4w9WgXcQ",
+        ] {
+            assert_eq!(normalized(url), canonical, "{url}");
+        }
+    }
+
+    #[test]
+    fn it_should_strip_tracking_params() {
+        assert_eq!(
+            normalized("https://example.com/article?utm_source=x&id=5&fbclid=abc"),
+            "https://example.com/article?id=5"
+        );
+        assert_eq!(
+            normalized("https://open.spotify.com/track/123?si=abc"),
+            "https://open.spotify.com/track/123"
+        );
+    }
+
+    #[test]
+    fn it_should_normalize_host_scheme_and_path() {
+        assert_eq!(
+            normalized("http://WWW.Example.com/a/b/#section"),
+            "https://example.com/a/b"
+        );
+        assert_eq!(normalized("https://example.com/"), "https://example.com/");
+    }
+
+    #[test]
+    fn it_should_sort_query_params() {
+        assert_eq!(
+            normalized("https://example.com/?b=2&a=1"),
+            "https://example.com/?a=1&b=2"
+        );
+    }
+}
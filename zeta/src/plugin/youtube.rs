@@ -11,10 +11,8 @@ use tokio::sync::RwLock;
 use tracing::{debug, error};
 use url::Url;
 
-use crate::{
-    http,
-    plugin::{self, prelude::*},
-};
+use crate::http::RequestBuilderExt;
+use crate::{http, plugin::prelude::*};
 
 /// YouTube Data API v3 base endpoint URL.
 pub const BASE_URL: &str = "https://www.googleapis.com/youtube/v3";
@@ -209,30 +207,57 @@ impl Plugin<Context> for YouTube {
         client: &Client,
         message: &Message,
     ) -> Result<(), ZetaError> {
-        if let Command::PRIVMSG(ref channel, ref user_message) = message.command {
-            if let Some(urls) = plugin::extract_urls(user_message) {
-                self.process_urls(urls, channel, client).await?;
-            } else if let Some(args) = self.command.parse(user_message) {
-                match self.search(args).await {
-                    Ok(results) => {
-                        if let Some(result) = results.first() {
-                            let id = result.id.video_id.as_ref().unwrap();
-                            let title = htmlize::unescape(&result.snippet.title);
-
-                            client.send_privmsg(channel, format!("\x0310>\x03\x02 YouTube:\x02\x0310 {title} - https://www.youtube.com/watch?v={id}"))?;
-                        } else {
-                            client.send_privmsg(channel, "\x0310> No results")?;
-                        }
-                    }
-                    Err(err) => {
-                        client.send_privmsg(channel, format!("\x0310> Error: {err}"))?;
+        if let Command::PRIVMSG(ref channel, ref user_message) = message.command
+            && let Some(args) = self.command.parse(user_message)
+        {
+            match self.search(args).await {
+                Ok(results) => {
+                    if let Some(result) = results.first() {
+                        let id = result.id.video_id.as_ref().unwrap();
+                        let title = htmlize::unescape(&result.snippet.title);
+
+                        let reply = Reply::tagged("YouTube")
+                            .text(format!("{title} - "))
+                            .link(format!("https://www.youtube.com/watch?v={id}"));
+
+                        client.send_privmsg(channel, reply)?;
+                    } else {
+                        client
+                            .send_privmsg(channel, Reply::tagged("YouTube").text("No results"))?;
                     }
                 }
+                Err(err) => {
+                    client.send_privmsg(
+                        channel,
+                        Reply::tagged("YouTube").text(format!("Error: {err}")),
+                    )?;
+                }
             }
         }
 
         Ok(())
     }
+
+    fn claims_url(&self, url: &Url) -> bool {
+        matches!(
+            YouTube::parse_youtube_url(url),
+            Some(UrlKind::Video(_) | UrlKind::Short(_))
+        )
+    }
+
+    async fn handle_url(
+        &self,
+        _ctx: &Context,
+        client: &Client,
+        message: &Message,
+        url: &Url,
+    ) -> Result<(), ZetaError> {
+        if let Command::PRIVMSG(ref channel, _) = message.command {
+            self.process_url(url, channel, client).await?;
+        }
+
+        Ok(())
+    }
 }
 
 impl YouTube {
@@ -258,44 +283,49 @@ impl YouTube {
         }
     }
 
-    /// Processes URLs found in a message
-    async fn process_urls(
+    /// Processes a video URL found in a message
+    async fn process_url(
         &self,
-        urls: Vec<Url>,
+        url: &Url,
         channel: &str,
         client: &Client,
     ) -> Result<(), ZetaError> {
-        for ref url in urls {
-            if let Some(UrlKind::Video(video_id) | UrlKind::Short(video_id)) =
-                YouTube::parse_youtube_url(url)
-            {
-                match self.get_video(&video_id).await {
-                    Ok(video) => {
-                        let snippet = video.snippet.as_ref();
-                        let statistics = video.statistics.as_ref();
-                        let title = snippet.map_or_else(|| "‽".to_string(), |s| s.title.clone());
-                        let category_id = snippet.map_or(String::new(), |s| s.category_id.clone());
-                        let categories = self.cached_video_categories().await.unwrap();
-                        // TODO: use indefinite form: https://crates.io/crates/indefinite
-                        let category = categories.get(&category_id).map_or_else(
-                            || "unknown category".to_string(),
-                            |s| s.snippet.title.clone(),
-                        );
-                        let channel_name = snippet.map_or_else(
-                            || "unknown channel".to_string(),
-                            |s| s.channel_title.clone(),
-                        );
-                        let view_count = statistics
-                            .and_then(|s| str::parse::<u64>(&s.view_count).ok())
-                            .unwrap_or(0);
-                        let view_count_formatted = view_count.to_formatted_string(&Locale::en);
-
-                        client
-                        .send_privmsg(channel, format!("\x0310> “\x0f{title}\x0310” is a\x0f {category}\x0310 video by\x0f {channel_name}\x0310 with\x0f {view_count_formatted}\x0310 views"))?;
-                    }
-                    Err(e) => {
-                        client.send_privmsg(channel, format!("Error: {e}"))?;
-                    }
+        if let Some(UrlKind::Video(video_id) | UrlKind::Short(video_id)) =
+            YouTube::parse_youtube_url(url)
+        {
+            match self.get_video(&video_id).await {
+                Ok(video) => {
+                    let snippet = video.snippet.as_ref();
+                    let statistics = video.statistics.as_ref();
+                    let title = snippet.map_or_else(|| "‽".to_string(), |s| s.title.clone());
+                    let category_id = snippet.map_or(String::new(), |s| s.category_id.clone());
+                    let categories = self.cached_video_categories().await.unwrap();
+                    // TODO: use indefinite form: https://crates.io/crates/indefinite
+                    let category = categories.get(&category_id).map_or_else(
+                        || "unknown category".to_string(),
+                        |s| s.snippet.title.clone(),
+                    );
+                    let channel_name = snippet.map_or_else(
+                        || "unknown channel".to_string(),
+                        |s| s.channel_title.clone(),
+                    );
+                    let view_count = statistics
+                        .and_then(|s| str::parse::<u64>(&s.view_count).ok())
+                        .unwrap_or(0);
+                    let reply = Reply::new()
+                        .quoted(title)
+                        .text(" is a ")
+                        .value(category)
+                        .text(" video by ")
+                        .value(channel_name)
+                        .text(" with ")
+                        .value(view_count.to_formatted_string(&Locale::en))
+                        .text(" views");
+
+                    client.send_privmsg(channel, reply)?;
+                }
+                Err(e) => {
+                    client.send_privmsg(channel, Reply::new().text(format!("Error: {e}")))?;
                 }
             }
         }
@@ -317,7 +347,7 @@ impl YouTube {
             .get(format!("{BASE_URL}/videoCategories"))
             .query(&params);
         let response = request
-            .send()
+            .send_shared()
             .await
             .map_err(|_| Error::InvalidResponse)?
             .error_for_status()?;
@@ -379,7 +409,7 @@ impl YouTube {
         debug!(?params, "searching for videos");
 
         let request = self.client.get(format!("{BASE_URL}/search")).query(&params);
-        let response = request.send().await.map_err(Error::Request)?;
+        let response = request.send_shared().await.map_err(Error::Request)?;
 
         match response.error_for_status() {
             Ok(response) => {
@@ -412,7 +442,7 @@ impl YouTube {
         ];
         let request = self.client.get(format!("{BASE_URL}/videos")).query(&params);
         let response = request
-            .send()
+            .send_shared()
             .await
             .map_err(|_| Error::InvalidResponse)?
             .error_for_status()?;
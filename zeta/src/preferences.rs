@@ -0,0 +1,326 @@
+//! Per-user preferences.
+//!
+//! Users save preferences such as their location and timezone with the `.set` command, and
+//! plugins read them to fill in sensible defaults. Preferences are keyed by the services account
+//! of the user when the server tells us about it (through the IRCv3 `account-tag` capability),
+//! falling back to their nickname.
+//!
+//! Preferences are only persisted when the `database` feature is enabled - otherwise every user
+//! has the default preferences.
+
+use std::fmt;
+use std::str::FromStr;
+
+use irc::proto::Message;
+use irc::proto::message::Tag;
+use jiff::tz::TimeZone;
+
+use crate::context::Context;
+use crate::isupport::CaseMapping;
+
+/// The maximum length of a saved location.
+const MAX_LOCATION_LEN: usize = 100;
+
+/// Errors that can occur when parsing preferences.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The key is not a known preference.
+    #[error("unknown preference {0:?}, expected one of: location, timezone, units, language")]
+    UnknownKey(String),
+    /// The location is empty or too long.
+    #[error("location must be between 1 and {MAX_LOCATION_LEN} characters")]
+    InvalidLocation,
+    /// The timezone isn't in the timezone database.
+    #[error("unknown timezone {0:?}, expected e.g. Europe/Copenhagen")]
+    InvalidTimezone(String),
+    /// The units aren't a known system of units.
+    #[error("unknown units {0:?}, expected metric or imperial")]
+    InvalidUnits(String),
+    /// The language isn't a language tag.
+    #[error("invalid language {0:?}, expected a language code such as en or da")]
+    InvalidLanguage(String),
+}
+
+/// A preference key.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Key {
+    /// The default location, e.g. for weather lookups.
+    Location,
+    /// The IANA timezone, e.g. `Europe/Copenhagen`.
+    Timezone,
+    /// The preferred system of units.
+    Units,
+    /// The preferred language, as a language tag such as `en` or `da`.
+    Language,
+}
+
+/// A system of units.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Units {
+    /// Metric units, e.g. °C and m/s.
+    #[default]
+    Metric,
+    /// Imperial units, e.g. °F and mph.
+    Imperial,
+}
+
+/// The owner of a set of preferences.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Owner {
+    /// A services account.
+    Account(String),
+    /// A nickname, for users that aren't logged in to services.
+    Nick(String),
+}
+
+/// The preferences of a single user.
+#[derive(Clone, Debug, Default)]
+pub struct UserPreferences {
+    /// The default location.
+    pub location: Option<String>,
+    /// The timezone.
+    pub timezone: Option<TimeZone>,
+    /// The system of units.
+    pub units: Option<Units>,
+    /// The language tag, which selects the locale of plugin output.
+    pub language: Option<String>,
+}
+
+impl Key {
+    /// All preference keys.
+    pub const ALL: [Key; 4] = [Key::Location, Key::Timezone, Key::Units, Key::Language];
+
+    /// Returns the name of the key as used in commands and storage.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Key::Location => "location",
+            Key::Timezone => "timezone",
+            Key::Units => "units",
+            Key::Language => "language",
+        }
+    }
+
+    /// Validates `value` for this key and returns its normalized form.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `value` isn't valid for this key.
+    pub fn normalize(self, value: &str) -> Result<String, Error> {
+        let value = value.trim();
+
+        match self {
+            Key::Location => {
+                if value.is_empty() || value.chars().count() > MAX_LOCATION_LEN {
+                    return Err(Error::InvalidLocation);
+                }
+
+                Ok(value.to_string())
+            }
+            Key::Timezone => TimeZone::get(value)
+                .ok()
+                .and_then(|tz| tz.iana_name().map(ToString::to_string))
+                .ok_or_else(|| Error::InvalidTimezone(value.to_string())),
+            Key::Units => value.parse::<Units>().map(|units| units.to_string()),
+            Key::Language => {
+                let is_valid = value.split(['-', '_']).enumerate().all(|(i, part)| {
+                    let len_ok = if i == 0 {
+                        (2..=3).contains(&part.len())
+                    } else {
+                        (2..=8).contains(&part.len())
+                    };
+
+                    len_ok && part.chars().all(|c| c.is_ascii_alphanumeric())
+                });
+
+                if is_valid {
+                    Ok(value.replace('_', "-").to_ascii_lowercase())
+                } else {
+                    Err(Error::InvalidLanguage(value.to_string()))
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Key, Error> {
+        Key::ALL
+            .into_iter()
+            .find(|key| key.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| Error::UnknownKey(s.to_string()))
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Units {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Units, Error> {
+        match s.to_ascii_lowercase().as_str() {
+            "metric" | "si" => Ok(Units::Metric),
+            "imperial" | "us" => Ok(Units::Imperial),
+            _ => Err(Error::InvalidUnits(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Units {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Units::Metric => "metric",
+            Units::Imperial => "imperial",
+        })
+    }
+}
+
+impl Owner {
+    /// Returns the owner of the preferences of the user who sent `message`.
+    ///
+    /// The services account from the IRCv3 `account` tag is preferred over the nickname. Both are
+    /// folded using `casemapping`.
+    #[must_use]
+    pub fn from_message(message: &Message, casemapping: CaseMapping) -> Option<Owner> {
+        let account = message.tags.as_ref().and_then(|tags| {
+            tags.iter().find_map(|Tag(key, value)| {
+                (key == "account")
+                    .then_some(value.as_deref())
+                    .flatten()
+                    .filter(|account| !account.is_empty() && *account != "*")
+            })
+        });
+
+        match account {
+            Some(account) => Some(Owner::Account(casemapping.fold(account))),
+            None => message
+                .source_nickname()
+                .map(|nick| Owner::Nick(casemapping.fold(nick))),
+        }
+    }
+
+    /// Returns the kind of owner as used in storage.
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Owner::Account(_) => "account",
+            Owner::Nick(_) => "nick",
+        }
+    }
+
+    /// Returns the normalized account name or nickname.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        match self {
+            Owner::Account(name) | Owner::Nick(name) => name,
+        }
+    }
+}
+
+impl UserPreferences {
+    /// Builds preferences from stored `(key, value)` pairs, ignoring unknown keys and values that
+    /// are no longer valid.
+    #[must_use]
+    pub fn from_pairs<K: AsRef<str>, V: AsRef<str>>(pairs: &[(K, V)]) -> UserPreferences {
+        let mut preferences = UserPreferences::default();
+
+        for (key, value) in pairs {
+            let value = value.as_ref();
+
+            match key.as_ref().parse() {
+                Ok(Key::Location) => preferences.location = Some(value.to_string()),
+                Ok(Key::Timezone) => preferences.timezone = TimeZone::get(value).ok(),
+                Ok(Key::Units) => preferences.units = value.parse().ok(),
+                Ok(Key::Language) => preferences.language = Some(value.to_string()),
+                Err(_) => {}
+            }
+        }
+
+        preferences
+    }
+}
+
+/// Returns the saved preferences of the user who sent `message`.
+///
+/// Lookup failures are logged and result in the default preferences, so plugins can always fall
+/// back to their usual behavior.
+pub async fn load(ctx: &Context, message: &Message) -> UserPreferences {
+    #[cfg(feature = "database")]
+    if let Some(owner) = Owner::from_message(message, ctx.casemapping()) {
+        match crate::database::preferences::all(&ctx.db, owner.kind(), owner.name()).await {
+            Ok(pairs) => return UserPreferences::from_pairs(&pairs),
+            Err(err) => tracing::warn!(error = %err, "could not load user preferences"),
+        }
+    }
+
+    #[cfg(not(feature = "database"))]
+    let _ = (ctx, message);
+
+    UserPreferences::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_parse_keys() {
+        assert_eq!("location".parse::<Key>().unwrap(), Key::Location);
+        assert_eq!("TimeZone".parse::<Key>().unwrap(), Key::Timezone);
+        assert!("colour".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn it_should_normalize_values() {
+        assert_eq!(
+            Key::Location.normalize("  Copenhagen ").unwrap(),
+            "Copenhagen"
+        );
+        assert!(Key::Location.normalize("").is_err());
+        assert_eq!(Key::Units.normalize("Imperial").unwrap(), "imperial");
+        assert!(Key::Units.normalize("furlongs").is_err());
+        assert_eq!(Key::Language.normalize("da_DK").unwrap(), "da-dk");
+        assert!(Key::Language.normalize("danish!").is_err());
+        assert!(Key::Timezone.normalize("Mars/Olympus_Mons").is_err());
+    }
+
+    #[test]
+    fn it_should_prefer_accounts_over_nicks() {
+        let message: Message = "@account=MK :mk|away!mk@maero.dk PRIVMSG #zeta :hi\r\n"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            Owner::from_message(&message, CaseMapping::Rfc1459),
+            Some(Owner::Account("mk".to_string()))
+        );
+
+        let message: Message = ":MK[away]!mk@maero.dk PRIVMSG #zeta :hi\r\n"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            Owner::from_message(&message, CaseMapping::Rfc1459),
+            Some(Owner::Nick("mk{away}".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_should_build_preferences_from_pairs() {
+        let preferences = UserPreferences::from_pairs(&[
+            ("location", "Copenhagen"),
+            ("units", "imperial"),
+            ("timezone", "Mars/Olympus_Mons"),
+            ("unknown", "value"),
+        ]);
+
+        assert_eq!(preferences.location.as_deref(), Some("Copenhagen"));
+        assert_eq!(preferences.units, Some(Units::Imperial));
+        assert!(preferences.timezone.is_none());
+        assert!(preferences.language.is_none());
+    }
+}
@@ -0,0 +1,245 @@
+//! Rejoining channels, and joining channels we're invited to.
+//!
+//! Channels we're kicked from are rejoined after a delay, and joins that fail because a channel is
+//! full, invite-only, we're banned or the key is wrong are retried with exponential backoff.
+//!
+//! If it's enabled, channels that admins invite us to are joined as well. With the `database`
+//! feature, those channels are remembered and joined again after reconnecting, until we part them.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use irc::proto::{Command, Message, Response};
+use tracing::{debug, info, warn};
+use zeta_plugin::Client;
+
+use crate::config::{IrcConfig, RejoinConfig};
+use crate::context::Context;
+#[cfg(feature = "database")]
+use crate::database::channels;
+use crate::permissions;
+
+/// Rejoins channels and handles invites.
+pub struct Rejoiner {
+    /// The IRC client configuration.
+    config: IrcConfig,
+    /// The number of failed joins in a row, by folded channel name.
+    failures: Mutex<HashMap<String, u32>>,
+}
+
+impl Rejoiner {
+    /// Creates a rejoiner for the given IRC configuration.
+    #[must_use]
+    pub fn new(config: IrcConfig) -> Rejoiner {
+        Rejoiner {
+            config,
+            failures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Acts on `message` if it's a kick, a failed join or an invite.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a command could not be sent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is poisoned.
+    pub async fn handle(
+        &self,
+        client: &Client,
+        ctx: &Arc<Context>,
+        message: &Message,
+    ) -> Result<(), irc::error::Error> {
+        let casemapping = ctx.casemapping();
+        let nickname = casemapping.fold(&ctx.nickname());
+        let is_self = |nick: &str| casemapping.fold(nick) == nickname;
+        let source = message.source_nickname().unwrap_or_default();
+
+        match message.command {
+            Command::Response(Response::RPL_WELCOME, _) => self.failures.lock().unwrap().clear(),
+            #[cfg(feature = "database")]
+            Command::Response(Response::RPL_ENDOFMOTD | Response::ERR_NOMOTD, _)
+                if self.config.invites.enabled =>
+            {
+                self.join_invited(client, ctx).await?;
+            }
+            Command::KICK(ref channel, ref target, _)
+                if is_self(target) && self.config.rejoin.on_kick =>
+            {
+                let delay = self.config.rejoin.kick_delay;
+
+                info!(%channel, by = %source, ?delay, "kicked from channel, rejoining");
+                self.schedule(client, ctx, channel, delay);
+            }
+            Command::Response(
+                Response::ERR_CHANNELISFULL
+                | Response::ERR_INVITEONLYCHAN
+                | Response::ERR_BANNEDFROMCHAN
+                | Response::ERR_BADCHANNELKEY,
+                ref args,
+            ) => {
+                let Some(channel) = args.get(1) else {
+                    return Ok(());
+                };
+                let failures = {
+                    let mut failures = self.failures.lock().unwrap();
+                    let count = failures.entry(casemapping.fold(channel)).or_default();
+
+                    *count += 1;
+                    *count
+                };
+
+                match retry_delay(&self.config.rejoin, failures) {
+                    Some(delay) => {
+                        debug!(%channel, failures, ?delay, "could not join channel, retrying");
+                        self.schedule(client, ctx, channel, delay);
+                    }
+                    None => warn!(%channel, failures, "could not join channel, giving up"),
+                }
+            }
+            Command::JOIN(ref channels, ..) if is_self(source) => {
+                let mut failures = self.failures.lock().unwrap();
+
+                for channel in channels.split(',') {
+                    failures.remove(&casemapping.fold(channel));
+                }
+            }
+            #[cfg(feature = "database")]
+            Command::PART(ref names, _) if is_self(source) => {
+                for channel in names.split(',') {
+                    if let Err(err) = channels::remove(&ctx.db, &casemapping.fold(channel)).await {
+                        warn!(error = %err, %channel, "could not forget invited channel");
+                    }
+                }
+            }
+            Command::INVITE(ref target, ref channel)
+                if is_self(target) && self.config.invites.enabled =>
+            {
+                if !permissions::is_admin(&ctx.config.permissions, message) {
+                    debug!(%channel, by = %source, "ignoring invite from non-admin");
+
+                    return Ok(());
+                }
+
+                info!(%channel, by = %source, "invited to channel, joining");
+                client.send_join(channel)?;
+
+                #[cfg(feature = "database")]
+                if let Err(err) =
+                    channels::add(&ctx.db, &casemapping.fold(channel), channel, source).await
+                {
+                    warn!(error = %err, %channel, "could not remember invited channel");
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Joins `channel` after `delay`, unless we've joined it in the meantime.
+    fn schedule(&self, client: &Client, ctx: &Arc<Context>, channel: &str, delay: Duration) {
+        let sender = client.sender();
+        let ctx = Arc::clone(ctx);
+        let channel = channel.to_string();
+        let key = self.key(&ctx, &channel);
+
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+
+            if ctx.channel(&channel).is_some() {
+                return;
+            }
+
+            if let Err(err) = sender.send(Command::JOIN(channel.clone(), key, None)) {
+                warn!(error = %err, %channel, "could not rejoin channel");
+            }
+        });
+    }
+
+    /// Joins the channels we were invited to before, that we're not already in.
+    #[cfg(feature = "database")]
+    async fn join_invited(&self, client: &Client, ctx: &Context) -> Result<(), irc::error::Error> {
+        let invited = match channels::all(&ctx.db).await {
+            Ok(invited) => invited,
+            Err(err) => {
+                warn!(error = %err, "could not load invited channels");
+
+                return Ok(());
+            }
+        };
+
+        for channel in invited {
+            if ctx.channel(&channel).is_none() {
+                debug!(%channel, "joining invited channel");
+                client.send_join(&channel)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the configured key of `channel`, if it has one.
+    fn key(&self, ctx: &Context, channel: &str) -> Option<String> {
+        let casemapping = ctx.casemapping();
+        let channel = casemapping.fold(channel);
+
+        self.config
+            .channels
+            .iter()
+            .find(|config| casemapping.fold(&config.name) == channel)?
+            .key
+            .clone()
+    }
+}
+
+/// Returns how long to wait before retrying a join that has failed `failures` times in a row, or
+/// `None` if we should give up.
+fn retry_delay(config: &RejoinConfig, failures: u32) -> Option<Duration> {
+    if failures > config.max_retries {
+        return None;
+    }
+
+    let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+
+    Some(
+        config
+            .retry_delay
+            .saturating_mul(factor)
+            .min(config.max_retry_delay),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_back_off_exponentially() {
+        let config = RejoinConfig {
+            retry_delay: Duration::from_secs(30),
+            max_retry_delay: Duration::from_secs(300),
+            max_retries: 5,
+            ..RejoinConfig::default()
+        };
+
+        assert_eq!(retry_delay(&config, 1), Some(Duration::from_secs(30)));
+        assert_eq!(retry_delay(&config, 2), Some(Duration::from_secs(60)));
+        assert_eq!(retry_delay(&config, 3), Some(Duration::from_secs(120)));
+        assert_eq!(retry_delay(&config, 5), Some(Duration::from_secs(300)));
+        assert_eq!(retry_delay(&config, 6), None);
+    }
+
+    #[test]
+    fn it_should_not_retry_when_disabled() {
+        let config = RejoinConfig {
+            max_retries: 0,
+            ..RejoinConfig::default()
+        };
+
+        assert_eq!(retry_delay(&config, 1), None);
+    }
+}
@@ -0,0 +1,220 @@
+//! Optional HTTP server for metrics and health probes.
+//!
+//! Serves the following endpoints:
+//!
+//! * `/metrics` - process and bot [metrics](crate::metrics) in the Prometheus text exposition
+//!   format.
+//! * `/healthz` - liveness probe that succeeds as long as the process is running.
+//! * `/readyz` - readiness probe that succeeds when we're registered on IRC, have joined all
+//!   configured channels and the database is reachable. The response body is a JSON document
+//!   with the result of each check and the plugins that failed to initialize.
+
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode, header};
+use hyper_util::rt::TokioIo;
+use serde::Serialize;
+use tokio::net::TcpListener;
+use tracing::{debug, warn};
+
+use crate::Registry;
+use crate::context::Context;
+use crate::metrics::{self, Snapshot};
+
+/// The content type of the Prometheus text exposition format.
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// The maximum duration of the database readiness check.
+#[cfg(feature = "database")]
+const DATABASE_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// State shared with the HTTP server.
+pub struct State {
+    /// The shared plugin context.
+    context: Arc<Context>,
+    /// Plugins that failed to initialize, with their errors.
+    failed_plugins: Vec<FailedPlugin>,
+}
+
+/// A plugin that failed to initialize.
+#[derive(Clone, Debug, Serialize)]
+pub struct FailedPlugin {
+    /// The name of the plugin.
+    pub name: String,
+    /// The initialization error.
+    pub error: String,
+}
+
+/// The result of the readiness checks.
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    /// Whether all checks passed.
+    pub ready: bool,
+    /// Whether we're registered with the IRC server.
+    pub irc_registered: bool,
+    /// Configured channels we're not in.
+    pub missing_channels: Vec<String>,
+    /// Whether the database is reachable, if the database is enabled.
+    pub database: Option<bool>,
+    /// Plugins that failed to initialize. These don't affect readiness.
+    pub failed_plugins: Vec<FailedPlugin>,
+}
+
+impl State {
+    /// Creates the server state from the shared context and the plugin registry.
+    #[must_use]
+    pub fn new(context: Arc<Context>, registry: &Registry) -> State {
+        let failed_plugins = registry
+            .failed
+            .iter()
+            .map(|(name, err)| FailedPlugin {
+                name: name.clone(),
+                error: err.to_string(),
+            })
+            .collect();
+
+        State {
+            context,
+            failed_plugins,
+        }
+    }
+
+    /// Runs the readiness checks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock of the channel state is poisoned.
+    pub async fn readiness(&self) -> Readiness {
+        let irc_registered = metrics::global().irc_registered();
+        let missing_channels = self.missing_channels();
+        let database = self.check_database().await;
+        let ready = irc_registered && missing_channels.is_empty() && database != Some(false);
+
+        Readiness {
+            ready,
+            irc_registered,
+            missing_channels,
+            database,
+            failed_plugins: self.failed_plugins.clone(),
+        }
+    }
+
+    /// Returns the configured channels we're not in.
+    fn missing_channels(&self) -> Vec<String> {
+        self.context
+            .config
+            .irc
+            .channels
+            .iter()
+            .filter(|channel| self.context.channel(&channel.name).is_none())
+            .map(|channel| channel.name.clone())
+            .collect()
+    }
+
+    /// Returns whether the database is reachable, or `None` if the database is disabled.
+    #[allow(clippy::unused_async)]
+    async fn check_database(&self) -> Option<bool> {
+        #[cfg(feature = "database")]
+        {
+            let query = sqlx::query("SELECT 1").execute(&self.context.db);
+
+            match tokio::time::timeout(DATABASE_CHECK_TIMEOUT, query).await {
+                Ok(Ok(_)) => Some(true),
+                Ok(Err(err)) => {
+                    warn!(error = %err, "database readiness check failed");
+                    Some(false)
+                }
+                Err(_) => {
+                    warn!("database readiness check timed out");
+                    Some(false)
+                }
+            }
+        }
+
+        #[cfg(not(feature = "database"))]
+        None
+    }
+}
+
+/// Accepts and serves HTTP connections on `listener` until the task is aborted.
+pub async fn serve(listener: TcpListener, state: Arc<State>) {
+    loop {
+        let (stream, remote_addr) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(err) => {
+                warn!(error = %err, "could not accept http connection");
+                continue;
+            }
+        };
+
+        let state = Arc::clone(&state);
+
+        tokio::spawn(async move {
+            let service = service_fn(|request| handle(request, Arc::clone(&state)));
+
+            if let Err(err) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                debug!(%remote_addr, error = %err, "http connection error");
+            }
+        });
+    }
+}
+
+/// Handles a single HTTP request.
+async fn handle(
+    request: Request<Incoming>,
+    state: Arc<State>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let response = match (request.method(), request.uri().path()) {
+        (&Method::GET, "/metrics") => {
+            let snapshot = Snapshot::capture();
+            let body = metrics::global().render(snapshot.as_ref());
+
+            text_response(StatusCode::OK, PROMETHEUS_CONTENT_TYPE, body)
+        }
+        (&Method::GET, "/healthz") => text_response(StatusCode::OK, "text/plain", "ok\n"),
+        (&Method::GET, "/readyz") => {
+            let readiness = state.readiness().await;
+            let status = if readiness.ready {
+                StatusCode::OK
+            } else {
+                StatusCode::SERVICE_UNAVAILABLE
+            };
+            let body = serde_json::to_string(&readiness).unwrap_or_default();
+
+            text_response(status, "application/json", body)
+        }
+        (_, "/metrics" | "/healthz" | "/readyz") => text_response(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "text/plain",
+            "method not allowed\n",
+        ),
+        _ => text_response(StatusCode::NOT_FOUND, "text/plain", "not found\n"),
+    };
+
+    Ok(response)
+}
+
+/// Returns a response with the given status, content type and body.
+fn text_response(
+    status: StatusCode,
+    content_type: &'static str,
+    body: impl Into<Bytes>,
+) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(body.into()));
+
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static(content_type),
+    );
+
+    response
+}
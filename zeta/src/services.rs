@@ -0,0 +1,507 @@
+//! Registration with the network and its services.
+//!
+//! Once we're registered, we become an IRC operator and set our user modes if it's configured, and
+//! join the channels that require identification once services have confirmed that we're
+//! identified, either with `RPL_LOGGEDIN` or a `NOTICE` from `NickServ`. If an identification
+//! timeout is configured, they're joined anyway if services haven't identified us by then.
+//!
+//! If the primary nickname was taken when we connected, it's watched with `MONITOR`, or polled
+//! with `ISON` on servers that don't support `MONITOR`, and taken as soon as it's available. If the
+//! services package of the network is configured, the nickname is also reclaimed from whoever is
+//! using it with `REGAIN` or `RECOVER`, backing off after every attempt that doesn't recover it.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use irc::proto::{Command, Message, Response};
+use tracing::{debug, info, warn};
+use zeta_plugin::Client;
+
+use crate::config::{IrcChannelConfig, IrcConfig};
+use crate::isupport::{CaseMapping, ISupport};
+
+/// Handles registration with the network and its services.
+pub struct Services {
+    /// The IRC client configuration.
+    config: IrcConfig,
+    /// The state of the current connection.
+    state: Mutex<State>,
+}
+
+/// The state of the current connection.
+#[derive(Debug, Default)]
+struct State {
+    /// Whether we've finished registering with the server.
+    registered: bool,
+    /// Whether services have confirmed that we're identified.
+    identified: bool,
+    /// Whether the channels that require identification have been joined.
+    joined: bool,
+    /// Whether the primary nickname is on our `MONITOR` list.
+    monitoring: bool,
+    /// When we finished registering with the server.
+    registered_at: Option<Instant>,
+    /// The number of times we've asked services to reclaim the primary nickname.
+    reclaim_attempts: u32,
+    /// When we may next ask services to reclaim the primary nickname.
+    next_reclaim: Option<Instant>,
+}
+
+impl Services {
+    /// Creates a handler for the given IRC configuration.
+    #[must_use]
+    pub const fn new(config: IrcConfig) -> Services {
+        Services {
+            config,
+            state: Mutex::new(State {
+                registered: false,
+                identified: false,
+                joined: false,
+                monitoring: false,
+                registered_at: None,
+                reclaim_attempts: 0,
+                next_reclaim: None,
+            }),
+        }
+    }
+
+    /// Updates the state from `message`, and acts on it.
+    ///
+    /// `nickname` is our current nickname, after `message` has been applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a command could not be sent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is poisoned.
+    pub fn handle(
+        &self,
+        client: &Client,
+        message: &Message,
+        nickname: &str,
+        isupport: &ISupport,
+    ) -> Result<(), irc::error::Error> {
+        let casemapping = isupport.casemapping;
+        let mut state = self.state.lock().unwrap();
+
+        match message.command {
+            Command::Response(Response::RPL_WELCOME, _) => *state = State::default(),
+            Command::Response(Response::RPL_ENDOFMOTD | Response::ERR_NOMOTD, _)
+                if !state.registered =>
+            {
+                state.registered = true;
+                state.registered_at = Some(Instant::now());
+
+                match self.config.oper {
+                    Some(ref oper) => {
+                        client.send(Command::OPER(oper.name.clone(), oper.password.clone()))?;
+                    }
+                    None => self.send_umodes(client, nickname)?,
+                }
+
+                if !self.is_primary(nickname, casemapping)
+                    && self.config.nick_recovery.enabled
+                    && isupport.monitor
+                {
+                    debug!(nickname = %self.config.nickname, "monitoring primary nickname");
+                    client.send(monitor('+', &self.config.nickname))?;
+                    state.monitoring = true;
+                }
+
+                if self.has_restricted_channels() && self.config.nick_password.is_none() {
+                    warn!(
+                        "channels require identification, but no nick_password is configured - \
+                         they're only joined if services identify us by other means or the \
+                         identification timeout passes"
+                    );
+                }
+
+                self.join_restricted_channels(client, &mut state)?;
+            }
+            Command::Response(Response::RPL_YOUREOPER, _) => {
+                info!("became an irc operator");
+                self.send_umodes(client, nickname)?;
+            }
+            Command::Response(Response::ERR_PASSWDMISMATCH | Response::ERR_NOOPERHOST, _)
+                if state.registered && self.config.oper.is_some() =>
+            {
+                warn!("could not become an irc operator");
+                self.send_umodes(client, nickname)?;
+            }
+            Command::Response(Response::RPL_LOGGEDIN, _) => {
+                debug!("identified with services");
+                state.identified = true;
+                self.join_restricted_channels(client, &mut state)?;
+            }
+            Command::NOTICE(_, ref text)
+                if !state.identified
+                    && is_nickserv(message, casemapping)
+                    && is_identified_notice(text) =>
+            {
+                debug!("nickserv says we're identified");
+                state.identified = true;
+                self.join_restricted_channels(client, &mut state)?;
+            }
+            Command::Response(Response::RPL_LOGGEDOUT, _) => state.identified = false,
+            Command::Response(Response::RPL_MONOFFLINE, ref args) => {
+                let targets = args.get(1).map(String::as_str).unwrap_or_default();
+
+                if contains_nick(targets.split(','), &self.config.nickname, casemapping) {
+                    self.take_primary(client, nickname, casemapping)?;
+                }
+            }
+            Command::Response(Response::RPL_MONONLINE, ref args) => {
+                let targets = args.get(1).map(String::as_str).unwrap_or_default();
+
+                if contains_nick(targets.split(','), &self.config.nickname, casemapping) {
+                    self.reclaim_primary(client, &mut state, nickname, casemapping)?;
+                }
+            }
+            Command::Response(Response::RPL_ISON, ref args) => {
+                let online = args.get(1).map(String::as_str).unwrap_or_default();
+
+                if contains_nick(online.split(' '), &self.config.nickname, casemapping) {
+                    self.reclaim_primary(client, &mut state, nickname, casemapping)?;
+                } else {
+                    self.take_primary(client, nickname, casemapping)?;
+                }
+            }
+            Command::NICK(ref new_nick)
+                if casemapping.fold(new_nick) == casemapping.fold(nickname)
+                    && self.is_primary(nickname, casemapping)
+                    && (state.monitoring || state.reclaim_attempts > 0) =>
+            {
+                info!(%nickname, "recovered primary nickname");
+                state.reclaim_attempts = 0;
+                state.next_reclaim = None;
+
+                if state.monitoring {
+                    client.send(monitor('-', &self.config.nickname))?;
+                    state.monitoring = false;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether the primary nickname is available, if we're not using it.
+    ///
+    /// This is called periodically. With `MONITOR`, the server tells us as soon as the nickname
+    /// is available, so it's only checked if it might have to be reclaimed through services.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command could not be sent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is poisoned.
+    pub fn check_primary(
+        &self,
+        client: &Client,
+        nickname: &str,
+        casemapping: CaseMapping,
+    ) -> Result<(), irc::error::Error> {
+        let state = self.state.lock().unwrap();
+
+        if !self.config.nick_recovery.enabled
+            || !state.registered
+            || self.is_primary(nickname, casemapping)
+            || (state.monitoring && self.config.services.is_none())
+        {
+            return Ok(());
+        }
+
+        client.send(Command::ISON(vec![self.config.nickname.clone()]))
+    }
+
+    /// Returns when the channels that require identification are joined anyway, if services
+    /// haven't identified us by then.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is poisoned.
+    pub fn identification_deadline(&self) -> Option<Instant> {
+        let state = self.state.lock().unwrap();
+
+        self.deadline(&state)
+    }
+
+    /// Joins the channels that require identification if services haven't identified us before
+    /// the identification timeout.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a command could not be sent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is poisoned.
+    pub fn check_identification(&self, client: &Client) -> Result<(), irc::error::Error> {
+        let mut state = self.state.lock().unwrap();
+
+        if self
+            .deadline(&state)
+            .is_none_or(|deadline| Instant::now() < deadline)
+        {
+            return Ok(());
+        }
+
+        warn!("services did not identify us in time, joining channels that require it anyway");
+        self.join_channels(client, &mut state)
+    }
+
+    /// Changes our nickname to the primary one, if we're not already using it.
+    ///
+    /// This should only be done once the server has said that the nickname is available, since
+    /// the client falls back to the next alternative nickname if it's in use.
+    fn take_primary(
+        &self,
+        client: &Client,
+        nickname: &str,
+        casemapping: CaseMapping,
+    ) -> Result<(), irc::error::Error> {
+        if !self.config.nick_recovery.enabled || self.is_primary(nickname, casemapping) {
+            return Ok(());
+        }
+
+        info!(nickname = %self.config.nickname, "primary nickname is available, taking it");
+        client.send(Command::NICK(self.config.nickname.clone()))
+    }
+
+    /// Asks services to reclaim the primary nickname from whoever is using it, if we know how.
+    ///
+    /// Attempts are spaced out by a delay that doubles after every attempt, so the password isn't
+    /// sent every interval when services won't hand the nickname over.
+    fn reclaim_primary(
+        &self,
+        client: &Client,
+        state: &mut State,
+        nickname: &str,
+        casemapping: CaseMapping,
+    ) -> Result<(), irc::error::Error> {
+        if !self.config.nick_recovery.enabled || self.is_primary(nickname, casemapping) {
+            return Ok(());
+        }
+
+        if let Some(services) = self.config.services
+            && let Some(ref password) = self.config.nick_password
+        {
+            let recovery = &self.config.nick_recovery;
+
+            if !state.reclaim_due(Instant::now(), recovery.interval, recovery.max_backoff) {
+                debug!(
+                    attempts = state.reclaim_attempts,
+                    "backing off from reclaiming primary nickname"
+                );
+
+                return Ok(());
+            }
+
+            let command = services.reclaim_command();
+
+            debug!(nickname = %self.config.nickname, %command, "reclaiming primary nickname");
+            client.send(Command::NICKSERV(vec![
+                command.to_string(),
+                self.config.nickname.clone(),
+                password.clone(),
+            ]))?;
+        }
+
+        Ok(())
+    }
+
+    /// Joins the channels that require identification, once we're registered and identified.
+    fn join_restricted_channels(
+        &self,
+        client: &Client,
+        state: &mut State,
+    ) -> Result<(), irc::error::Error> {
+        if !state.registered || !state.identified || state.joined {
+            return Ok(());
+        }
+
+        self.join_channels(client, state)
+    }
+
+    /// Joins the channels that require identification, unless they've already been joined.
+    fn join_channels(&self, client: &Client, state: &mut State) -> Result<(), irc::error::Error> {
+        if state.joined {
+            return Ok(());
+        }
+
+        state.joined = true;
+
+        for channel in self.restricted_channels() {
+            debug!(channel = %channel.name, "joining channel that requires identification");
+
+            match channel.key {
+                Some(ref key) => client.send_join_with_keys(&channel.name, key)?,
+                None => client.send_join(&channel.name)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sets the configured user modes.
+    fn send_umodes(&self, client: &Client, nickname: &str) -> Result<(), irc::error::Error> {
+        if let Some(ref umodes) = self.config.umodes {
+            client.send(Command::Raw(
+                "MODE".to_string(),
+                vec![nickname.to_string(), umodes.clone()],
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the channels that are joined once we're identified.
+    fn restricted_channels(&self) -> impl Iterator<Item = &IrcChannelConfig> {
+        self.config
+            .channels
+            .iter()
+            .filter(|channel| channel.requires_identification)
+    }
+
+    /// Returns whether there are channels that are joined once we're identified.
+    fn has_restricted_channels(&self) -> bool {
+        self.restricted_channels().next().is_some()
+    }
+
+    /// Returns when the channels that require identification are joined anyway, if there's an
+    /// identification timeout and they haven't been joined yet.
+    fn deadline(&self, state: &State) -> Option<Instant> {
+        let timeout = self.config.identification_timeout?;
+        let registered_at = state.registered_at?;
+
+        (!state.joined && self.has_restricted_channels()).then(|| registered_at + timeout)
+    }
+
+    /// Returns whether `nickname` is the primary nickname.
+    fn is_primary(&self, nickname: &str, casemapping: CaseMapping) -> bool {
+        casemapping.fold(nickname) == casemapping.fold(&self.config.nickname)
+    }
+}
+
+impl State {
+    /// Returns whether services may be asked to reclaim the primary nickname at `now`, and if so,
+    /// records the attempt.
+    ///
+    /// The delay after an attempt starts at `interval` and doubles with every attempt, up to
+    /// `max_backoff`.
+    fn reclaim_due(&mut self, now: Instant, interval: Duration, max_backoff: Duration) -> bool {
+        if self.next_reclaim.is_some_and(|next| now < next) {
+            return false;
+        }
+
+        let delay = interval
+            .saturating_mul(2_u32.saturating_pow(self.reclaim_attempts))
+            .min(max_backoff);
+
+        self.reclaim_attempts = self.reclaim_attempts.saturating_add(1);
+        // Checks are `interval` apart, so leave some slack for replies to them arriving early
+        self.next_reclaim = Some(now + delay.saturating_sub(interval / 2));
+
+        true
+    }
+}
+
+/// Returns whether `message` was sent by `NickServ`.
+fn is_nickserv(message: &Message, casemapping: CaseMapping) -> bool {
+    message
+        .source_nickname()
+        .is_some_and(|nick| casemapping.fold(nick) == casemapping.fold("NickServ"))
+}
+
+/// Returns whether `text` is a `NickServ` notice saying that we're identified.
+///
+/// Atheme says "You are now identified for <account>." and Anope says "Password accepted - you
+/// are now recognized."
+fn is_identified_notice(text: &str) -> bool {
+    let text = text.to_lowercase();
+
+    text.contains("you are now identified") || text.contains("you are now recognized")
+}
+
+/// Returns a `MONITOR` command that adds (`+`) or removes (`-`) `nickname`.
+fn monitor(action: char, nickname: &str) -> Command {
+    Command::Raw(
+        "MONITOR".to_string(),
+        vec![action.to_string(), nickname.to_string()],
+    )
+}
+
+/// Returns whether `targets` contains `nickname`.
+///
+/// Targets can be either nicknames or `nick!user@host` hostmasks.
+fn contains_nick<'a>(
+    mut targets: impl Iterator<Item = &'a str>,
+    nickname: &str,
+    casemapping: CaseMapping,
+) -> bool {
+    let nickname = casemapping.fold(nickname);
+
+    targets.any(|target| {
+        let nick = target.split_once('!').map_or(target, |(nick, _)| nick);
+
+        casemapping.fold(nick) == nickname
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_find_nicknames_in_targets() {
+        let casemapping = CaseMapping::Rfc1459;
+
+        assert!(contains_nick(
+            "other,Zeta[dev]".split(','),
+            "zeta{dev}",
+            casemapping
+        ));
+        assert!(contains_nick(
+            "zeta!zeta@maero.dk".split(','),
+            "zeta",
+            casemapping
+        ));
+        assert!(!contains_nick("zet4 z3ta".split(' '), "zeta", casemapping));
+        assert!(!contains_nick("".split(' '), "zeta", casemapping));
+    }
+
+    #[test]
+    fn it_should_recognize_identified_notices() {
+        assert!(is_identified_notice(
+            "You are now identified for \x02zeta\x02."
+        ));
+        assert!(is_identified_notice(
+            "Password accepted - you are now recognized."
+        ));
+        assert!(!is_identified_notice("Invalid password for \x02zeta\x02."));
+    }
+
+    #[test]
+    fn it_should_back_off_from_reclaiming() {
+        let mut state = State::default();
+        let interval = Duration::from_secs(60);
+        let max_backoff = Duration::from_secs(300);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert!(state.reclaim_due(at(0), interval, max_backoff));
+        assert!(!state.reclaim_due(at(20), interval, max_backoff));
+        assert!(state.reclaim_due(at(60), interval, max_backoff));
+        assert!(!state.reclaim_due(at(120), interval, max_backoff));
+        assert!(state.reclaim_due(at(180), interval, max_backoff));
+        assert!(!state.reclaim_due(at(360), interval, max_backoff));
+        assert!(state.reclaim_due(at(420), interval, max_backoff));
+        // Capped at `max_backoff`
+        assert!(state.reclaim_due(at(720), interval, max_backoff));
+    }
+}
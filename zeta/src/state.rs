@@ -0,0 +1,591 @@
+//! Channel state tracking.
+//!
+//! The [`Tracker`] follows the channels we're in, who is in them, their membership modes (e.g.
+//! op and voice), channel modes and topics, and the hostmasks and services accounts of the users
+//! we share a channel with. It's fed every message we receive, and learns about existing members
+//! from `NAMES` and `WHO` replies when we join a channel. Accounts are learned from the IRCv3
+//! `extended-join`, `account-notify` and `account-tag` extensions, when the server supports them.
+//!
+//! Plugins get read-only snapshots of the state through [`Context`](crate::context::Context).
+
+use std::collections::{BTreeMap, HashMap};
+
+use irc::proto::message::Tag;
+use irc::proto::{ChannelMode, Command, Message, Mode, Prefix, Response};
+
+use crate::isupport::ISupport;
+
+/// The state of the channels we're in and their members.
+#[derive(Debug, Default)]
+pub struct Tracker {
+    /// Channels by their folded name.
+    channels: HashMap<String, Channel>,
+    /// Users we share a channel with, by their folded nickname.
+    users: HashMap<String, User>,
+}
+
+/// A channel we're in.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Channel {
+    /// The name of the channel.
+    pub name: String,
+    /// The topic of the channel, if it has one.
+    pub topic: Option<String>,
+    /// Channel modes and their parameters, e.g. `('l', Some("10"))` or `('n', None)`.
+    ///
+    /// List modes such as bans aren't tracked.
+    pub modes: BTreeMap<char, Option<String>>,
+    /// Members by their folded nickname.
+    pub members: HashMap<String, Member>,
+}
+
+/// A member of a channel.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Member {
+    /// The nickname of the member.
+    pub nick: String,
+    /// The membership modes of the member, e.g. `ov`, from the highest ranked to the lowest.
+    pub modes: String,
+    /// The nickname prefixes of the membership modes, e.g. `@+`.
+    pub prefixes: String,
+}
+
+/// A user we share a channel with.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct User {
+    /// The nickname of the user.
+    pub nick: String,
+    /// The username of the user, if known.
+    pub user: Option<String>,
+    /// The hostname of the user, if known.
+    pub host: Option<String>,
+    /// The services account the user is logged in to, if known.
+    pub account: Option<String>,
+}
+
+impl Tracker {
+    /// Creates an empty tracker.
+    #[must_use]
+    pub fn new() -> Tracker {
+        Tracker::default()
+    }
+
+    /// Forgets everything, e.g. when reconnecting.
+    pub fn clear(&mut self) {
+        self.channels.clear();
+        self.users.clear();
+    }
+
+    /// Returns the channel with the given folded name.
+    #[must_use]
+    pub fn channel(&self, name: &str) -> Option<&Channel> {
+        self.channels.get(name)
+    }
+
+    /// Returns all the channels we're in.
+    pub fn channels(&self) -> impl Iterator<Item = &Channel> {
+        self.channels.values()
+    }
+
+    /// Returns the user with the given folded nickname.
+    #[must_use]
+    pub fn user(&self, nick: &str) -> Option<&User> {
+        self.users.get(nick)
+    }
+
+    /// Updates the state from a `message` we received.
+    ///
+    /// `own_nick` is our current nickname, and `isupport` the features advertised by the server.
+    pub fn update(&mut self, message: &Message, own_nick: &str, isupport: &ISupport) {
+        let casemapping = isupport.casemapping;
+        let fold = |s: &str| casemapping.fold(s);
+
+        if let Some(Prefix::Nickname(nick, user, host)) = &message.prefix
+            && let Some(known) = self.users.get_mut(&fold(nick))
+        {
+            if !user.is_empty() {
+                known.user = Some(user.clone());
+            }
+
+            if !host.is_empty() {
+                known.host = Some(host.clone());
+            }
+
+            if let Some(account) = account_tag(message) {
+                known.account = account;
+            }
+        }
+
+        let source = message.source_nickname().unwrap_or_default();
+        let is_self = !source.is_empty() && fold(source) == fold(own_nick);
+
+        match message.command {
+            Command::Response(Response::RPL_WELCOME, _) => self.clear(),
+            Command::JOIN(ref channels, ref account, _) => {
+                let Some(Prefix::Nickname(nick, username, host)) = &message.prefix else {
+                    return;
+                };
+                let key = fold(nick);
+                let mut is_member = false;
+
+                for name in channels.split(',') {
+                    if is_self {
+                        self.channels.insert(
+                            fold(name),
+                            Channel {
+                                name: name.to_string(),
+                                ..Channel::default()
+                            },
+                        );
+                    }
+
+                    if let Some(channel) = self.channels.get_mut(&fold(name)) {
+                        channel.members.insert(key.clone(), Member::new(nick));
+                        is_member = true;
+                    }
+                }
+
+                if is_member {
+                    let user = self.user_mut(nick, &key);
+
+                    user.user = Some(username.clone());
+                    user.host = Some(host.clone());
+
+                    // With `extended-join`, the second parameter is the account name, or `*`
+                    if let Some(account) = account {
+                        user.account = parse_account(account);
+                    }
+                }
+            }
+            Command::PART(ref channels, _) => {
+                for name in channels.split(',') {
+                    self.part(&fold(name), &fold(source), is_self);
+                }
+            }
+            Command::KICK(ref channel, ref target, _) => {
+                let is_self = fold(target) == fold(own_nick);
+
+                self.part(&fold(channel), &fold(target), is_self);
+            }
+            Command::QUIT(_) => {
+                let key = fold(source);
+
+                for channel in self.channels.values_mut() {
+                    channel.members.remove(&key);
+                }
+
+                self.users.remove(&key);
+            }
+            Command::NICK(ref new_nick) => {
+                let (old_key, new_key) = (fold(source), fold(new_nick));
+
+                for channel in self.channels.values_mut() {
+                    if let Some(mut member) = channel.members.remove(&old_key) {
+                        member.nick.clone_from(new_nick);
+                        channel.members.insert(new_key.clone(), member);
+                    }
+                }
+
+                if let Some(mut user) = self.users.remove(&old_key) {
+                    user.nick.clone_from(new_nick);
+                    self.users.insert(new_key, user);
+                }
+            }
+            Command::ACCOUNT(ref account) => {
+                if let Some(user) = self.users.get_mut(&fold(source)) {
+                    user.account = parse_account(account);
+                }
+            }
+            Command::ChannelMODE(ref channel, ref modes) => {
+                if let Some(channel) = self.channels.get_mut(&fold(channel)) {
+                    for mode in modes {
+                        channel.apply_mode(mode, isupport);
+                    }
+                }
+            }
+            Command::TOPIC(ref channel, ref topic) => {
+                if let Some(channel) = self.channels.get_mut(&fold(channel)) {
+                    channel.topic = topic.clone().filter(|topic| !topic.is_empty());
+                }
+            }
+            Command::Response(Response::RPL_TOPIC, ref args) => {
+                if let [_, channel, topic, ..] = args.as_slice()
+                    && let Some(channel) = self.channels.get_mut(&fold(channel))
+                {
+                    channel.topic = Some(topic.clone()).filter(|topic| !topic.is_empty());
+                }
+            }
+            Command::Response(Response::RPL_NOTOPIC, ref args) => {
+                if let [_, channel, ..] = args.as_slice()
+                    && let Some(channel) = self.channels.get_mut(&fold(channel))
+                {
+                    channel.topic = None;
+                }
+            }
+            Command::Response(Response::RPL_NAMREPLY, ref args) => {
+                if let [.., channel, names] = args.as_slice() {
+                    self.names(&fold(channel), names, isupport);
+                }
+            }
+            Command::Response(Response::RPL_WHOREPLY, ref args) => {
+                // <client> <channel> <username> <host> <server> <nick> <flags> :<hopcount> <realname>
+                if let [_, channel, username, host, _, nick, flags, ..] = args.as_slice() {
+                    let nick_key = fold(nick);
+                    let channel_key = fold(channel);
+
+                    // Users we don't share a channel with would never be pruned
+                    if !self.channels.contains_key(&channel_key)
+                        && !self.users.contains_key(&nick_key)
+                    {
+                        return;
+                    }
+
+                    let user = self.user_mut(nick, &nick_key);
+
+                    user.user = Some(username.clone());
+                    user.host = Some(host.clone());
+
+                    if let Some(channel) = self.channels.get_mut(&channel_key)
+                        && let Some(member) = channel.members.get_mut(&nick_key)
+                    {
+                        // `H` or `G` for here or gone, `*` for IRC operators, then the prefixes
+                        let prefixes = flags.chars().filter(|c| isupport.prefix_mode(*c).is_some());
+
+                        member.set_prefixes(prefixes, isupport);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Removes the member `nick` from `channel`, or the whole channel if it's us that left.
+    fn part(&mut self, channel: &str, nick: &str, is_self: bool) {
+        if is_self {
+            self.channels.remove(channel);
+            self.prune_users();
+        } else if let Some(channel) = self.channels.get_mut(channel) {
+            channel.members.remove(nick);
+
+            if !self
+                .channels
+                .values()
+                .any(|channel| channel.members.contains_key(nick))
+            {
+                self.users.remove(nick);
+            }
+        }
+    }
+
+    /// Adds the members of a `RPL_NAMREPLY` to `channel`.
+    ///
+    /// Names are prefixed with their membership prefixes, all of them with `multi-prefix`, and
+    /// may be full hostmasks with `userhost-in-names`. Replies for channels we're not in, e.g. to
+    /// a `NAMES` for another channel, are ignored.
+    fn names(&mut self, channel: &str, names: &str, isupport: &ISupport) {
+        let casemapping = isupport.casemapping;
+
+        if !self.channels.contains_key(channel) {
+            return;
+        }
+
+        for name in names.split_whitespace() {
+            let hostmask = name.trim_start_matches(|c| isupport.prefix_mode(c).is_some());
+            let prefixes = name[..name.len() - hostmask.len()].chars();
+            let (nick, userhost) = hostmask.split_once('!').unwrap_or((hostmask, ""));
+            let key = casemapping.fold(nick);
+
+            if let Some(channel) = self.channels.get_mut(channel) {
+                channel
+                    .members
+                    .entry(key.clone())
+                    .or_insert_with(|| Member::new(nick))
+                    .set_prefixes(prefixes, isupport);
+            }
+
+            let user = self.user_mut(nick, &key);
+
+            if let Some((username, host)) = userhost.split_once('@') {
+                user.user = Some(username.to_string());
+                user.host = Some(host.to_string());
+            }
+        }
+    }
+
+    /// Returns the user with the folded nickname `key`, adding it if it's unknown.
+    fn user_mut(&mut self, nick: &str, key: &str) -> &mut User {
+        self.users.entry(key.to_string()).or_insert_with(|| User {
+            nick: nick.to_string(),
+            ..User::default()
+        })
+    }
+
+    /// Forgets users that we no longer share a channel with.
+    fn prune_users(&mut self) {
+        let channels = &self.channels;
+
+        self.users.retain(|nick, _| {
+            channels
+                .values()
+                .any(|channel| channel.members.contains_key(nick))
+        });
+    }
+}
+
+impl Channel {
+    /// Returns the member with the given folded nickname.
+    #[must_use]
+    pub fn member(&self, nick: &str) -> Option<&Member> {
+        self.members.get(nick)
+    }
+
+    /// Applies a channel mode change.
+    fn apply_mode(&mut self, mode: &Mode<ChannelMode>, isupport: &ISupport) {
+        let (adding, mode, arg) = match mode {
+            Mode::Plus(mode, arg) => (true, mode, arg.as_deref()),
+            Mode::Minus(mode, arg) => (false, mode, arg.as_deref()),
+            Mode::NoPrefix(_) => return,
+        };
+        let Some(mode) = mode.to_string().chars().next() else {
+            return;
+        };
+
+        if isupport.mode_rank(mode).is_some() {
+            if let Some(nick) = arg
+                && let Some(member) = self.members.get_mut(&isupport.casemapping.fold(nick))
+            {
+                member.set_mode(mode, adding, isupport);
+            }
+        } else if matches!(mode, 'b' | 'e' | 'I' | 'q') {
+            // List modes aren't tracked
+        } else if adding {
+            self.modes.insert(mode, arg.map(ToString::to_string));
+        } else {
+            self.modes.remove(&mode);
+        }
+    }
+}
+
+impl Member {
+    /// Creates a member without any membership modes.
+    fn new(nick: &str) -> Member {
+        Member {
+            nick: nick.to_string(),
+            ..Member::default()
+        }
+    }
+
+    /// Returns whether the member has the membership mode `mode`, e.g. `o`.
+    #[must_use]
+    pub fn has_mode(&self, mode: char) -> bool {
+        self.modes.contains(mode)
+    }
+
+    /// Returns whether the member is a channel operator.
+    #[must_use]
+    pub fn is_op(&self) -> bool {
+        self.has_mode('o')
+    }
+
+    /// Returns whether the member is voiced.
+    #[must_use]
+    pub fn is_voiced(&self) -> bool {
+        self.has_mode('v')
+    }
+
+    /// Returns the highest ranked nickname prefix of the member, e.g. `@`.
+    #[must_use]
+    pub fn prefix(&self) -> Option<char> {
+        self.prefixes.chars().next()
+    }
+
+    /// Adds or removes the membership mode `mode`.
+    fn set_mode(&mut self, mode: char, adding: bool, isupport: &ISupport) {
+        let mut modes: Vec<char> = self.modes.chars().filter(|m| *m != mode).collect();
+
+        if adding {
+            modes.push(mode);
+        }
+
+        self.set_modes(modes, isupport);
+    }
+
+    /// Replaces the membership modes with those of the nickname `prefixes`, e.g. `@+`.
+    fn set_prefixes(&mut self, prefixes: impl Iterator<Item = char>, isupport: &ISupport) {
+        let modes = prefixes
+            .filter_map(|prefix| isupport.prefix_mode(prefix))
+            .collect();
+
+        self.set_modes(modes, isupport);
+    }
+
+    /// Replaces the membership modes, ordered by their rank.
+    fn set_modes(&mut self, mut modes: Vec<char>, isupport: &ISupport) {
+        modes.sort_by_key(|mode| isupport.mode_rank(*mode));
+        modes.dedup();
+
+        self.prefixes = modes
+            .iter()
+            .filter_map(|mode| isupport.mode_prefix(*mode))
+            .collect();
+        self.modes = modes.into_iter().collect();
+    }
+}
+
+impl User {
+    /// Returns the `nick!user@host` hostmask of the user, if their username and host are known.
+    #[must_use]
+    pub fn hostmask(&self) -> Option<String> {
+        Some(format!(
+            "{}!{}@{}",
+            self.nick,
+            self.user.as_deref()?,
+            self.host.as_deref()?
+        ))
+    }
+}
+
+/// Returns the account of the sender of `message` from its IRCv3 `account` tag.
+///
+/// Returns `Some(None)` if the tag says the user isn't logged in, and `None` if there's no tag.
+fn account_tag(message: &Message) -> Option<Option<String>> {
+    message
+        .tags
+        .as_ref()?
+        .iter()
+        .find_map(|Tag(key, value)| (key == "account").then(|| value.as_deref()))
+        .map(|value| value.and_then(parse_account))
+}
+
+/// Parses an account name, where `*` means that the user isn't logged in.
+fn parse_account(account: &str) -> Option<String> {
+    (!account.is_empty() && account != "*").then(|| account.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(tracker: &mut Tracker, lines: &[&str]) {
+        let isupport = ISupport::new();
+
+        for line in lines {
+            let message: Message = format!("{line}\r\n").parse().expect("invalid message");
+
+            tracker.update(&message, "zeta", &isupport);
+        }
+    }
+
+    #[test]
+    fn it_should_track_members_from_names() {
+        let mut tracker = Tracker::new();
+
+        feed(
+            &mut tracker,
+            &[
+                ":zeta!zeta@bot JOIN #zeta",
+                ":irc.example.com 353 zeta = #zeta :@+mk!mk@maero.dk +Guest zeta",
+                ":irc.example.com 332 zeta #zeta :Welcome to #zeta",
+            ],
+        );
+
+        let channel = tracker.channel("#zeta").expect("channel");
+        let mk = channel.member("mk").expect("member");
+
+        assert_eq!(channel.topic.as_deref(), Some("Welcome to #zeta"));
+        assert!(mk.is_op() && mk.is_voiced());
+        assert_eq!(mk.prefix(), Some('@'));
+        assert!(channel.member("guest").is_some_and(Member::is_voiced));
+        assert_eq!(
+            tracker.user("mk").and_then(User::hostmask).as_deref(),
+            Some("mk!mk@maero.dk")
+        );
+    }
+
+    #[test]
+    fn it_should_follow_joins_modes_and_nick_changes() {
+        let mut tracker = Tracker::new();
+
+        feed(
+            &mut tracker,
+            &[
+                ":zeta!zeta@bot JOIN #zeta",
+                ":mk!mk@maero.dk JOIN #zeta mk :Mikkel",
+                ":ChanServ!ChanServ@services MODE #zeta +o-v+l mk mk 10",
+                ":mk!mk@maero.dk NICK mk_",
+            ],
+        );
+
+        let channel = tracker.channel("#zeta").expect("channel");
+        let member = channel.member("mk_").expect("member");
+
+        assert!(member.is_op());
+        assert_eq!(member.nick, "mk_");
+        assert_eq!(channel.modes.get(&'l'), Some(&Some("10".to_string())));
+        assert!(channel.member("mk").is_none());
+        assert_eq!(
+            tracker.user("mk_").and_then(|user| user.account.clone()),
+            Some("mk".to_string())
+        );
+    }
+
+    #[test]
+    fn it_should_forget_members_that_leave() {
+        let mut tracker = Tracker::new();
+
+        feed(
+            &mut tracker,
+            &[
+                ":zeta!zeta@bot JOIN #zeta",
+                ":zeta!zeta@bot JOIN #other",
+                ":mk!mk@maero.dk JOIN #zeta",
+                ":mk!mk@maero.dk JOIN #other",
+                ":mk!mk@maero.dk PART #zeta",
+            ],
+        );
+
+        assert!(
+            tracker
+                .channel("#zeta")
+                .expect("channel")
+                .member("mk")
+                .is_none()
+        );
+        assert!(tracker.user("mk").is_some());
+
+        feed(&mut tracker, &[":mk!mk@maero.dk QUIT :bye"]);
+
+        assert!(tracker.user("mk").is_none());
+        assert!(
+            tracker
+                .channel("#other")
+                .expect("channel")
+                .member("mk")
+                .is_none()
+        );
+
+        feed(&mut tracker, &[":op!op@host KICK #other zeta :bye"]);
+
+        assert!(tracker.channel("#other").is_none());
+    }
+
+    #[test]
+    fn it_should_ignore_users_of_untracked_channels() {
+        let mut tracker = Tracker::new();
+
+        feed(
+            &mut tracker,
+            &[
+                ":zeta!zeta@bot JOIN #zeta",
+                ":irc.example.com 353 zeta = #other :@mk!mk@maero.dk Guest",
+                ":irc.example.com 352 zeta #other guest guest.host irc.example.com Guest H :0 Guest",
+                ":irc.example.com 352 zeta * stranger some.host irc.example.com Stranger H :0 Stranger",
+            ],
+        );
+
+        assert!(tracker.channel("#other").is_none());
+        assert!(tracker.user("mk").is_none());
+        assert!(tracker.user("guest").is_none());
+        assert!(tracker.user("stranger").is_none());
+    }
+}
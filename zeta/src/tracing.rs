@@ -1,17 +1,103 @@
+use std::collections::HashMap;
 use std::env;
 
 use miette::{IntoDiagnostic, WrapErr};
 use opentelemetry::InstrumentationScope;
 use opentelemetry::trace::TracerProvider;
+use opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge;
+use opentelemetry_otlp::tonic_types::metadata::MetadataMap;
+use opentelemetry_otlp::{
+    LogExporter, MetricExporter, SpanExporter, WithExportConfig, WithHttpConfig, WithTonicConfig,
+};
 use opentelemetry_resource_detectors::{
     HostResourceDetector, K8sResourceDetector, OsResourceDetector,
 };
 use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::logs::SdkLoggerProvider;
+use opentelemetry_sdk::metrics::SdkMeterProvider;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
 use opentelemetry_sdk::resource::{EnvResourceDetector, ResourceDetector};
-use tracing::info;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use tracing::{Subscriber, info};
+use tracing_subscriber::layer::Layered;
+use tracing_subscriber::{
+    EnvFilter, Layer, Registry, layer::SubscriberExt, util::SubscriberInitExt,
+};
+
+use crate::config::{self, OtlpProtocol, TracingFormat};
+
+/// The registry with the filter applied, which the other layers are stacked on.
+type Filtered = Layered<EnvFilter, Registry>;
+
+/// A type-erased layer on top of the filtered registry.
+type BoxedLayer = Box<dyn Layer<Filtered> + Send + Sync>;
+
+/// Keeps the OpenTelemetry providers alive and flushes them when dropped.
+#[derive(Default)]
+pub struct Guard {
+    tracer_provider: Option<SdkTracerProvider>,
+    logger_provider: Option<SdkLoggerProvider>,
+    meter_provider: Option<SdkMeterProvider>,
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        // Errors are ignored since there's nowhere left to report them
+        if let Some(provider) = self.tracer_provider.take() {
+            let _ = provider.shutdown();
+        }
+
+        if let Some(provider) = self.logger_provider.take() {
+            let _ = provider.shutdown();
+        }
+
+        if let Some(provider) = self.meter_provider.take() {
+            let _ = provider.shutdown();
+        }
+    }
+}
+
+/// Builds an OTLP exporter for the configured protocol, endpoint and headers.
+///
+/// When using HTTP, an explicitly configured endpoint is used as-is by the exporter, so the
+/// signal-specific `path` is appended to it.
+macro_rules! build_exporter {
+    ($exporter:ty, $config:expr, $path:literal) => {{
+        let config: &config::TracingConfig = $config;
 
-use crate::config;
+        match config.protocol {
+            OtlpProtocol::Grpc => {
+                let mut builder = <$exporter>::builder()
+                    .with_tonic()
+                    .with_metadata(metadata(&config.headers)?);
+
+                if let Some(ref endpoint) = config.endpoint {
+                    builder = builder.with_endpoint(endpoint);
+                }
+
+                builder.build()
+            }
+            OtlpProtocol::Http => {
+                let mut builder = <$exporter>::builder()
+                    .with_http()
+                    .with_headers(config.headers.clone());
+
+                if let Some(ref endpoint) = config.endpoint {
+                    builder = builder.with_endpoint(format!(
+                        "{}{}",
+                        endpoint.trim_end_matches('/'),
+                        $path
+                    ));
+                }
+
+                builder.build()
+            }
+        }
+        .into_diagnostic()
+        .wrap_err(concat!("building otlp ", stringify!($exporter), " failed"))
+    }};
+}
 
 /// Returns a list of resource detectors to use to enrich OpenTelemetry attributes.
 fn otel_resource_detectors() -> Vec<Box<dyn ResourceDetector>> {
@@ -23,54 +109,157 @@ fn otel_resource_detectors() -> Vec<Box<dyn ResourceDetector>> {
     ]
 }
 
-pub fn try_init(tracing: &config::TracingConfig) -> miette::Result<()> {
-    // Create a tracing layer with the configured tracer
-    let telemetry_layer = if tracing.enabled {
-        // Set up the OTLP exporter
-        let otlp_exporter = opentelemetry_otlp::SpanExporter::builder()
-            .with_http()
-            .build()
+/// Returns the resource describing this process.
+fn otel_resource() -> Resource {
+    let res_detectors = otel_resource_detectors();
+
+    Resource::builder_empty()
+        .with_service_name(env!("CARGO_PKG_NAME"))
+        .with_detectors(&res_detectors)
+        .build()
+}
+
+/// Converts the configured headers to gRPC metadata.
+fn metadata(headers: &HashMap<String, String>) -> miette::Result<MetadataMap> {
+    let mut map = HeaderMap::with_capacity(headers.len());
+
+    for (name, value) in headers {
+        let name = HeaderName::try_from(name.as_str())
+            .into_diagnostic()
+            .wrap_err_with(|| format!("invalid otlp header name {name:?}"))?;
+        let value = HeaderValue::try_from(value.as_str())
             .into_diagnostic()
-            .wrap_err("building otlp http exporter failed")?;
-        // Set up resource detectors to enrich otel attributes
-        let res_detectors = otel_resource_detectors();
-        // Resource detectors for tracing context
-        let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder()
-            .with_batch_exporter(otlp_exporter)
-            .with_resource(
-                Resource::builder_empty()
-                    .with_service_name(env!("CARGO_PKG_NAME"))
-                    .with_detectors(&res_detectors)
-                    .build(),
-            )
+            .wrap_err_with(|| format!("invalid otlp header value for {name}"))?;
+
+        map.insert(name, value);
+    }
+
+    Ok(MetadataMap::from_headers(map))
+}
+
+/// Returns the stdout layer in the configured format.
+fn stdout_layer(format: TracingFormat) -> BoxedLayer {
+    let layer = tracing_subscriber::fmt::layer();
+
+    match format {
+        TracingFormat::Json => layer.json().boxed(),
+        TracingFormat::Pretty => layer.pretty().boxed(),
+        TracingFormat::Compact => layer.compact().boxed(),
+    }
+}
+
+/// Returns the subscriber that passes the events and spans enabled by `filter` to `layers`.
+///
+/// The filter is applied to the registry itself rather than added as one of the layers, since a
+/// type-erased filter in a list of layers doesn't filter the other layers.
+fn subscriber(filter: EnvFilter, layers: Vec<BoxedLayer>) -> impl Subscriber + Send + Sync {
+    tracing_subscriber::registry().with(filter).with(layers)
+}
+
+/// Initializes logging to stdout and, if enabled, exporting of traces, logs and metrics over
+/// OTLP.
+///
+/// The returned guard must be kept alive for as long as telemetry should be exported.
+pub fn try_init(tracing: &config::TracingConfig) -> miette::Result<Guard> {
+    let mut guard = Guard::default();
+    let filter = EnvFilter::try_from_default_env().or_else(|_| EnvFilter::try_new(&tracing.filter));
+    let filter = filter
+        .into_diagnostic()
+        .wrap_err("invalid tracing filter")?;
+    let mut layers: Vec<BoxedLayer> = vec![stdout_layer(tracing.format)];
+
+    if tracing.enabled {
+        let resource = otel_resource();
+
+        // Set up the span exporter, sampling a ratio of new traces
+        let exporter = build_exporter!(SpanExporter, tracing, "/v1/traces")?;
+        let sampler =
+            Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(tracing.sampling_ratio)));
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_sampler(sampler)
+            .with_resource(resource.clone())
             .build();
         let scope = InstrumentationScope::builder(env!("CARGO_PKG_NAME"))
             .with_version(env!("CARGO_PKG_VERSION"))
             .with_schema_url("https://opentelemetry.io/schema/1.0.0")
             .build();
         let tracer = provider.tracer_with_scope(scope);
-        let layer = tracing_opentelemetry::layer().with_tracer(tracer);
 
-        Some(layer)
-    } else {
-        None
-    };
+        // Propagate the trace context to upstream services in outgoing HTTP requests
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        layers.push(tracing_opentelemetry::layer().with_tracer(tracer).boxed());
+        guard.tracer_provider = Some(provider);
+
+        if tracing.logs {
+            let exporter = build_exporter!(LogExporter, tracing, "/v1/logs")?;
+            let provider = SdkLoggerProvider::builder()
+                .with_batch_exporter(exporter)
+                .with_resource(resource.clone())
+                .build();
 
-    let stdout_layer = tracing_subscriber::fmt::layer().json();
+            layers.push(OpenTelemetryTracingBridge::new(&provider).boxed());
+            guard.logger_provider = Some(provider);
+        }
+
+        if tracing.metrics {
+            let exporter = build_exporter!(MetricExporter, tracing, "/v1/metrics")?;
+            let provider = SdkMeterProvider::builder()
+                .with_periodic_exporter(exporter)
+                .with_resource(resource)
+                .build();
+
+            opentelemetry::global::set_meter_provider(provider.clone());
+            layers.push(tracing_opentelemetry::MetricsLayer::new(provider.clone()).boxed());
+            guard.meter_provider = Some(provider);
+        }
+    }
 
     // initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "zeta=debug,reddit=debug,dendanskeordbog=debug".into()),
-        )
-        .with(telemetry_layer)
-        .with(stdout_layer)
+    subscriber(filter, layers)
         .try_init()
         .into_diagnostic()
         .wrap_err("could not init registry")?;
 
-    info!("tracing initialized");
+    info!(
+        enabled = tracing.enabled,
+        protocol = ?tracing.protocol,
+        "tracing initialized"
+    );
+
+    Ok(guard)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tracing::{Event, Level, debug, warn};
+    use tracing_subscriber::layer::Context;
+
+    use super::*;
+
+    /// A layer that records the level of every event it sees.
+    struct Recorder(Arc<Mutex<Vec<Level>>>);
+
+    impl<S: Subscriber> Layer<S> for Recorder {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            self.0.lock().unwrap().push(*event.metadata().level());
+        }
+    }
+
+    #[test]
+    fn it_should_not_record_filtered_events() {
+        let levels = Arc::new(Mutex::new(Vec::new()));
+        let layers: Vec<BoxedLayer> = vec![Recorder(Arc::clone(&levels)).boxed()];
+        let subscriber = subscriber(EnvFilter::new("info"), layers);
+
+        tracing::subscriber::with_default(subscriber, || {
+            debug!("filtered out");
+            info!("recorded");
+            warn!("recorded");
+        });
 
-    Ok(())
+        assert_eq!(*levels.lock().unwrap(), vec![Level::INFO, Level::WARN]);
+    }
 }
@@ -0,0 +1,372 @@
+//! URL extraction from IRC messages.
+//!
+//! People rarely paste bare URLs: they wrap them in `<...>`, parentheses or quotes, end sentences
+//! with them, make them bold, or leave out the scheme entirely. [`extract`] finds the HTTP(S) URLs
+//! a human would see in a message:
+//!
+//! * IRC formatting codes are stripped before looking for URLs.
+//! * URLs may be surrounded by brackets, quotes and punctuation. Trailing punctuation and
+//!   unbalanced closing brackets are left out, so `(see https://example.com/a_(b))` yields
+//!   `https://example.com/a_(b)`.
+//! * Scheme-less `www.` and `youtu.be/` links are assumed to use HTTPS.
+//! * Internationalized domain names are converted to punycode, and non-ASCII characters in paths
+//!   are percent-encoded.
+
+use std::borrow::Cow;
+
+use url::{Host, Url};
+
+/// Prefixes that start a URL with a scheme.
+const SCHEME_PREFIXES: &[&str] = &["http://", "https://"];
+
+/// Prefixes that start a URL without a scheme.
+const SCHEMELESS_PREFIXES: &[&str] = &["www.", "youtu.be/"];
+
+/// Characters that may precede a URL without a scheme.
+const OPENERS: &[char] = &['(', '[', '{', '<', '"', '\'', '`', '*', '_'];
+
+/// Characters that can't be part of a URL, and always end it.
+const TERMINATORS: &[char] = &['<', '>', '"', '`'];
+
+/// Characters that are left out when they end a URL.
+const TRAILING_PUNCTUATION: &[char] = &[
+    '.', ',', ':', ';', '!', '?', '\'', '*', '_', '…', '。', '、', '，', '！', '？',
+];
+
+/// Closing brackets that are left out when they end a URL and aren't balanced within it.
+const BRACKETS: &[(char, char)] = &[('(', ')'), ('[', ']'), ('{', '}')];
+
+/// Returns the HTTP(S) URLs in `text`, in the order they appear.
+#[must_use]
+pub fn extract(text: &str) -> Vec<Url> {
+    strip_formatting(text)
+        .split_whitespace()
+        .flat_map(extract_from_word)
+        .collect()
+}
+
+/// Returns the URLs in a single whitespace-delimited `word`.
+fn extract_from_word(word: &str) -> Vec<Url> {
+    let mut urls = vec![];
+    let mut rest = word;
+
+    while let Some((start, has_scheme)) = find_start(rest) {
+        let opener = rest[..start].chars().next_back();
+        let candidate = &rest[start..];
+        let end = candidate
+            .find(|c: char| TERMINATORS.contains(&c) || (opener == Some('\'') && c == '\''))
+            .unwrap_or(candidate.len());
+        let trimmed = trim_trailing(&candidate[..end]);
+
+        if let Some(url) = parse(trimmed, has_scheme) {
+            urls.push(url);
+        }
+
+        // Continue after the candidate, making sure to always make progress
+        let consumed = trimmed.len().max(1);
+        let next = (consumed..=candidate.len())
+            .find(|&i| candidate.is_char_boundary(i))
+            .unwrap_or(candidate.len());
+
+        rest = &candidate[next..];
+    }
+
+    urls
+}
+
+/// Returns the byte offset of the first URL in `word`, and whether it has a scheme.
+fn find_start(word: &str) -> Option<(usize, bool)> {
+    let mut previous = None;
+
+    for (idx, c) in word.char_indices() {
+        let rest = &word[idx..];
+
+        if !previous.is_some_and(char::is_alphanumeric)
+            && SCHEME_PREFIXES
+                .iter()
+                .any(|p| starts_with_ignore_case(rest, p))
+        {
+            return Some((idx, true));
+        }
+
+        if previous.is_none_or(|c| OPENERS.contains(&c))
+            && SCHEMELESS_PREFIXES
+                .iter()
+                .any(|p| starts_with_ignore_case(rest, p))
+        {
+            return Some((idx, false));
+        }
+
+        previous = Some(c);
+    }
+
+    None
+}
+
+/// Returns `candidate` without trailing punctuation and unbalanced closing brackets.
+fn trim_trailing(mut candidate: &str) -> &str {
+    while let Some(last) = candidate.chars().next_back() {
+        let is_unbalanced = BRACKETS.iter().any(|&(open, close)| {
+            last == close && candidate.matches(open).count() < candidate.matches(close).count()
+        });
+
+        if !is_unbalanced && !TRAILING_PUNCTUATION.contains(&last) {
+            break;
+        }
+
+        candidate = &candidate[..candidate.len() - last.len_utf8()];
+    }
+
+    candidate
+}
+
+/// Parses a URL candidate, assuming HTTPS if it has no scheme.
+fn parse(candidate: &str, has_scheme: bool) -> Option<Url> {
+    let url = if has_scheme {
+        Url::parse(candidate).ok()?
+    } else {
+        Url::parse(&format!("https://{candidate}")).ok()?
+    };
+
+    let is_valid = match url.host()? {
+        Host::Domain(domain) => {
+            let labels: Vec<&str> = domain.split('.').collect();
+
+            // Scheme-less links need a domain below `www`, i.e. `www.com` isn't a link
+            labels.iter().all(|label| !label.is_empty())
+                && (has_scheme || labels.len() > 2 || (labels.len() == 2 && labels[0] != "www"))
+        }
+        Host::Ipv4(_) | Host::Ipv6(_) => true,
+    };
+
+    is_valid.then_some(url)
+}
+
+/// Returns whether `s` starts with `prefix`, ignoring ASCII case.
+fn starts_with_ignore_case(s: &str, prefix: &str) -> bool {
+    s.as_bytes()
+        .get(..prefix.len())
+        .is_some_and(|head| head.eq_ignore_ascii_case(prefix.as_bytes()))
+}
+
+/// Removes IRC formatting codes from `text`.
+///
+/// This handles bold, italics, underline, strikethrough, monospace, reverse, reset and both
+/// regular (`\x03fg,bg`) and hex (`\x04rrggbb,rrggbb`) colors.
+#[must_use]
+pub fn strip_formatting(text: &str) -> Cow<'_, str> {
+    if !text.contains(|c: char| is_format_char(c) || c == '\x03' || c == '\x04') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let color_digit: fn(&char) -> bool = match c {
+            '\x03' => char::is_ascii_digit,
+            '\x04' => char::is_ascii_hexdigit,
+            c if is_format_char(c) => continue,
+            c => {
+                stripped.push(c);
+                continue;
+            }
+        };
+        let max_digits = if c == '\x03' { 2 } else { 6 };
+
+        // The foreground color, optionally followed by a comma and the background color
+        let mut digits = 0;
+
+        while digits < max_digits && chars.next_if(color_digit).is_some() {
+            digits += 1;
+        }
+
+        if digits > 0 {
+            let mut lookahead = chars.clone();
+
+            if lookahead.next() == Some(',') && lookahead.peek().is_some_and(color_digit) {
+                chars.next();
+
+                let mut digits = 0;
+
+                while digits < max_digits && chars.next_if(color_digit).is_some() {
+                    digits += 1;
+                }
+            }
+        }
+    }
+
+    Cow::Owned(stripped)
+}
+
+/// Returns whether `c` is a formatting code without parameters.
+const fn is_format_char(c: char) -> bool {
+    matches!(
+        c,
+        '\x02' | '\x0f' | '\x11' | '\x16' | '\x1d' | '\x1e' | '\x1f'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract_strs(text: &str) -> Vec<String> {
+        extract(text).into_iter().map(String::from).collect()
+    }
+
+    #[test]
+    fn it_should_extract_urls() {
+        let corpus: &[(&str, &[&str])] = &[
+            // Plain URLs
+            ("https://example.com", &["https://example.com/"]),
+            (
+                "http://example.com/a/b?c=d#e",
+                &["http://example.com/a/b?c=d#e"],
+            ),
+            ("HTTPS://EXAMPLE.COM/Path", &["https://example.com/Path"]),
+            ("hello https://example.com world", &["https://example.com/"]),
+            (
+                "https://a.example https://b.example",
+                &["https://a.example/", "https://b.example/"],
+            ),
+            (
+                "tabs\thttps://example.com\tbetween",
+                &["https://example.com/"],
+            ),
+            (
+                "https://example.com:8080/x",
+                &["https://example.com:8080/x"],
+            ),
+            ("http://1.2.3.4/x", &["http://1.2.3.4/x"]),
+            ("http://[::1]:8080/", &["http://[::1]:8080/"]),
+            // Wrapped in brackets and quotes
+            ("<https://example.com/a>", &["https://example.com/a"]),
+            ("(https://example.com/a)", &["https://example.com/a"]),
+            ("[https://example.com/a]", &["https://example.com/a"]),
+            ("\"https://example.com/a\"", &["https://example.com/a"]),
+            ("'https://example.com/a'", &["https://example.com/a"]),
+            ("`https://example.com/a`", &["https://example.com/a"]),
+            ("(<https://example.com/a>)", &["https://example.com/a"]),
+            (
+                "[a link](https://example.com/a)",
+                &["https://example.com/a"],
+            ),
+            ("url=\"https://example.com/a\"", &["https://example.com/a"]),
+            // Balanced brackets are part of the URL
+            (
+                "https://en.wikipedia.org/wiki/Rust_(programming_language)",
+                &["https://en.wikipedia.org/wiki/Rust_(programming_language)"],
+            ),
+            (
+                "(see https://en.wikipedia.org/wiki/Rust_(programming_language))",
+                &["https://en.wikipedia.org/wiki/Rust_(programming_language)"],
+            ),
+            ("https://example.com/a[0]", &["https://example.com/a[0]"]),
+            // Trailing punctuation
+            ("see https://example.com.", &["https://example.com/"]),
+            ("https://example.com/a, and", &["https://example.com/a"]),
+            ("https://example.com/a!?", &["https://example.com/a"]),
+            ("https://example.com/a:", &["https://example.com/a"]),
+            ("https://example.com/a;", &["https://example.com/a"]),
+            ("https://example.com/a...", &["https://example.com/a"]),
+            ("https://example.com/a…", &["https://example.com/a"]),
+            ("*https://example.com/a*", &["https://example.com/a"]),
+            ("https://example.com/a/.", &["https://example.com/a/"]),
+            (
+                "https://example.com/?q=a.b",
+                &["https://example.com/?q=a.b"],
+            ),
+            // Apostrophes inside URLs are kept
+            ("https://example.com/it's", &["https://example.com/it's"]),
+            // IRC formatting
+            ("\x02https://example.com/a\x02", &["https://example.com/a"]),
+            (
+                "\x0304https://example.com/a\x03",
+                &["https://example.com/a"],
+            ),
+            (
+                "\x0304,12https://example.com/a\x0f",
+                &["https://example.com/a"],
+            ),
+            ("\x1fhttps://example.com/a\x1f", &["https://example.com/a"]),
+            ("\x1dhttps://example.com/a\x1d", &["https://example.com/a"]),
+            (
+                "\x04ff0000https://example.com/a\x04",
+                &["https://example.com/a"],
+            ),
+            (
+                "https://example.com/\x02a\x02b",
+                &["https://example.com/ab"],
+            ),
+            // Scheme-less links
+            ("www.example.com", &["https://www.example.com/"]),
+            ("www.example.com/a?b=c", &["https://www.example.com/a?b=c"]),
+            ("(www.example.com)", &["https://www.example.com/"]),
+            ("WWW.Example.com.", &["https://www.example.com/"]),
+            ("youtu.be/dQw4w9WgXcQ", &["https://youtu.be/dQw4w9WgXcQ"]),
+            (
+                "watch youtu.be/dQw4w9WgXcQ!",
+                &["https://youtu.be/dQw4w9WgXcQ"],
+            ),
+            // Internationalized domain names and paths
+            ("https://bücher.de/", &["https://xn--bcher-kva.de/"]),
+            ("www.bücher.de", &["https://www.xn--bcher-kva.de/"]),
+            ("https://例え.jp/", &["https://xn--r8jz45g.jp/"]),
+            (
+                "https://example.com/ünïcode",
+                &["https://example.com/%C3%BCn%C3%AFcode"],
+            ),
+            ("(https://bücher.de)", &["https://xn--bcher-kva.de/"]),
+            // URLs within URLs
+            (
+                "https://web.archive.org/web/2020/https://example.com/",
+                &["https://web.archive.org/web/2020/https://example.com/"],
+            ),
+        ];
+
+        for (text, expected) in corpus {
+            assert_eq!(extract_strs(text), *expected, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn it_should_ignore_non_urls() {
+        let corpus = [
+            "",
+            "hello world",
+            "ftp://example.com/some/file.zip",
+            "mailto:someone@example.com",
+            "http://",
+            "https://",
+            "https:// example.com",
+            "www.",
+            "www.com",
+            "swww.example.com",
+            "foo.www.example.com",
+            "xhttps://example.com",
+            "youtu.be",
+            "notyoutu.be/abc",
+            "www..example.com",
+        ];
+
+        for text in corpus {
+            assert_eq!(extract_strs(text), Vec::<String>::new(), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn it_should_strip_formatting() {
+        assert_eq!(strip_formatting("plain"), "plain");
+        assert_eq!(strip_formatting("\x02bold\x02 text"), "bold text");
+        assert_eq!(strip_formatting("\x0310cyan\x03 text"), "cyan text");
+        assert_eq!(strip_formatting("\x033,4colors\x0f"), "colors");
+        assert_eq!(strip_formatting("\x0312,text"), ",text");
+        assert_eq!(strip_formatting("\x03123"), "3");
+        assert_eq!(strip_formatting("\x04FF00FF,00ff00hex"), "hex");
+        assert_eq!(
+            strip_formatting("\x11mono\x16rev\x1estrike"),
+            "monorevstrike"
+        );
+    }
+}
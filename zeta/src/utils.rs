@@ -25,10 +25,43 @@ impl Truncatable for str {
     }
 }
 
+/// Formats how long ago something happened in a human-readable way, e.g. `3 days ago`.
+pub fn format_age(age: time::Duration) -> String {
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+    const MONTH: i64 = 30 * DAY;
+    const YEAR: i64 = 365 * DAY;
+
+    let seconds = age.whole_seconds();
+    let (value, unit) = match seconds {
+        ..MINUTE => return "just now".to_string(),
+        MINUTE..HOUR => (seconds / MINUTE, "minute"),
+        HOUR..DAY => (seconds / HOUR, "hour"),
+        DAY..MONTH => (seconds / DAY, "day"),
+        MONTH..YEAR => (seconds / MONTH, "month"),
+        _ => (seconds / YEAR, "year"),
+    };
+    let plural = if value == 1 { "" } else { "s" };
+
+    format!("{value} {unit}{plural} ago")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn format_age_in_largest_unit() {
+        assert_eq!(format_age(time::Duration::seconds(-5)), "just now");
+        assert_eq!(format_age(time::Duration::seconds(59)), "just now");
+        assert_eq!(format_age(time::Duration::minutes(1)), "1 minute ago");
+        assert_eq!(format_age(time::Duration::minutes(90)), "1 hour ago");
+        assert_eq!(format_age(time::Duration::days(3)), "3 days ago");
+        assert_eq!(format_age(time::Duration::days(65)), "2 months ago");
+        assert_eq!(format_age(time::Duration::days(800)), "2 years ago");
+    }
+
     #[test]
     fn truncate_string_with_suffix() {
         let string: String = "this is a very long string".to_string();
@@ -1,15 +1,33 @@
 //! The main process for communicating over IRC and managing state.
+use std::collections::HashSet;
+use std::future;
+use std::pin::pin;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use futures::stream::StreamExt;
-use irc::client::prelude::Client;
-use irc::proto::Message;
-use tracing::{debug, warn};
+use irc::client::ClientStream;
+use irc::client::prelude::ChannelExt;
+use irc::proto::{Capability, Command, Message, Response};
+use opentelemetry::trace::Status;
+use tokio::net::TcpListener;
+use tracing::{Instrument, Span, debug, field, info, info_span, warn};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use url::Url;
+use zeta_plugin::Client;
 
 use crate::Error;
 use crate::Registry;
+use crate::command;
 use crate::config::Config;
-use crate::plugin::Context;
+use crate::consts::PLUGIN_TICK_INTERVAL;
+use crate::ctcp::{self, Ctcp};
+use crate::logger::ChannelLogger;
+use crate::message::MessageExt;
+use crate::plugin::{self, Context};
+use crate::rejoin::Rejoiner;
+use crate::services::Services;
+use crate::{http, metrics, server};
 
 /// The main IRC bot struct that manages connection state and message handling.
 pub struct Zeta {
@@ -21,6 +39,16 @@ pub struct Zeta {
     registry: Registry,
     /// The shared context for plugins
     context: Arc<Context>,
+    /// The channel logger, if channel logging is enabled
+    logger: Option<ChannelLogger>,
+    /// State shared with the HTTP server
+    server: Arc<server::State>,
+    /// The CTCP responder, if CTCP queries are answered
+    ctcp: Option<ctcp::Responder>,
+    /// Registration with the network and its services
+    services: Services,
+    /// Rejoins channels and handles invites
+    rejoiner: Rejoiner,
 }
 
 impl Zeta {
@@ -34,6 +62,13 @@ impl Zeta {
         #[cfg(feature = "database")] db: crate::database::Database,
         dns: hickory_resolver::TokioResolver,
     ) -> Self {
+        // Plugins build their HTTP clients when they're registered
+        http::init(&config.http);
+
+        if zeta_plugin::reply::set_theme(config.theme.clone()).is_err() {
+            warn!("reply theme is already set, ignoring configuration");
+        }
+
         let context = Arc::new(Context::new(
             #[cfg(feature = "database")]
             db,
@@ -41,12 +76,28 @@ impl Zeta {
             config.clone(),
         ));
         let registry = Registry::preloaded(&context);
+        let server = Arc::new(server::State::new(Arc::clone(&context), &registry));
+        let logger = config
+            .logger
+            .enabled
+            .then(|| ChannelLogger::new(config.logger.clone()));
+        let ctcp = config
+            .ctcp
+            .enabled
+            .then(|| ctcp::Responder::new(&config.ctcp));
+        let services = Services::new(config.irc.clone());
+        let rejoiner = Rejoiner::new(config.irc.clone());
 
         Zeta {
             client: None,
             registry,
             config,
             context,
+            logger,
+            server,
+            ctcp,
+            services,
+            rejoiner,
         }
     }
 
@@ -56,6 +107,7 @@ impl Zeta {
     ///
     /// This function will return an error in the following situations:
     ///
+    /// - [`Error::BindServer`] - if the HTTP server is enabled and can't listen on its address.
     /// - [`Error::IrcClient`] - if the instantiation of the IRC client fails (e.g. due to
     ///   configuration issues.)
     /// - [`Error::IrcRegistration`] - if user registration fails (e.g. if the nickname is already taken.)
@@ -63,19 +115,102 @@ impl Zeta {
     ///
     /// Plugin errors are logged but not propagated — one failing plugin won't block others.
     pub async fn run(&mut self) -> Result<(), Error> {
-        let mut client = Client::from_config(self.config.irc.clone().into())
+        if self.config.server.enabled {
+            let address = self.config.server.address;
+            let listener = TcpListener::bind(address)
+                .await
+                .map_err(Error::BindServer)?;
+
+            info!(%address, "http server listening");
+            tokio::spawn(server::serve(listener, Arc::clone(&self.server)));
+        }
+
+        let mut client = irc::client::Client::from_config(self.config.irc.clone().into())
             .await
             .map_err(Error::IrcClient)?;
 
+        metrics::global().set_irc_connected(true);
+
+        // Request server-time so channel logs use the time the server saw a message,
+        // account-tag so user preferences can be keyed by services account, echo-message so the
+        // messages we send are logged as the server saw them, and the rest so the channel state has every prefix,
+        // hostmask and account of the members. Each capability is requested separately since the
+        // server rejects the whole request if it doesn't support one of them.
+        for capability in [
+            Capability::ServerTime,
+            Capability::AccountTag,
+            Capability::EchoMessage,
+            Capability::MultiPrefix,
+            Capability::ExtendedJoin,
+            Capability::AccountNotify,
+            Capability::UserhostInNames,
+        ] {
+            client
+                .send_cap_req(&[capability])
+                .map_err(Error::IrcRegistration)?;
+        }
+
         client.identify().map_err(Error::IrcRegistration)?;
 
         let mut stream = client.stream()?;
 
-        self.client = Some(client);
+        self.client = Some(Client::new(client, record_sent));
 
-        if let Some(client) = &self.client {
-            while let Some(message) = stream.next().await.transpose()? {
-                self.handle_message(client, message).await?;
+        let result = self.process_messages(&mut stream).await;
+
+        metrics::global().set_irc_connected(false);
+        // We're no longer in any channels
+        self.context.state.write().unwrap().clear();
+
+        result
+    }
+
+    /// Processes messages from `stream` until the connection is closed.
+    ///
+    /// Whether the primary nickname is available is checked periodically in between messages, and
+    /// the channels that require identification are joined once the identification timeout
+    /// passes. Plugins are sent a tick every [`PLUGIN_TICK_INTERVAL`].
+    async fn process_messages(&self, stream: &mut ClientStream) -> Result<(), Error> {
+        let Some(client) = &self.client else {
+            return Ok(());
+        };
+        // A zero period would make the interval panic
+        let period = self
+            .config
+            .irc
+            .nick_recovery
+            .interval
+            .max(Duration::from_secs(1));
+        let mut recovery = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        let mut ticks = tokio::time::interval_at(
+            tokio::time::Instant::now() + PLUGIN_TICK_INTERVAL,
+            PLUGIN_TICK_INTERVAL,
+        );
+
+        loop {
+            let deadline = self.services.identification_deadline();
+
+            tokio::select! {
+                message = stream.next() => {
+                    let Some(message) = message.transpose()? else {
+                        break;
+                    };
+
+                    self.handle_message(client, message).await?;
+                }
+                _ = recovery.tick() => {
+                    self.services.check_primary(
+                        client,
+                        &self.context.nickname(),
+                        self.context.casemapping(),
+                    )?;
+                }
+                () = sleep_until(deadline) => {
+                    self.services.check_identification(client)?;
+                }
+                _ = ticks.tick() => {
+                    self.tick(client).await;
+                }
             }
         }
 
@@ -88,6 +223,13 @@ impl Zeta {
     /// to each plugin in the registry for processing. Plugins can respond to
     /// messages, update state, or perform other actions as needed.
     ///
+    /// URLs in `PRIVMSG`s are then extracted, and each URL is handed to the first plugin that
+    /// claims it.
+    ///
+    /// CTCP messages aren't passed to plugins as messages: actions are passed to
+    /// [`Plugin::handle_action`](crate::Plugin::handle_action) instead, and queries such as
+    /// `VERSION` are answered directly.
+    ///
     /// If a plugin fails to handle a message, the error is logged but processing
     /// continues for remaining plugins. This prevents one misbehaving plugin from
     /// blocking all others.
@@ -101,12 +243,280 @@ impl Zeta {
     async fn handle_message(&self, client: &Client, message: Message) -> Result<(), Error> {
         debug!(?message, "processing irc message");
 
-        for (plugin_name, plugin) in &self.registry.plugins {
-            if let Err(e) = plugin.handle_message(&self.context, client, &message).await {
-                warn!(plugin = %plugin_name, error = %e, "plugin error during message handling");
+        // Quits and nickname changes are logged to the channels the user was in before them
+        let channels = match message.command {
+            Command::QUIT(..) | Command::NICK(..) if self.logger.is_some() => {
+                self.context.channels()
+            }
+            _ => vec![],
+        };
+
+        {
+            let isupport = self.context.isupport.read().unwrap();
+
+            self.context.state.write().unwrap().update(
+                &message,
+                &self.context.nickname(),
+                &isupport,
+            );
+        }
+
+        match message.command {
+            Command::Response(Response::RPL_WELCOME, ref args) => {
+                metrics::global().set_irc_registered(true);
+
+                // The server tells us which nickname we ended up with
+                if let Some(nickname) = args.first() {
+                    self.context.nickname.write().unwrap().clone_from(nickname);
+                }
+            }
+            Command::NICK(ref nickname) if self.is_from_self(&message) => {
+                self.context.nickname.write().unwrap().clone_from(nickname);
+            }
+            Command::Response(Response::RPL_ISUPPORT, ref args) => {
+                self.context.isupport.write().unwrap().update(args);
+            }
+            Command::JOIN(ref channels, ..) if self.is_from_self(&message) => {
+                for channel in channels.split(',') {
+                    // The server sends the names of the members when we join, but not their
+                    // hostmasks
+                    client.send(Command::WHO(Some(channel.to_string()), None))?;
+                }
+            }
+            _ => {}
+        }
+
+        {
+            let isupport = self.context.isupport.read().unwrap();
+
+            self.services
+                .handle(client, &message, &self.context.nickname(), &isupport)?;
+        }
+
+        self.rejoiner
+            .handle(client, &self.context, &message)
+            .await?;
+
+        if let Some(logger) = &self.logger {
+            logger
+                .log(&message, &channels, self.context.casemapping())
+                .await;
+        }
+
+        // Messages we sent ourselves are echoed back by servers that support echo-message, and
+        // should only be logged.
+        if self.is_echo(&message) {
+            return Ok(());
+        }
+
+        let channel = message
+            .response_target()
+            .filter(|target| target.is_channel_name())
+            .unwrap_or_default();
+        let nick = message.source_nickname().unwrap_or_default();
+        // The command field is recorded by `run_plugin` when a plugin command matches
+        let span = info_span!("message", channel, nick, command = field::Empty);
+
+        self.dispatch(client, &message).instrument(span).await
+    }
+
+    /// Passes `message` to the plugins, and answers CTCP queries.
+    async fn dispatch(&self, client: &Client, message: &Message) -> Result<(), Error> {
+        match ctcp::from_message(message) {
+            Some(Ctcp::Action(text)) => {
+                for (plugin_name, plugin) in self.plugins_for(message) {
+                    run_plugin(
+                        plugin_name,
+                        None,
+                        plugin.handle_action(&self.context, client, message, text),
+                    )
+                    .await;
+                }
+
+                self.dispatch_urls(client, message, text).await;
+            }
+            Some(ref query) => {
+                if let Some(responder) = &self.ctcp {
+                    debug!(?query, "replying to ctcp query");
+                    responder.respond(client, message, query)?;
+                }
+            }
+            None => {
+                for (plugin_name, plugin) in self.plugins_for(message) {
+                    run_plugin(
+                        plugin_name,
+                        None,
+                        plugin.handle_message(&self.context, client, message),
+                    )
+                    .await;
+                }
+
+                if let Command::PRIVMSG(_, ref text) = message.command {
+                    self.dispatch_urls(client, message, text).await;
+                }
             }
         }
 
         Ok(())
     }
+
+    /// Sends a tick to every plugin.
+    async fn tick(&self, client: &Client) {
+        for (plugin_name, plugin) in &self.registry.plugins {
+            run_plugin(plugin_name, None, plugin.handle_tick(&self.context, client)).await;
+        }
+    }
+
+    /// Hands each URL in `text` to the first plugin that claims it.
+    async fn dispatch_urls(&self, client: &Client, message: &Message, text: &str) {
+        for url in self.message_urls(text) {
+            let Some((plugin_name, plugin)) = self
+                .plugins_for(message)
+                .find(|(_, plugin)| plugin.claims_url(&url))
+            else {
+                continue;
+            };
+            let span = plugin_span(plugin_name);
+
+            span.record("url", url.as_str());
+            run_plugin(
+                plugin_name,
+                Some(span),
+                plugin.handle_url(&self.context, client, message, &url),
+            )
+            .await;
+        }
+    }
+
+    /// Returns the plugins that `message` should be passed to.
+    ///
+    /// Private messages are only passed to plugins that accept them.
+    fn plugins_for(
+        &self,
+        message: &Message,
+    ) -> impl Iterator<Item = &(String, Box<dyn plugin::Plugin<Context>>)> {
+        let is_private = message.is_private();
+
+        self.registry
+            .plugins
+            .iter()
+            .filter(move |(_, plugin)| !is_private || plugin.accepts_private_messages())
+    }
+
+    /// Returns the unique URLs in `text`, up to the configured maximum per message.
+    fn message_urls(&self, text: &str) -> Vec<Url> {
+        let mut seen = HashSet::new();
+
+        plugin::extract_urls(text)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|url| seen.insert(url.clone()))
+            .take(self.config.plugins.urls.max_per_message)
+            .collect()
+    }
+
+    /// Returns whether `message` is a message we sent that was echoed back to us.
+    fn is_echo(&self, message: &Message) -> bool {
+        matches!(message.command, Command::PRIVMSG(..) | Command::NOTICE(..))
+            && self.is_from_self(message)
+    }
+
+    /// Returns whether `message` was sent by us.
+    fn is_from_self(&self, message: &Message) -> bool {
+        message
+            .source_nickname()
+            .is_some_and(|nick| self.is_self(nick))
+    }
+
+    /// Returns whether `nick` is our current nickname.
+    fn is_self(&self, nick: &str) -> bool {
+        let casemapping = self.context.casemapping();
+
+        casemapping.fold(nick) == casemapping.fold(&self.context.nickname())
+    }
+}
+
+/// Returns the span for a plugin that handles a message.
+fn plugin_span(plugin_name: &str) -> Span {
+    info_span!(
+        "plugin",
+        plugin = %plugin_name,
+        command = field::Empty,
+        url = field::Empty,
+        outcome = field::Empty,
+    )
+}
+
+/// Runs a plugin `handler`, and records its outcome.
+///
+/// Plugins are passed every message, so a plugin only gets its own `span` once it's known to
+/// handle the message: either up front, when it's handed a URL, or as soon as it matches a
+/// command or reports that it acts on the message with [`command::invoked`]. The command is then
+/// also recorded on the span of the message. The plugin metrics are likewise only recorded for
+/// plugins that handled the message, or failed.
+///
+/// Plugin errors are logged rather than returned, so one failing plugin won't block others.
+async fn run_plugin(
+    plugin_name: &str,
+    mut span: Option<Span>,
+    handler: impl Future<Output = Result<(), plugin::Error>>,
+) {
+    let started = Instant::now();
+    let mut handler = pin!(handler);
+    let result = future::poll_fn(|cx| {
+        let (poll, command) = match span {
+            Some(ref span) => (span.in_scope(|| handler.as_mut().poll(cx)), None),
+            None => command::matched(|| handler.as_mut().poll(cx)),
+        };
+
+        if let Some(command) = command {
+            let plugin_span = plugin_span(plugin_name);
+
+            Span::current().record("command", command);
+            plugin_span.record("command", command);
+            span = Some(plugin_span);
+        }
+
+        poll
+    })
+    .await;
+
+    if span.is_some() || result.is_err() {
+        metrics::global().record_plugin(plugin_name, started.elapsed(), result.is_err());
+    }
+
+    let Some(span) = span else {
+        if let Err(e) = result {
+            warn!(plugin = %plugin_name, error = %e, "plugin error during message handling");
+        }
+
+        return;
+    };
+
+    match result {
+        Ok(()) => {
+            span.record("outcome", "ok");
+        }
+        Err(e) => {
+            span.record("outcome", "error");
+            span.set_status(Status::error(e.to_string()));
+            span.in_scope(|| warn!(error = %e, "plugin error during message handling"));
+        }
+    }
+}
+
+/// Sleeps until `deadline`, or forever if there isn't one.
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+        None => future::pending().await,
+    }
+}
+
+/// Records that `message` is being sent to the server.
+fn record_sent(message: &Message) {
+    let line = String::from(&message.command);
+    let command = line.split(' ').next().unwrap_or_default();
+
+    metrics::global().record_message_sent(command);
 }